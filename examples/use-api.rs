@@ -0,0 +1,15 @@
+use pifu::{build, Arch, BuildOptions, Config, PlatformTarget};
+
+fn main() {
+    let conf = Config::from_file("examples/pifu.toml").unwrap();
+    let options = BuildOptions::builder()
+        .targets(&[PlatformTarget::Deb])
+        .arches(&[Arch::X86_64])
+        .build();
+    conf.validate("examples/pifu.toml", &options).unwrap();
+
+    let artifacts = build(&conf, &options).unwrap();
+    for artifact in &artifacts {
+        println!("{} {}: {:?}", artifact.target, artifact.arch, artifact.path);
+    }
+}
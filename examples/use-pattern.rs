@@ -1,6 +1,6 @@
 use regex::Regex;
 
-use pifu::base::expand_file_macro_simple;
+use pifu::expand_file_macro_simple;
 
 fn main() {
     let s = "app-${env.HOME}.deb";
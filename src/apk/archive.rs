@@ -0,0 +1,163 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Apk package is concatenation of gzip streams of signature, control and
+//! data tar archives. Signature and control archives have no end-of-archive
+//! blocks, so that they are merged with data archive by `tar`.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::Write;
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::base::hash::sha1_hex;
+use crate::error::Error;
+
+/// Size of end-of-archive blocks appended by `tar::Builder`.
+const END_OF_ARCHIVE_LEN: usize = 1024;
+
+const PAX_CHECKSUM_KEY: &str = "APK-TOOLS.checksum.SHA1";
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn new_header(
+    path: &str,
+    kind: tar::EntryType,
+    size: u64,
+    mode: u32,
+) -> Result<tar::Header, Error> {
+    let mut header = tar::Header::new_ustar();
+    header.set_path(path)?;
+    header.set_entry_type(kind);
+    header.set_size(size);
+    header.set_mode(mode);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_username("root")?;
+    header.set_groupname("root")?;
+    Ok(header)
+}
+
+/// Build a tar archive with one regular file, without end-of-archive blocks.
+fn single_file_archive(name: &str, content: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = new_header(name, tar::EntryType::Regular, content.len() as u64, 0o644)?;
+    header.set_mtime(chrono::Utc::now().timestamp().unsigned_abs());
+    header.set_cksum();
+    builder.append(&header, content)?;
+    let mut tar = builder.into_inner()?;
+    tar.truncate(tar.len() - END_OF_ARCHIVE_LEN);
+    gzip(&tar)
+}
+
+/// Gzipped signature archive, containing `.SIGN.RSA256.<key_name>`.
+pub fn signature_archive(key_name: &str, signature: &[u8]) -> Result<Vec<u8>, Error> {
+    single_file_archive(&format!(".SIGN.RSA256.{}", key_name), signature)
+}
+
+/// Gzipped control archive, containing `.PKGINFO`.
+pub fn control_archive(pkginfo: &str) -> Result<Vec<u8>, Error> {
+    single_file_archive(".PKGINFO", pkginfo.as_bytes())
+}
+
+/// Append pax extended header with one `key=value` record.
+fn append_pax_record<W: Write>(
+    builder: &mut tar::Builder<W>,
+    key: &str,
+    value: &str,
+) -> Result<(), Error> {
+    // Length of record includes its own decimal digits.
+    let rest = format!(" {}={}\n", key, value);
+    let mut len = rest.len() + 1;
+    while format!("{}{}", len, rest).len() != len {
+        len += 1;
+    }
+    let record = format!("{}{}", len, rest);
+    let mut header = new_header(
+        "PaxHeader",
+        tar::EntryType::XHeader,
+        record.len() as u64,
+        0o644,
+    )?;
+    header.set_cksum();
+    builder.append(&header, record.as_bytes())?;
+    Ok(())
+}
+
+/// Gzipped data archive of files in `dir`, regular files and symbolic links
+/// carry sha1 checksum in pax header.
+///
+/// Returns the archive and installed size in bytes.
+pub fn data_archive(dir: &Path) -> Result<(Vec<u8>, u64), Error> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut installed_size = 0;
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        let name = path.strip_prefix(dir)?.to_string_lossy().to_string();
+        if name.is_empty() {
+            continue;
+        }
+        let metadata = fs::symlink_metadata(path)?;
+        let mtime = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+        #[cfg(not(target_os = "windows"))]
+        let mode = metadata.permissions().mode() & 0o7777;
+        #[cfg(target_os = "windows")]
+        let mode = if metadata.is_dir() { 0o755 } else { 0o644 };
+
+        let file_type = entry.file_type();
+        let (mut header, content) = if file_type.is_dir() {
+            (
+                new_header(&name, tar::EntryType::Directory, 0, mode)?,
+                Vec::new(),
+            )
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(path)?.to_string_lossy().to_string();
+            append_pax_record(&mut builder, PAX_CHECKSUM_KEY, &sha1_hex(target.as_bytes()))?;
+            let mut header = new_header(&name, tar::EntryType::Symlink, 0, 0o777)?;
+            header.set_link_name(&target)?;
+            (header, Vec::new())
+        } else {
+            let content = fs::read(path)?;
+            append_pax_record(&mut builder, PAX_CHECKSUM_KEY, &sha1_hex(&content))?;
+            installed_size += content.len() as u64;
+            let header = new_header(&name, tar::EntryType::Regular, content.len() as u64, mode)?;
+            (header, content)
+        };
+        header.set_mtime(mtime);
+        header.set_cksum();
+        builder.append(&header, content.as_slice())?;
+    }
+    let tar = builder.into_inner()?;
+    Ok((gzip(&tar)?, installed_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_control_archive() {
+        let archive = control_archive("pkgname = hello\n").unwrap();
+        let mut tar = Vec::new();
+        GzDecoder::new(archive.as_slice())
+            .read_to_end(&mut tar)
+            .unwrap();
+        // One header block and one content block, without end-of-archive blocks.
+        assert_eq!(tar.len(), 1024);
+        assert_eq!(&tar[..8], b".PKGINFO");
+        assert_eq!(&tar[512..528], b"pkgname = hello\n");
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::archive::{control_archive, data_archive, signature_archive};
+use super::pkginfo::{generate_pkginfo, validate_apk_version};
+use crate::appstream::install_metainfo;
+use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, LinuxConfig};
+use crate::desktop::install_desktop;
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+use crate::sign::openssl_sign;
+
+/// Packager of Alpine `.apk` format, `openssl` command is required to sign package.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ApkPackager;
+
+impl Packager for ApkPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Apk
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_apk(conf, linux_conf, arch, &runner)
+    }
+}
+
+pub fn build_apk(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let apk_conf = &linux_conf.apk;
+    validate_apk_version(&conf.metadata.version).map_err(|message| {
+        Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Invalid config: `metadata.version` {}", message),
+        )
+    })?;
+
+    let files = if let Some(files) = apk_conf.files.as_ref() {
+        files
+    } else if let Some(files) = linux_conf.files.as_ref() {
+        files
+    } else {
+        return Err(Error::new(
+            ErrorKind::FilesNotSet,
+            "`files` property not set for apk format",
+        ));
+    };
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    let apk_dir = workdir.join("apk");
+    utils::rmdir(&apk_dir)?;
+    let data_dir = apk_dir.join("data");
+    fs::create_dir_all(&data_dir)?;
+
+    copy_filesets(files, conf, arch, PlatformTarget::Apk, &data_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::Apk,
+            &data_dir,
+            false,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &data_dir)?;
+    }
+
+    let (data, installed_size) = data_archive(&data_dir)?;
+    let datahash = hex::encode(Sha256::digest(&data));
+    let pkginfo = generate_pkginfo(conf, apk_conf, arch, installed_size, &datahash)?;
+    fs::write(apk_dir.join(".PKGINFO"), &pkginfo)?;
+    let control = control_archive(&pkginfo)?;
+
+    let mut package = Vec::new();
+    if apk_conf.sign {
+        package.extend(sign_control(conf, &apk_dir, &control, runner)?);
+    }
+    package.extend(control);
+    package.extend(data);
+
+    let artifact_name =
+        expand_file_macro(&apk_conf.artifact_name, conf, arch, PlatformTarget::Apk)?;
+    let artifact = workdir.join(artifact_name);
+    fs::write(&artifact, package)?;
+    Ok(vec![artifact])
+}
+
+/// Sign gzipped control archive with abuild key, returns gzipped signature archive.
+fn sign_control(
+    conf: &Config,
+    apk_dir: &Path,
+    control: &[u8],
+    runner: &Runner,
+) -> Result<Vec<u8>, Error> {
+    let sign_conf = conf.sign_conf()?;
+    let key = sign_conf
+        .abuild_key
+        .as_ref()
+        .map(Path::new)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::SignError,
+                "`sign.abuild_key` is required to sign apk package",
+            )
+        })?;
+    let key_name = key
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::SignError, "Invalid abuild key").with_path(key))?;
+
+    let control_file = apk_dir.join("control.tar.gz");
+    fs::write(&control_file, control)?;
+    let signature = openssl_sign(sign_conf, key, &control_file, runner)?;
+    signature_archive(&format!("{}.pub", key_name.to_string_lossy()), &signature)
+}
@@ -0,0 +1,50 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::base::fileset::FileSet;
+use crate::base::utils::default_false;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApkConfig {
+    /// File list.
+    pub files: Option<Vec<FileSet>>,
+
+    /// Integer - Package release number, `pkgver` is `<version>-r<pkgrel>`.
+    #[serde(default)]
+    pub pkgrel: u32,
+
+    /// Array of String - Required packages, like `["musl", "so:libz.so.1"]`.
+    #[serde(default)]
+    pub depends: Vec<String>,
+
+    /// Boolean - whether to sign package with `abuild_key` in `[sign]` section.
+    #[serde(default = "default_false")]
+    pub sign: bool,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+impl Default for ApkConfig {
+    fn default() -> Self {
+        Self {
+            files: None,
+            pkgrel: 0,
+            depends: Vec::new(),
+            sign: false,
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
@@ -0,0 +1,102 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use regex::Regex;
+use std::fmt::Write;
+
+use super::config::ApkConfig;
+use crate::base::Arch;
+use crate::config::Config;
+use crate::error::Error;
+
+/// Get architecture name used by Alpine.
+#[must_use]
+pub const fn apk_arch(arch: Arch) -> &'static str {
+    match arch {
+        Arch::X86 => "x86",
+        Arch::X86_64 => "x86_64",
+        Arch::AArch64 => "aarch64",
+        Arch::Mips64 => "mips64",
+    }
+}
+
+/// Apk version is like `1.2.3`, `1.2.3a` or `1.2.3_rc1`, release is appended by pifu.
+pub fn validate_apk_version(version: &str) -> Result<(), String> {
+    let pattern =
+        Regex::new(r"^[0-9]+(\.[0-9]+)*[a-z]?(_(alpha|beta|pre|rc|cvs|svn|git|hg|p)[0-9]*)*$")
+            .map_err(|err| err.to_string())?;
+    if pattern.is_match(version) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} is invalid for apk package, it shall be like `1.2.3` or `1.2.3_rc1`",
+            version
+        ))
+    }
+}
+
+/// Values of `.PKGINFO` are single line.
+fn single_line(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Generate `.PKGINFO` of control archive.
+///
+/// `size` is installed size in bytes, and `datahash` is sha256 of data archive.
+pub fn generate_pkginfo(
+    conf: &Config,
+    apk_conf: &ApkConfig,
+    arch: Arch,
+    size: u64,
+    datahash: &str,
+) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let mut info = String::new();
+    writeln!(info, "# Generated by pifu")?;
+    writeln!(info, "pkgname = {}", metadata.name)?;
+    writeln!(info, "pkgver = {}-r{}", metadata.version, apk_conf.pkgrel)?;
+    writeln!(info, "pkgdesc = {}", single_line(&metadata.description))?;
+    writeln!(info, "url = {}", metadata.homepage)?;
+    writeln!(info, "builddate = {}", chrono::Utc::now().timestamp())?;
+    writeln!(info, "packager = {}", single_line(&metadata.author))?;
+    writeln!(info, "size = {}", size)?;
+    writeln!(info, "arch = {}", apk_arch(arch))?;
+    writeln!(info, "origin = {}", metadata.name)?;
+    writeln!(info, "license = {}", metadata.license)?;
+    for depend in &apk_conf.depends {
+        writeln!(info, "depend = {}", depend)?;
+    }
+    writeln!(info, "datahash = {}", datahash)?;
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_validate_apk_version() {
+        assert!(validate_apk_version("1.0.0").is_ok());
+        assert!(validate_apk_version("2.1a_rc1").is_ok());
+        assert!(validate_apk_version("1.0.0-1").is_err());
+        assert!(validate_apk_version("v1.0").is_err());
+    }
+
+    #[test]
+    fn test_generate_pkginfo() {
+        let conf = Config::new(Metadata::new("hello", "1.0.0"));
+        let apk_conf = ApkConfig {
+            pkgrel: 2,
+            depends: vec!["musl".to_string()],
+            ..ApkConfig::default()
+        };
+        let info = generate_pkginfo(&conf, &apk_conf, Arch::AArch64, 1024, "abcd").unwrap();
+        assert!(info.contains("\npkgver = 1.0.0-r2\n"));
+        assert!(info.contains("\nsize = 1024\n"));
+        assert!(info.contains("\narch = aarch64\n"));
+        assert!(info.contains("\ndepend = musl\n"));
+        assert!(info.ends_with("\ndatahash = abcd\n"));
+    }
+}
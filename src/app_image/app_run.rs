@@ -0,0 +1,115 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::config::AppImageConfig;
+use crate::config::Config;
+use crate::error::{Error, ErrorKind};
+
+/// Returns true if `name` is a valid shell variable name.
+#[must_use]
+pub fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quote `s` in single quotes, no expansion is done by shell.
+fn single_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote `s` in double quotes, shell variables are expanded.
+fn double_quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', r"\\")
+            .replace('"', "\\\"")
+            .replace('`', "\\`")
+    )
+}
+
+/// Generate `AppRun` script which sets environment and runs `entry`.
+///
+/// `framework_env` is set by bundled frameworks, which can be overridden in `env`.
+pub fn generate_app_run(
+    conf: &Config,
+    app_image_conf: &AppImageConfig,
+    framework_env: &BTreeMap<String, String>,
+) -> Result<String, Error> {
+    if let Some(name) = app_image_conf
+        .env
+        .keys()
+        .find(|name| !is_valid_env_name(name))
+    {
+        return Err(Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!(
+                "Invalid environment variable name {:?} in `linux.app_image.env`",
+                name
+            ),
+        ));
+    }
+    let entry = app_image_conf.entry(conf);
+
+    let mut script = String::new();
+    writeln!(script, "#!/bin/sh")?;
+    writeln!(script, "# Generated by pifu.")?;
+    writeln!(script)?;
+    // $APPDIR is set by AppImage runtime, but not when AppDir is run directly.
+    writeln!(
+        script,
+        r#"HERE="${{APPDIR:-$(dirname "$(readlink -f "${{0}}")")}}""#
+    )?;
+    if app_image_conf.embed_libs && !app_image_conf.patch_rpath {
+        writeln!(
+            script,
+            r#"export LD_LIBRARY_PATH="${{HERE}}/libs${{LD_LIBRARY_PATH:+:${{LD_LIBRARY_PATH}}}}""#
+        )?;
+    }
+    let mut env = framework_env.clone();
+    env.extend(app_image_conf.env.clone());
+    for (name, value) in &env {
+        writeln!(script, "export {}={}", name, double_quote(value))?;
+    }
+
+    write!(script, "exec \"${{HERE}}\"/{}", single_quote(entry))?;
+    for arg in &app_image_conf.args {
+        write!(script, " {}", single_quote(arg))?;
+    }
+    if app_image_conf.pass_args {
+        write!(script, r#" "$@""#)?;
+    }
+    writeln!(script)?;
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_generate_app_run() {
+        let conf = Config::new(Metadata::new("hello", "1.0.0"));
+        let mut app_image_conf = AppImageConfig::default();
+        app_image_conf.env.insert(
+            "QT_PLUGIN_PATH".to_string(),
+            "$HERE/libs/qt5/plugins".to_string(),
+        );
+        app_image_conf.args.push("--name=it's".to_string());
+        let script = generate_app_run(&conf, &app_image_conf, &BTreeMap::new()).unwrap();
+        assert!(script.contains("export QT_PLUGIN_PATH=\"$HERE/libs/qt5/plugins\"\n"));
+        assert!(script.ends_with("exec \"${HERE}\"/'hello' '--name=it'\\''s' \"$@\"\n"));
+
+        app_image_conf
+            .env
+            .insert("BAD-NAME".to_string(), String::new());
+        assert!(generate_app_run(&conf, &app_image_conf, &BTreeMap::new()).is_err());
+    }
+}
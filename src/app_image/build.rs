@@ -5,15 +5,54 @@
 use regex::Regex;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Command;
 
+use super::app_run::generate_app_run;
+use super::frameworks::bundle_frameworks;
+use super::rpath::patch_rpath;
+use super::signature::sign_app_image;
+use super::update::embed_update_information;
+use super::zsync::write_zsync;
+use crate::appstream::install_metainfo;
 use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
 use crate::base::utils;
-use crate::base::Arch;
-use crate::config::{get_binary_dir, Config, LinuxConfig};
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, LinuxConfig};
+use crate::desktop::install_desktop;
 use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
 
-pub fn build_app_image(conf: &Config, linux_conf: &LinuxConfig, arch: Arch) -> Result<(), Error> {
+/// Packager of `AppImage` format, `appimagetool` is required.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AppImagePackager;
+
+impl Packager for AppImagePackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::AppImage
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        let appimage_tool = options.tool_path(conf, "appimagetool", arch)?;
+        build_app_image(conf, linux_conf, arch, &appimage_tool, &runner)
+    }
+}
+
+pub fn build_app_image(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+    appimage_tool: &Path,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
     let app_image_conf = &linux_conf.app_image;
 
     let files = if let Some(files) = app_image_conf.files.as_ref() {
@@ -35,72 +74,136 @@ pub fn build_app_image(conf: &Config, linux_conf: &LinuxConfig, arch: Arch) -> R
     let libs_dir = app_image_dir.join("libs");
     fs::create_dir_all(&app_image_dir)?;
 
-    copy_filesets(files, &conf.metadata.src_dir, &app_image_dir)?;
+    copy_filesets(files, conf, arch, PlatformTarget::AppImage, &app_image_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::AppImage,
+            &app_image_dir,
+            true,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &app_image_dir)?;
+    }
 
+    let bundled = bundle_frameworks(conf, app_image_conf, &app_image_dir, &libs_dir, runner)?;
     if app_image_conf.embed_libs {
         fs::create_dir_all(&libs_dir)?;
-        copy_libraries(
-            &app_image_conf.exe_files,
-            &app_image_conf.exclude_libs,
-            &libs_dir,
-        )?;
+        let mut elf_files = app_image_conf
+            .exe_files
+            .iter()
+            .map(|exe_file| {
+                expand_file_macro(exe_file, conf, arch, PlatformTarget::AppImage).map(PathBuf::from)
+            })
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+        elf_files.extend(bundled.elf_files);
+        copy_libraries(&elf_files, &app_image_conf.exclude_libs, &libs_dir, runner)?;
+    }
+
+    if app_image_conf.patch_rpath {
+        patch_rpath(&app_image_dir, &libs_dir)?;
+    }
+
+    let app_run_file = app_image_dir.join("AppRun");
+    if app_run_file.exists() {
+        if !bundled.env.is_empty() {
+            log::warn!(
+                "AppRun is copied from files, set these variables in it: {:?}",
+                bundled.env
+            );
+        }
+    } else {
+        let script = generate_app_run(conf, app_image_conf, &bundled.env)?;
+        fs::write(&app_run_file, script)?;
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&app_run_file, fs::Permissions::from_mode(0o755))?;
+        }
     }
 
-    compile_app_image(workdir, &app_image_dir_name, arch)
+    let artifact_name = expand_file_macro(
+        &app_image_conf.artifact_name,
+        conf,
+        arch,
+        PlatformTarget::AppImage,
+    )?;
+    compile_app_image(
+        workdir,
+        &app_image_dir_name,
+        &artifact_name,
+        arch,
+        appimage_tool,
+        runner,
+    )?;
+
+    let artifact = workdir.join(&artifact_name);
+    let mut files = vec![artifact.clone()];
+    if let Some(update_information) = app_image_conf.update_information.as_ref() {
+        let update_information =
+            expand_file_macro(update_information, conf, arch, PlatformTarget::AppImage)?;
+        embed_update_information(&artifact, &update_information)?;
+    }
+    // Update information is covered by signature.
+    if app_image_conf.sign {
+        sign_app_image(&artifact, conf.sign_conf()?, runner)?;
+    }
+    // zsync file shall be generated after AppImage file is finalized.
+    if app_image_conf.update_information.is_some() {
+        files.push(write_zsync(&artifact, &artifact_name)?);
+    }
+    Ok(files)
 }
 
-fn copy_libraries(
-    exe_files: &[String],
+/// Copy libraries which `exe_files` depend on to `libs_dir`.
+///
+/// `ldd` resolves dependencies recursively.
+pub fn copy_libraries(
+    exe_files: &[PathBuf],
     exclude_libs: &[String],
     libs_dir: &Path,
+    runner: &Runner,
 ) -> Result<(), Error> {
     let pattern = Regex::new(r"\s+(.+)\s+=>\s+(\S+)\s+\(\S+\)")?;
     for exe_file in exe_files {
-        let output = Command::new("ldd").arg(exe_file).output()?;
-        let stdout = String::from_utf8(output.stdout)?;
+        let mut cmd = Command::new("ldd");
+        cmd.arg(exe_file);
+        let stdout = runner.run(&mut cmd, ErrorKind::IoError)?;
         for cap in pattern.captures_iter(&stdout) {
             // TODO(Shaohua): No need to create another string object.
-            if !exclude_libs.contains(&cap[1].to_string()) {
-                fs::copy(&cap[2], Path::join(libs_dir, &cap[1]))?;
+            let dest = libs_dir.join(&cap[1]);
+            if !exclude_libs.contains(&cap[1].to_string()) && !dest.exists() {
+                fs::copy(&cap[2], dest)?;
             }
         }
     }
     Ok(())
 }
 
-fn get_appimage_tool(arch: Arch) -> Result<PathBuf, Error> {
-    let mut binary_dir = get_binary_dir()?;
-    binary_dir.push(format!("appimagetool-{}.AppImage", arch));
-    Ok(binary_dir)
-}
-
-fn compile_app_image<P: AsRef<Path>>(workdir: &Path, dir: &P, arch: Arch) -> Result<(), Error> {
-    let appimage_tool = get_appimage_tool(arch)?;
+fn compile_app_image<P: AsRef<Path>>(
+    workdir: &Path,
+    dir: &P,
+    artifact_name: &str,
+    arch: Arch,
+    appimage_tool: &Path,
+    runner: &Runner,
+) -> Result<(), Error> {
     log::info!("Using appimagetool: {:?}", &appimage_tool);
-    let mut cmd = Command::new(appimage_tool.as_os_str());
-    if cfg!(not(debug_assertions)) {
-        cmd.stdout(Stdio::null()).stderr(Stdio::null());
+    if !appimage_tool.exists() {
+        return Err(Error::new(
+            ErrorKind::AppImageCompilerError,
+            "`appimagetool` not found, please install with `pifu --download` command",
+        )
+        .with_path(appimage_tool));
     }
-    let status = cmd
-        .env("ARCH", &arch.to_string())
+    let mut cmd = Command::new(appimage_tool.as_os_str());
+    cmd.env("ARCH", arch.to_string())
         .current_dir(workdir)
         .arg(dir.as_ref())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map_err(|err| {
-            Error::from_string(
-                ErrorKind::AppImageCompilerError,
-                format!("Failed to run `appimagetool` command, error: {:?}, please install with `pifu --download` command", err),
-            )
-        })?;
-
-    if status.success() {
-        Ok(())
-    } else {
-        Err(Error::from_string(
-            ErrorKind::AppImageCompilerError,
-            format!("`appimagetool` returns error, workdir: {:?}", workdir),
-        ))
-    }
+        .arg(artifact_name);
+    runner.run(&mut cmd, ErrorKind::AppImageCompilerError)?;
+    Ok(())
 }
@@ -2,12 +2,21 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use serde::{Deserialize, Serialize};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 
 use crate::base::fileset::FileSet;
-use crate::base::utils::default_true;
+use crate::base::utils::{default_false, default_true};
+use crate::base::{deserialize_named, NamedEnum};
+use crate::config::Config;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AppImageConfig {
     /// A list of elf executable files. If they are dynamically linked, dependent
     /// libraries will be copied.
@@ -22,9 +31,135 @@ pub struct AppImageConfig {
     /// File list.
     pub files: Option<Vec<FileSet>>,
 
-    // TODO(Shaohua): Add artifact_name
     #[serde(default = "default_exclude_libs")]
     pub exclude_libs: Vec<String>,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+
+    /// String - Executable to run, relative to `AppDir`, default is package name.
+    ///
+    /// `AppRun` script is generated unless it is copied in `files`.
+    pub entry: Option<String>,
+
+    /// Environment variables set by generated `AppRun`, like
+    /// `{ QT_PLUGIN_PATH = "$HERE/libs/qt5/plugins" }`. `$HERE` refers to `AppDir`,
+    /// values are double quoted so other shell variables are also expanded.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Arguments passed to `entry` before command line arguments.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Boolean - whether to pass command line arguments to `entry`.
+    #[serde(default = "default_true")]
+    pub pass_args: bool,
+
+    /// String - Update information embedded in `AppImage`, macros are expanded.
+    ///
+    /// Like `gh-releases-zsync|user|repo|latest|${name}-*-${arch}.AppImage.zsync`
+    /// or `zsync|https://example.com/${name}-latest-${arch}.AppImage.zsync`.
+    /// If set, zsync control file is also generated next to the artifact.
+    pub update_information: Option<String>,
+
+    /// Boolean - whether to embed gpg signature, key is read from `[sign]` section.
+    #[serde(default = "default_false")]
+    pub sign: bool,
+
+    /// Plugins of frameworks to bundle, like `["qt5", "gtk3", "gstreamer"]`.
+    ///
+    /// Plugins and their dependent libraries are copied to `AppDir/libs`,
+    /// and environment variables are set in generated `AppRun`.
+    #[serde(default)]
+    pub bundle_frameworks: Vec<Framework>,
+
+    /// Plugin directories of frameworks, which are detected with `qmake` or
+    /// `pkg-config` if not set, like `{ qt5 = "/opt/qt5/plugins" }`.
+    ///
+    /// For `gtk3`, it is the library directory containing `gtk-3.0` and `gdk-pixbuf-2.0`.
+    #[serde(default)]
+    pub framework_paths: BTreeMap<String, String>,
+
+    /// Array of String - `GStreamer` plugins to bundle, like `["coreelements", "playback"]`
+    /// for `libgstcoreelements.so` and `libgstplayback.so`.
+    ///
+    /// Default is a list of core, playback and audio/video output plugins.
+    pub gstreamer_plugins: Option<Vec<String>>,
+
+    /// Boolean - whether to set `DT_RUNPATH` of ELF files in `AppDir` to `libs`
+    /// relative to `$ORIGIN`.
+    ///
+    /// If enabled, generated `AppRun` does not set `LD_LIBRARY_PATH`, which
+    /// leaks into child processes.
+    #[serde(default = "default_false")]
+    pub patch_rpath: bool,
+}
+
+impl AppImageConfig {
+    /// Get path of executable to run, relative to `AppDir`.
+    #[must_use]
+    pub fn entry<'a>(&'a self, conf: &'a Config) -> &'a str {
+        self.entry
+            .as_deref()
+            .unwrap_or(&conf.metadata.name)
+            .trim_start_matches('/')
+    }
+}
+
+/// Frameworks which load plugins at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Framework {
+    Qt5,
+    Qt6,
+    Gtk3,
+    Gstreamer,
+}
+
+impl NamedEnum for Framework {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Qt5, "qt5"),
+        (Self::Qt6, "qt6"),
+        (Self::Gtk3, "gtk3"),
+        (Self::Gstreamer, "gstreamer"),
+    ];
+}
+
+impl<'de> Deserialize<'de> for Framework {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_named(deserializer)
+    }
+}
+
+impl JsonSchema for Framework {
+    fn schema_name() -> String {
+        "Framework".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        Self::schema("Framework which loads plugins at runtime")
+    }
+}
+
+impl fmt::Display for Framework {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Framework {
+    /// Tool used to detect plugin directory.
+    #[must_use]
+    pub const fn detect_tool(self) -> &'static str {
+        match self {
+            Self::Qt5 => "qmake",
+            Self::Qt6 => "qmake6",
+            Self::Gtk3 | Self::Gstreamer => "pkg-config",
+        }
+    }
 }
 
 impl Default for AppImageConfig {
@@ -34,11 +169,22 @@ impl Default for AppImageConfig {
             embed_libs: true,
             files: None,
             exclude_libs: default_exclude_libs(),
+            artifact_name: default_artifact_name(),
+            entry: None,
+            env: BTreeMap::new(),
+            args: Vec::new(),
+            pass_args: true,
+            update_information: None,
+            sign: false,
+            bundle_frameworks: Vec::new(),
+            framework_paths: BTreeMap::new(),
+            gstreamer_plugins: None,
+            patch_rpath: false,
         }
     }
 }
 
-fn default_exclude_libs() -> Vec<String> {
+pub fn default_exclude_libs() -> Vec<String> {
     vec![
         "libc.so.6".to_string(),
         "libdl.so.2".to_string(),
@@ -46,3 +192,7 @@ fn default_exclude_libs() -> Vec<String> {
         "libpthread.so.0".to_string(),
     ]
 }
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
@@ -0,0 +1,321 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Minimal ELF reader and writer, to locate sections reserved by `AppImage`
+//! runtime like `.upd_info`, and to patch dynamic section of bundled files.
+
+use std::ops::Range;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const ELF_CLASS_32: u8 = 1;
+const ELF_CLASS_64: u8 = 2;
+const ELF_DATA_LSB: u8 = 1;
+
+/// Program header of a segment.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    /// File offset of this program header.
+    pub header: usize,
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub file_size: u64,
+    pub mem_size: u64,
+    pub align: u64,
+}
+
+/// Section header.
+#[derive(Debug, Clone)]
+pub struct Section {
+    /// File offset of this section header.
+    pub header: usize,
+    pub name: Vec<u8>,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Section {
+    #[must_use]
+    pub fn range(&self) -> Option<Range<usize>> {
+        let start = usize::try_from(self.offset).ok()?;
+        let end = start.checked_add(usize::try_from(self.size).ok()?)?;
+        Some(start..end)
+    }
+}
+
+/// Layout of ELF header, which is 32-bit or 64-bit, in little or big endian.
+#[derive(Debug, Clone, Copy)]
+pub struct Elf {
+    pub is_64: bool,
+    is_lsb: bool,
+    ph_offset: usize,
+    ph_entry_size: usize,
+    ph_num: usize,
+    sh_offset: usize,
+    sh_entry_size: usize,
+    sh_num: usize,
+    sh_str_index: usize,
+}
+
+impl Elf {
+    /// Parse ELF header, returns `None` if `data` is not an ELF file.
+    #[must_use]
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if !data.starts_with(ELF_MAGIC) {
+            return None;
+        }
+        let is_64 = match *data.get(4)? {
+            ELF_CLASS_32 => false,
+            ELF_CLASS_64 => true,
+            _ => return None,
+        };
+        let mut elf = Self {
+            is_64,
+            is_lsb: *data.get(5)? == ELF_DATA_LSB,
+            ph_offset: 0,
+            ph_entry_size: 0,
+            ph_num: 0,
+            sh_offset: 0,
+            sh_entry_size: 0,
+            sh_num: 0,
+            sh_str_index: 0,
+        };
+        let (ph_offset, sh_offset, sizes) = if is_64 {
+            (elf.addr(data, 0x20)?, elf.addr(data, 0x28)?, 0x36)
+        } else {
+            (elf.addr(data, 0x1c)?, elf.addr(data, 0x20)?, 0x2a)
+        };
+        elf.ph_offset = usize::try_from(ph_offset).ok()?;
+        elf.sh_offset = usize::try_from(sh_offset).ok()?;
+        elf.ph_entry_size = usize::from(elf.u16(data, sizes)?);
+        elf.ph_num = usize::from(elf.u16(data, sizes + 2)?);
+        elf.sh_entry_size = usize::from(elf.u16(data, sizes + 4)?);
+        elf.sh_num = usize::from(elf.u16(data, sizes + 6)?);
+        elf.sh_str_index = usize::from(elf.u16(data, sizes + 8)?);
+        Some(elf)
+    }
+
+    /// Size of address sized value, 8 in ELF64 and 4 in ELF32.
+    #[must_use]
+    pub const fn addr_size(&self) -> usize {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    fn read<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+        data.get(offset..offset.checked_add(N)?)?.try_into().ok()
+    }
+
+    #[must_use]
+    pub fn u16(&self, data: &[u8], offset: usize) -> Option<u16> {
+        let bytes = Self::read(data, offset)?;
+        Some(if self.is_lsb {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    #[must_use]
+    pub fn u32(&self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes = Self::read(data, offset)?;
+        Some(if self.is_lsb {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, data: &[u8], offset: usize) -> Option<u64> {
+        let bytes = Self::read(data, offset)?;
+        Some(if self.is_lsb {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        })
+    }
+
+    /// Read address sized value, which is `u32` in ELF32 and `u64` in ELF64.
+    #[must_use]
+    pub fn addr(&self, data: &[u8], offset: usize) -> Option<u64> {
+        if self.is_64 {
+            self.u64(data, offset)
+        } else {
+            self.u32(data, offset).map(u64::from)
+        }
+    }
+
+    pub fn set_u32(&self, data: &mut [u8], offset: usize, value: u32) -> Option<()> {
+        let bytes = if self.is_lsb {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        };
+        data.get_mut(offset..offset.checked_add(4)?)?
+            .copy_from_slice(&bytes);
+        Some(())
+    }
+
+    /// Write address sized value, returns `None` if it overflows in ELF32.
+    pub fn set_addr(&self, data: &mut [u8], offset: usize, value: u64) -> Option<()> {
+        if self.is_64 {
+            let bytes = if self.is_lsb {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            };
+            data.get_mut(offset..offset.checked_add(8)?)?
+                .copy_from_slice(&bytes);
+            Some(())
+        } else {
+            self.set_u32(data, offset, u32::try_from(value).ok()?)
+        }
+    }
+
+    /// Parse all program headers.
+    #[must_use]
+    pub fn segments(&self, data: &[u8]) -> Option<Vec<Segment>> {
+        (0..self.ph_num)
+            .map(|index| {
+                let header = self.ph_offset.checked_add(index * self.ph_entry_size)?;
+                let kind = self.u32(data, header)?;
+                if self.is_64 {
+                    Some(Segment {
+                        header,
+                        kind,
+                        flags: self.u32(data, header + 4)?,
+                        offset: self.addr(data, header + 8)?,
+                        vaddr: self.addr(data, header + 16)?,
+                        file_size: self.addr(data, header + 32)?,
+                        mem_size: self.addr(data, header + 40)?,
+                        align: self.addr(data, header + 48)?,
+                    })
+                } else {
+                    Some(Segment {
+                        header,
+                        kind,
+                        offset: self.addr(data, header + 4)?,
+                        vaddr: self.addr(data, header + 8)?,
+                        file_size: self.addr(data, header + 16)?,
+                        mem_size: self.addr(data, header + 20)?,
+                        flags: self.u32(data, header + 24)?,
+                        align: self.addr(data, header + 28)?,
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Write all fields of program header of `segment`.
+    pub fn set_segment(&self, data: &mut [u8], segment: &Segment) -> Option<()> {
+        let header = segment.header;
+        self.set_u32(data, header, segment.kind)?;
+        let (flags, fields) = if self.is_64 {
+            (4, [8, 16, 24, 32, 40, 48])
+        } else {
+            (24, [4, 8, 12, 16, 20, 28])
+        };
+        self.set_u32(data, header + flags, segment.flags)?;
+        // Physical address is same as virtual address.
+        let values = [
+            segment.offset,
+            segment.vaddr,
+            segment.vaddr,
+            segment.file_size,
+            segment.mem_size,
+            segment.align,
+        ];
+        for (field, value) in fields.iter().zip(values) {
+            self.set_addr(data, header + field, value)?;
+        }
+        Some(())
+    }
+
+    /// Parse all section headers, with their names.
+    #[must_use]
+    pub fn sections(&self, data: &[u8]) -> Option<Vec<Section>> {
+        if self.sh_num == 0 {
+            return Some(Vec::new());
+        }
+        let mut sections = (0..self.sh_num)
+            .map(|index| {
+                let header = self.sh_offset.checked_add(index * self.sh_entry_size)?;
+                let name = self.u32(data, header)?;
+                let fields = if self.is_64 {
+                    [16, 24, 32]
+                } else {
+                    [12, 16, 20]
+                };
+                let section = Section {
+                    header,
+                    name: Vec::new(),
+                    addr: self.addr(data, header + fields[0])?,
+                    offset: self.addr(data, header + fields[1])?,
+                    size: self.addr(data, header + fields[2])?,
+                };
+                Some((name, section))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let str_table = data.get(sections.get(self.sh_str_index)?.1.range()?)?;
+        for (name_offset, section) in &mut sections {
+            section.name = str_table
+                .get(usize::try_from(*name_offset).ok()?..)?
+                .split(|byte| *byte == 0)
+                .next()?
+                .to_vec();
+        }
+        Some(sections.into_iter().map(|(_, section)| section).collect())
+    }
+
+    /// Update address, file offset and size of `section`.
+    pub fn set_section(&self, data: &mut [u8], section: &Section) -> Option<()> {
+        let fields = if self.is_64 {
+            [16, 24, 32]
+        } else {
+            [12, 16, 20]
+        };
+        let values = [section.addr, section.offset, section.size];
+        for (field, value) in fields.iter().zip(values) {
+            self.set_addr(data, section.header + field, value)?;
+        }
+        Some(())
+    }
+}
+
+/// Find file range of section with `name` in ELF file `data`.
+///
+/// Returns `None` if `data` is not a valid ELF file or section not found.
+#[must_use]
+pub fn find_section(data: &[u8], name: &str) -> Option<Range<usize>> {
+    let elf = Elf::parse(data)?;
+    let range = elf
+        .sections(data)?
+        .into_iter()
+        .find(|section| section.name == name.as_bytes())?
+        .range()?;
+    (range.end <= data.len()).then_some(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_find_section() {
+        let exe = fs::read(env::current_exe().unwrap()).unwrap();
+        let text = find_section(&exe, ".text").unwrap();
+        assert!(text.start > 0 && text.end > text.start);
+        assert!(find_section(&exe, ".upd_info").is_none());
+        assert!(find_section(b"not an elf", ".text").is_none());
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use super::config::{AppImageConfig, Framework};
+use crate::base::runner::Runner;
+use crate::base::NamedEnum;
+use crate::config::Config;
+use crate::error::{Error, ErrorKind};
+
+/// Qt plugin directories required by most GUI apps.
+const QT_PLUGIN_DIRS: &[&str] = &[
+    "platforms",
+    "platformthemes",
+    "platforminputcontexts",
+    "imageformats",
+    "iconengines",
+    "styles",
+    "xcbglintegrations",
+    "wayland-decoration-client",
+    "wayland-graphics-integration-client",
+    "wayland-shell-integration",
+];
+
+/// `GStreamer` plugins required to play common media files, plugins not found
+/// in plugin directory are skipped.
+const GSTREAMER_PLUGINS: &[&str] = &[
+    "coreelements",
+    "typefindfunctions",
+    "playback",
+    "app",
+    "autodetect",
+    "audioconvert",
+    "audioresample",
+    "volume",
+    "videoconvert",
+    "videoscale",
+    "videoconvertscale",
+    "alsa",
+    "pulseaudio",
+    "ximagesink",
+    "xvimagesink",
+    "gio",
+    "isomp4",
+    "matroska",
+    "ogg",
+    "vorbis",
+    "opus",
+    "wavparse",
+];
+
+/// Default binary versions, used if plugin directory is set in `framework_paths`.
+const GTK_BINARY_VERSION: &str = "3.0.0";
+const GDK_PIXBUF_BINARY_VERSION: &str = "2.10.0";
+
+/// Plugins copied to `AppDir`, and environment variables to load them.
+#[derive(Debug, Default)]
+pub struct BundledFrameworks {
+    pub env: BTreeMap<String, String>,
+
+    /// ELF files copied, their dependent libraries shall also be copied.
+    pub elf_files: Vec<PathBuf>,
+}
+
+/// Copy plugins of `bundle_frameworks` to `libs_dir`.
+pub fn bundle_frameworks(
+    conf: &Config,
+    app_image_conf: &AppImageConfig,
+    app_image_dir: &Path,
+    libs_dir: &Path,
+    runner: &Runner,
+) -> Result<BundledFrameworks, Error> {
+    let mut bundled = BundledFrameworks::default();
+    for framework in &app_image_conf.bundle_frameworks {
+        let path = app_image_conf
+            .framework_paths
+            .get(framework.name())
+            .map(PathBuf::from);
+        match framework {
+            Framework::Qt5 | Framework::Qt6 => {
+                bundle_qt(
+                    conf,
+                    app_image_conf,
+                    *framework,
+                    path,
+                    app_image_dir,
+                    runner,
+                    &mut bundled,
+                )?;
+            }
+            Framework::Gtk3 => bundle_gtk3(path, libs_dir, runner, &mut bundled)?,
+            Framework::Gstreamer => {
+                bundle_gstreamer(app_image_conf, path, libs_dir, runner, &mut bundled)?;
+            }
+        }
+    }
+    Ok(bundled)
+}
+
+/// Run detect tool of `framework` and returns its trimmed output.
+fn query(framework: Framework, args: &[&str], runner: &Runner) -> Result<String, Error> {
+    let mut cmd = Command::new(framework.detect_tool());
+    cmd.args(args);
+    let stdout = runner
+        .run(&mut cmd, ErrorKind::AppImageCompilerError)
+        .map_err(|err| {
+            Error::from_string(
+                ErrorKind::AppImageCompilerError,
+                format!(
+                    "Failed to detect plugin directory of `{}`, set it in `linux.app_image.framework_paths`",
+                    framework.name()
+                ),
+            )
+            .with_source(err)
+        })?;
+    Ok(stdout.trim().to_string())
+}
+
+fn pkg_config_variable(
+    framework: Framework,
+    package: &str,
+    variable: &str,
+    runner: &Runner,
+) -> Result<String, Error> {
+    query(
+        framework,
+        &[&format!("--variable={}", variable), package],
+        runner,
+    )
+}
+
+fn is_elf(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|()| magic == *b"\x7fELF")
+}
+
+/// Copy `src` directory to `dest` recursively, ELF files are appended to `bundled`.
+fn copy_dir(src: &Path, dest: &Path, bundled: &mut BundledFrameworks) -> Result<(), Error> {
+    for entry in WalkDir::new(src).follow_links(true) {
+        let entry = entry?;
+        let dest_path = dest.join(entry.path().strip_prefix(src)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest_path)?;
+        } else {
+            fs::copy(entry.path(), &dest_path)
+                .map_err(|err| Error::from(err).with_path(entry.path()))?;
+            if is_elf(&dest_path) {
+                bundled.elf_files.push(dest_path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Copy loader cache file, with absolute paths of modules stripped, so that modules
+/// are loaded relative to module directory.
+fn copy_cache_file(src: &Path, module_dir: &Path, dest: &Path) -> Result<(), Error> {
+    let content = fs::read_to_string(src).map_err(|err| Error::from(err).with_path(src))?;
+    let prefix = format!("{}/", module_dir.display());
+    fs::write(dest, content.replace(&prefix, ""))?;
+    Ok(())
+}
+
+/// Get relative path from directory of `entry` to `AppDir`, like `../../`.
+fn app_dir_prefix(entry: &str) -> String {
+    let depth = Path::new(entry).components().count().saturating_sub(1);
+    "../".repeat(depth)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn bundle_qt(
+    conf: &Config,
+    app_image_conf: &AppImageConfig,
+    framework: Framework,
+    path: Option<PathBuf>,
+    app_image_dir: &Path,
+    runner: &Runner,
+    bundled: &mut BundledFrameworks,
+) -> Result<(), Error> {
+    let plugins_dir = match path {
+        Some(path) => path,
+        None => PathBuf::from(query(framework, &["-query", "QT_INSTALL_PLUGINS"], runner)?),
+    };
+    let relative_dir = format!("libs/{}/plugins", framework.name());
+    let dest_dir = app_image_dir.join(&relative_dir);
+    for name in QT_PLUGIN_DIRS {
+        let dir = plugins_dir.join(name);
+        if dir.is_dir() {
+            copy_dir(&dir, &dest_dir.join(name), bundled)?;
+        }
+    }
+    if !dest_dir.join("platforms").is_dir() {
+        log::warn!("No Qt platform plugins found in {:?}", plugins_dir);
+    }
+
+    // qt.conf is read from directory of executable.
+    let entry = app_image_conf.entry(conf);
+    let qt_conf = app_image_dir.join(entry).with_file_name("qt.conf");
+    if !qt_conf.exists() {
+        if let Some(parent) = qt_conf.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            &qt_conf,
+            format!(
+                "[Paths]\nPlugins = {}{}\n",
+                app_dir_prefix(entry),
+                relative_dir
+            ),
+        )?;
+    }
+    bundled.env.insert(
+        "QT_PLUGIN_PATH".to_string(),
+        format!("$HERE/{}", relative_dir),
+    );
+    Ok(())
+}
+
+fn bundle_gtk3(
+    path: Option<PathBuf>,
+    libs_dir: &Path,
+    runner: &Runner,
+    bundled: &mut BundledFrameworks,
+) -> Result<(), Error> {
+    let framework = Framework::Gtk3;
+    let (gtk_dir, loaders_dir, loaders_cache) = if let Some(library_dir) = path {
+        let loaders_dir = library_dir
+            .join("gdk-pixbuf-2.0")
+            .join(GDK_PIXBUF_BINARY_VERSION);
+        (
+            library_dir.join("gtk-3.0").join(GTK_BINARY_VERSION),
+            loaders_dir.join("loaders"),
+            loaders_dir.join("loaders.cache"),
+        )
+    } else {
+        let library_dir = pkg_config_variable(framework, "gtk+-3.0", "libdir", runner)?;
+        let version = pkg_config_variable(framework, "gtk+-3.0", "gtk_binary_version", runner)?;
+        let loaders_dir =
+            pkg_config_variable(framework, "gdk-pixbuf-2.0", "gdk_pixbuf_moduledir", runner)?;
+        let loaders_cache =
+            pkg_config_variable(framework, "gdk-pixbuf-2.0", "gdk_pixbuf_cache_file", runner)?;
+        (
+            Path::new(&library_dir).join("gtk-3.0").join(version),
+            PathBuf::from(loaders_dir),
+            PathBuf::from(loaders_cache),
+        )
+    };
+
+    // Modules are searched in `$GTK_PATH/<binary version>/`.
+    if let Some(version) = gtk_dir.file_name() {
+        let dest_dir = libs_dir.join("gtk-3.0").join(version);
+        copy_dir(&gtk_dir, &dest_dir, bundled)?;
+        bundled
+            .env
+            .insert("GTK_PATH".to_string(), "$HERE/libs/gtk-3.0".to_string());
+        let immodules_cache = gtk_dir.join("immodules.cache");
+        if immodules_cache.exists() {
+            copy_cache_file(
+                &immodules_cache,
+                &gtk_dir.join("immodules"),
+                &dest_dir.join("immodules.cache"),
+            )?;
+            bundled.env.insert(
+                "GTK_IM_MODULE_FILE".to_string(),
+                format!(
+                    "$HERE/libs/gtk-3.0/{}/immodules.cache",
+                    version.to_string_lossy()
+                ),
+            );
+        }
+    }
+
+    let dest_dir = libs_dir.join("gdk-pixbuf-2.0");
+    copy_dir(&loaders_dir, &dest_dir.join("loaders"), bundled)?;
+    copy_cache_file(
+        &loaders_cache,
+        &loaders_dir,
+        &dest_dir.join("loaders.cache"),
+    )?;
+    bundled.env.insert(
+        "GDK_PIXBUF_MODULEDIR".to_string(),
+        "$HERE/libs/gdk-pixbuf-2.0/loaders".to_string(),
+    );
+    bundled.env.insert(
+        "GDK_PIXBUF_MODULE_FILE".to_string(),
+        "$HERE/libs/gdk-pixbuf-2.0/loaders.cache".to_string(),
+    );
+    Ok(())
+}
+
+fn bundle_gstreamer(
+    app_image_conf: &AppImageConfig,
+    path: Option<PathBuf>,
+    libs_dir: &Path,
+    runner: &Runner,
+    bundled: &mut BundledFrameworks,
+) -> Result<(), Error> {
+    let framework = Framework::Gstreamer;
+    let plugins_dir = match path {
+        Some(path) => path,
+        None => PathBuf::from(pkg_config_variable(
+            framework,
+            "gstreamer-1.0",
+            "pluginsdir",
+            runner,
+        )?),
+    };
+    let dest_dir = libs_dir.join("gstreamer-1.0");
+    fs::create_dir_all(&dest_dir)?;
+    let plugins = app_image_conf.gstreamer_plugins.as_ref().map_or_else(
+        || GSTREAMER_PLUGINS.iter().map(ToString::to_string).collect(),
+        Clone::clone,
+    );
+    let mut copied = 0;
+    for plugin in &plugins {
+        let filename = format!("libgst{}.so", plugin);
+        let src = plugins_dir.join(&filename);
+        if !src.is_file() {
+            // Default list contains plugins of several versions.
+            if app_image_conf.gstreamer_plugins.is_some() {
+                log::warn!(
+                    "GStreamer plugin {:?} not found in {:?}",
+                    plugin,
+                    plugins_dir
+                );
+            }
+            continue;
+        }
+        let dest = dest_dir.join(filename);
+        fs::copy(&src, &dest).map_err(|err| Error::from(err).with_path(&src))?;
+        bundled.elf_files.push(dest);
+        copied += 1;
+    }
+    if copied == 0 {
+        log::warn!("No GStreamer plugins found in {:?}", plugins_dir);
+    }
+    bundled.env.insert(
+        "GST_PLUGIN_SYSTEM_PATH_1_0".to_string(),
+        "$HERE/libs/gstreamer-1.0".to_string(),
+    );
+
+    // Plugin scanner of host system may not match bundled gstreamer.
+    let scanner = pkg_config_variable(framework, "gstreamer-1.0", "pluginscannerdir", runner)
+        .map(|dir| Path::new(&dir).join("gst-plugin-scanner"));
+    match scanner {
+        Ok(scanner) if scanner.exists() => {
+            let dest = libs_dir.join("gst-plugin-scanner");
+            fs::copy(&scanner, &dest).map_err(|err| Error::from(err).with_path(&scanner))?;
+            bundled.elf_files.push(dest);
+            bundled.env.insert(
+                "GST_PLUGIN_SCANNER_1_0".to_string(),
+                "$HERE/libs/gst-plugin-scanner".to_string(),
+            );
+        }
+        _ => log::warn!("gst-plugin-scanner not found, it is not bundled"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_dir_prefix() {
+        assert_eq!(app_dir_prefix("hello"), "");
+        assert_eq!(app_dir_prefix("usr/bin/hello"), "../../");
+    }
+
+    #[test]
+    fn test_bundle_gstreamer() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let plugins_dir = tmp_dir.path().join("plugins");
+        fs::create_dir_all(&plugins_dir).unwrap();
+        for name in ["coreelements", "playback", "opengl"] {
+            fs::write(plugins_dir.join(format!("libgst{}.so", name)), b"\x7fELF").unwrap();
+        }
+        let libs_dir = tmp_dir.path().join("libs");
+        let runner = Runner::new(tmp_dir.path(), "gstreamer", false).unwrap();
+        let app_image_conf = AppImageConfig {
+            gstreamer_plugins: Some(vec!["coreelements".to_string(), "playback".to_string()]),
+            ..AppImageConfig::default()
+        };
+
+        let mut bundled = BundledFrameworks::default();
+        bundle_gstreamer(
+            &app_image_conf,
+            Some(plugins_dir),
+            &libs_dir,
+            &runner,
+            &mut bundled,
+        )
+        .unwrap();
+        let dest_dir = libs_dir.join("gstreamer-1.0");
+        assert!(dest_dir.join("libgstplayback.so").is_file());
+        assert!(!dest_dir.join("libgstopengl.so").exists());
+        assert!(bundled
+            .elf_files
+            .contains(&dest_dir.join("libgstcoreelements.so")));
+    }
+}
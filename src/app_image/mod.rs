@@ -2,8 +2,18 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+mod app_run;
 mod build;
 mod config;
+mod elf;
+mod frameworks;
+mod rpath;
+mod signature;
+mod update;
+mod zsync;
 
-pub use build::build_app_image;
-pub use config::AppImageConfig;
+pub use app_run::is_valid_env_name;
+pub use build::{copy_libraries, AppImagePackager};
+pub use config::{default_exclude_libs, AppImageConfig, Framework};
+pub use rpath::patch_rpath;
+pub use update::validate_update_information;
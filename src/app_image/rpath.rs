@@ -0,0 +1,455 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Rewrite `DT_RUNPATH` of ELF files, so that bundled libraries are found
+//! without `LD_LIBRARY_PATH`.
+
+use std::fs;
+use std::path::{Component, Path};
+use walkdir::WalkDir;
+
+use super::elf::{Elf, Segment};
+use crate::error::{Error, ErrorKind};
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_NOTE: u32 = 4;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const DT_NULL: u64 = 0;
+const DT_STRTAB: u64 = 5;
+const DT_STRSZ: u64 = 10;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+
+const fn align_up(value: u64, align: u64) -> u64 {
+    if align <= 1 {
+        value
+    } else {
+        value.div_ceil(align) * align
+    }
+}
+
+fn to_usize(value: u64) -> Result<usize, String> {
+    usize::try_from(value).map_err(|_err| "offset out of range".to_string())
+}
+
+/// Convert virtual address to file offset.
+fn vaddr_to_offset(segments: &[Segment], vaddr: u64) -> Option<u64> {
+    segments
+        .iter()
+        .find(|segment| {
+            segment.kind == PT_LOAD
+                && vaddr >= segment.vaddr
+                && vaddr < segment.vaddr + segment.file_size
+        })
+        .map(|segment| segment.offset + vaddr - segment.vaddr)
+}
+
+/// Dynamic section, entries are pairs of tag and value.
+struct Dynamic {
+    segment: Segment,
+    entries: Vec<(u64, u64)>,
+
+    /// Number of entries which fit in dynamic section, including `DT_NULL`.
+    capacity: usize,
+}
+
+impl Dynamic {
+    fn read(elf: &Elf, data: &[u8], segment: Segment) -> Result<Self, String> {
+        let entry_size = elf.addr_size() * 2;
+        let offset = to_usize(segment.offset)?;
+        let capacity = to_usize(segment.file_size)? / entry_size;
+        let mut entries = Vec::new();
+        for index in 0..capacity {
+            let entry = offset + index * entry_size;
+            let tag = elf.addr(data, entry).ok_or("invalid dynamic section")?;
+            if tag == DT_NULL {
+                break;
+            }
+            let value = elf
+                .addr(data, entry + elf.addr_size())
+                .ok_or("invalid dynamic section")?;
+            entries.push((tag, value));
+        }
+        Ok(Self {
+            segment,
+            entries,
+            capacity,
+        })
+    }
+
+    fn get(&self, tag: u64) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|(entry_tag, _)| *entry_tag == tag)
+            .map(|(_, value)| *value)
+    }
+
+    fn set(&mut self, tag: u64, value: u64) {
+        for entry in &mut self.entries {
+            if entry.0 == tag {
+                entry.1 = value;
+            }
+        }
+    }
+
+    /// Write entries at `offset`, the rest of `capacity` is filled with `DT_NULL`.
+    fn write(&self, elf: &Elf, data: &mut [u8], offset: usize, capacity: usize) -> Option<()> {
+        let size = elf.addr_size();
+        for index in 0..capacity {
+            let (tag, value) = self.entries.get(index).copied().unwrap_or((DT_NULL, 0));
+            let entry = offset + index * size * 2;
+            elf.set_addr(data, entry, tag)?;
+            elf.set_addr(data, entry + size, value)?;
+        }
+        Some(())
+    }
+}
+
+/// Set `DT_RUNPATH` of ELF file to `runpath`, `DT_RPATH` is replaced.
+///
+/// Returns false if `data` is not dynamically linked or runpath is unchanged.
+///
+/// Existing runpath is overwritten in place if new one fits, else string table
+/// (and dynamic section if it is full) is copied to a new `PT_LOAD` segment
+/// appended to file, which reuses program header of a `PT_NOTE` segment.
+pub fn set_runpath(data: &mut Vec<u8>, runpath: &str) -> Result<bool, String> {
+    let elf = Elf::parse(data).ok_or("not an ELF file")?;
+    let segments = elf.segments(data).ok_or("invalid program headers")?;
+    let Some(segment) = segments.iter().find(|s| s.kind == PT_DYNAMIC) else {
+        return Ok(false);
+    };
+    let mut dynamic = Dynamic::read(&elf, data, *segment)?;
+    let strtab_vaddr = dynamic.get(DT_STRTAB).ok_or("no DT_STRTAB")?;
+    let strtab_size = dynamic.get(DT_STRSZ).ok_or("no DT_STRSZ")?;
+    let strtab_offset =
+        to_usize(vaddr_to_offset(&segments, strtab_vaddr).ok_or("DT_STRTAB not in any segment")?)?;
+    let strtab = data
+        .get(strtab_offset..strtab_offset + to_usize(strtab_size)?)
+        .ok_or("invalid string table")?
+        .to_vec();
+
+    let old_tag = if dynamic.get(DT_RUNPATH).is_some() {
+        Some(DT_RUNPATH)
+    } else if dynamic.get(DT_RPATH).is_some() {
+        Some(DT_RPATH)
+    } else {
+        None
+    };
+    if let Some(old_tag) = old_tag {
+        let old_offset = to_usize(dynamic.get(old_tag).unwrap_or_default())?;
+        let old = strtab
+            .get(old_offset..)
+            .and_then(|s| s.split(|byte| *byte == 0).next())
+            .ok_or("invalid runpath offset")?;
+        if old == runpath.as_bytes() && old_tag == DT_RUNPATH {
+            return Ok(false);
+        }
+        if runpath.len() <= old.len() {
+            let start = strtab_offset + old_offset;
+            let old_len = old.len();
+            data[start..start + old_len].fill(0);
+            data[start..start + runpath.len()].copy_from_slice(runpath.as_bytes());
+            for entry in &mut dynamic.entries {
+                if entry.0 == old_tag {
+                    entry.0 = DT_RUNPATH;
+                }
+            }
+            let offset = to_usize(dynamic.segment.offset)?;
+            dynamic
+                .write(&elf, data, offset, dynamic.capacity)
+                .ok_or("failed to write dynamic section")?;
+            return Ok(true);
+        }
+    }
+
+    append_runpath(&elf, data, &segments, dynamic, strtab, runpath)?;
+    Ok(true)
+}
+
+/// Copy string table with `runpath` appended to a new segment.
+fn append_runpath(
+    elf: &Elf,
+    data: &mut Vec<u8>,
+    segments: &[Segment],
+    mut dynamic: Dynamic,
+    mut strtab: Vec<u8>,
+    runpath: &str,
+) -> Result<(), String> {
+    let last_load = segments
+        .iter()
+        .rposition(|s| s.kind == PT_LOAD)
+        .ok_or("no PT_LOAD segment")?;
+    // Loadable segments shall be sorted by virtual address.
+    let mut new_segment = segments
+        .iter()
+        .skip(last_load + 1)
+        .find(|s| s.kind == PT_NOTE)
+        .copied()
+        .ok_or("no spare program header to add a segment")?;
+    let loads = segments.iter().filter(|s| s.kind == PT_LOAD);
+    let align = loads.clone().map(|s| s.align).max().unwrap_or(1).max(1);
+    let max_vaddr = loads.map(|s| s.vaddr + s.mem_size).max().unwrap_or(0);
+
+    let file_offset = align_up(data.len() as u64, align);
+    let vaddr = align_up(max_vaddr, align);
+    let runpath_offset = strtab.len() as u64;
+    strtab.extend_from_slice(runpath.as_bytes());
+    strtab.push(0);
+
+    dynamic
+        .entries
+        .retain(|(tag, _)| *tag != DT_RPATH && *tag != DT_RUNPATH);
+    dynamic.set(DT_STRTAB, vaddr);
+    dynamic.set(DT_STRSZ, strtab.len() as u64);
+    dynamic.entries.push((DT_RUNPATH, runpath_offset));
+
+    let entry_size = elf.addr_size() * 2;
+    let move_dynamic = dynamic.entries.len() + 1 > dynamic.capacity;
+    let dynamic_offset = align_up(strtab.len() as u64, entry_size as u64);
+    let content_size = if move_dynamic {
+        dynamic_offset + ((dynamic.entries.len() + 1) * entry_size) as u64
+    } else {
+        strtab.len() as u64
+    };
+
+    data.resize(to_usize(file_offset + content_size)?, 0);
+    let start = to_usize(file_offset)?;
+    data[start..start + strtab.len()].copy_from_slice(&strtab);
+
+    new_segment.kind = PT_LOAD;
+    new_segment.flags = PF_R | PF_W;
+    new_segment.offset = file_offset;
+    new_segment.vaddr = vaddr;
+    new_segment.file_size = content_size;
+    new_segment.mem_size = content_size;
+    new_segment.align = align;
+    elf.set_segment(data, &new_segment)
+        .ok_or("failed to write program header")?;
+
+    if move_dynamic {
+        let capacity = dynamic.entries.len() + 1;
+        dynamic
+            .write(elf, data, to_usize(file_offset + dynamic_offset)?, capacity)
+            .ok_or("failed to write dynamic section")?;
+        dynamic.segment.offset = file_offset + dynamic_offset;
+        dynamic.segment.vaddr = vaddr + dynamic_offset;
+        dynamic.segment.file_size = (capacity * entry_size) as u64;
+        dynamic.segment.mem_size = dynamic.segment.file_size;
+        elf.set_segment(data, &dynamic.segment)
+            .ok_or("failed to write program header")?;
+    } else {
+        let offset = to_usize(dynamic.segment.offset)?;
+        dynamic
+            .write(elf, data, offset, dynamic.capacity)
+            .ok_or("failed to write dynamic section")?;
+    }
+
+    // Keep section headers consistent, so that tools like `readelf` work.
+    for mut section in elf.sections(data).unwrap_or_default() {
+        if section.name == b".dynstr" {
+            section.addr = vaddr;
+            section.offset = file_offset;
+            section.size = strtab.len() as u64;
+        } else if section.name == b".dynamic" {
+            section.addr = dynamic.segment.vaddr;
+            section.offset = dynamic.segment.offset;
+            section.size = dynamic.segment.file_size;
+        } else {
+            continue;
+        }
+        elf.set_section(data, &section)
+            .ok_or("failed to write section header")?;
+    }
+    Ok(())
+}
+
+/// Get runpath of file in `dir` to find libraries in `libs_dir`, like `$ORIGIN/../libs`.
+fn origin_runpath(dir: &Path, libs_dir: &Path) -> String {
+    let from: Vec<Component> = dir.components().collect();
+    let to: Vec<Component> = libs_dir.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec!["$ORIGIN".to_string()];
+    parts.extend((common..from.len()).map(|_| "..".to_string()));
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|part| part.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+/// Rewrite runpath of all dynamically linked ELF files in `app_dir` to `libs_dir`.
+pub fn patch_rpath(app_dir: &Path, libs_dir: &Path) -> Result<(), Error> {
+    for entry in WalkDir::new(app_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let mut data = fs::read(path)?;
+        if Elf::parse(&data).is_none() {
+            continue;
+        }
+        let dir = path.parent().unwrap_or(app_dir);
+        let runpath = origin_runpath(dir, libs_dir);
+        let changed = set_runpath(&mut data, &runpath).map_err(|message| {
+            Error::from_string(
+                ErrorKind::AppImageCompilerError,
+                format!("Failed to patch runpath: {}", message),
+            )
+            .with_path(path)
+        })?;
+        if changed {
+            log::info!("Set runpath of {:?} to {}", path, runpath);
+            fs::write(path, data)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_image::elf::find_section;
+
+    const DT_SONAME: u64 = 14;
+    const DT_DEBUG: u64 = 21;
+
+    /// Shared libraries in fixture directory are linked from `hello.c` with GNU ld:
+    /// `ld -m elf_i386 -shared --build-id -z max-page-size=0x1000 -z noseparate-code
+    /// --hash-style=gnu -soname libhello.so --enable-new-dtags -rpath /usr/lib/hello/old-runpath`,
+    /// `libhello32.so` and `libhello64.so` have no runpath.
+    fn read_fixture(name: &str) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/elf")
+            .join(name);
+        fs::read(path).unwrap()
+    }
+
+    fn read_dynamic(data: &[u8]) -> Dynamic {
+        let elf = Elf::parse(data).unwrap();
+        let segments = elf.segments(data).unwrap();
+        let segment = segments.iter().find(|s| s.kind == PT_DYNAMIC).unwrap();
+        Dynamic::read(&elf, data, *segment).unwrap()
+    }
+
+    /// Read string of `tag` from string table, which is located with program headers.
+    fn read_string(data: &[u8], tag: u64) -> Option<String> {
+        let elf = Elf::parse(data).unwrap();
+        let segments = elf.segments(data).unwrap();
+        let dynamic = read_dynamic(data);
+        let strtab = vaddr_to_offset(&segments, dynamic.get(DT_STRTAB)?)?;
+        let start = to_usize(strtab + dynamic.get(tag)?).ok()?;
+        let end = start + data[start..].iter().position(|byte| *byte == 0)?;
+        Some(String::from_utf8_lossy(&data[start..end]).to_string())
+    }
+
+    /// Fill spare slots of dynamic section with `DT_DEBUG`, leaving one `DT_NULL`.
+    fn fill_dynamic(data: &mut [u8]) {
+        let elf = Elf::parse(data).unwrap();
+        let mut dynamic = read_dynamic(data);
+        while dynamic.entries.len() + 1 < dynamic.capacity {
+            dynamic.entries.push((DT_DEBUG, 0));
+        }
+        let offset = to_usize(dynamic.segment.offset).unwrap();
+        dynamic.write(&elf, data, offset, dynamic.capacity).unwrap();
+    }
+
+    fn assert_runpath(data: &[u8], runpath: &str) {
+        assert_eq!(read_string(data, DT_RUNPATH).as_deref(), Some(runpath));
+        assert_eq!(read_string(data, DT_SONAME).as_deref(), Some("libhello.so"));
+        assert_eq!(read_dynamic(data).get(DT_RPATH), None);
+
+        // Section headers point to the same string table.
+        let dynstr = find_section(data, ".dynstr").unwrap();
+        let needle = format!("{}\0", runpath);
+        assert!(data[dynstr]
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes()));
+    }
+
+    #[test]
+    fn test_set_runpath_in_place() {
+        for name in ["libhello-runpath32.so", "libhello-runpath64.so"] {
+            let mut data = read_fixture(name);
+            let len = data.len();
+            assert_eq!(
+                read_string(&data, DT_RUNPATH).as_deref(),
+                Some("/usr/lib/hello/old-runpath")
+            );
+            assert_eq!(set_runpath(&mut data, "$ORIGIN/../libs"), Ok(true));
+            assert_eq!(data.len(), len);
+            assert_runpath(&data, "$ORIGIN/../libs");
+            assert_eq!(set_runpath(&mut data, "$ORIGIN/../libs"), Ok(false));
+        }
+    }
+
+    #[test]
+    fn test_set_runpath_append() {
+        let runpath = "$ORIGIN/../../libs/qt5/plugins/platforms/../../..";
+        for name in [
+            "libhello-runpath32.so",
+            "libhello-runpath64.so",
+            "libhello32.so",
+            "libhello64.so",
+        ] {
+            let mut data = read_fixture(name);
+            let len = data.len();
+            let dynamic = read_dynamic(&data);
+            assert_eq!(set_runpath(&mut data, runpath), Ok(true));
+            assert!(data.len() > len);
+            assert_runpath(&data, runpath);
+
+            // Dynamic section is updated in place, as it has spare slots.
+            let new_dynamic = read_dynamic(&data);
+            assert_eq!(new_dynamic.segment.offset, dynamic.segment.offset);
+            assert!(new_dynamic.get(DT_STRTAB) > dynamic.get(DT_STRTAB));
+            assert_eq!(set_runpath(&mut data, runpath), Ok(false));
+        }
+    }
+
+    #[test]
+    fn test_set_runpath_dynamic_full() {
+        for name in ["libhello32.so", "libhello64.so"] {
+            let mut data = read_fixture(name);
+            fill_dynamic(&mut data);
+            let dynamic = read_dynamic(&data);
+            assert_eq!(dynamic.entries.len() + 1, dynamic.capacity);
+
+            assert_eq!(set_runpath(&mut data, "$ORIGIN"), Ok(true));
+            assert_runpath(&data, "$ORIGIN");
+
+            // Dynamic section is moved to the new segment, after string table.
+            let new_dynamic = read_dynamic(&data);
+            assert_eq!(new_dynamic.entries.len(), dynamic.entries.len() + 1);
+            assert!(new_dynamic.segment.offset > dynamic.segment.offset);
+            assert!(new_dynamic.segment.vaddr > new_dynamic.get(DT_STRTAB).unwrap());
+            let section = find_section(&data, ".dynamic").unwrap();
+            assert_eq!(section.start, to_usize(new_dynamic.segment.offset).unwrap());
+
+            let elf = Elf::parse(&data).unwrap();
+            let segments = elf.segments(&data).unwrap();
+            let load = segments.iter().rfind(|s| s.kind == PT_LOAD).unwrap();
+            assert_eq!(load.offset + load.file_size, data.len() as u64);
+            assert!(!segments.iter().any(|s| s.kind == PT_NOTE));
+        }
+    }
+
+    #[test]
+    fn test_origin_runpath() {
+        let libs_dir = Path::new("/tmp/app/libs");
+        assert_eq!(
+            origin_runpath(Path::new("/tmp/app/usr/bin"), libs_dir),
+            "$ORIGIN/../../libs"
+        );
+        assert_eq!(origin_runpath(libs_dir, libs_dir), "$ORIGIN");
+        assert_eq!(
+            origin_runpath(Path::new("/tmp/app/libs/qt5/plugins/platforms"), libs_dir),
+            "$ORIGIN/../../.."
+        );
+    }
+}
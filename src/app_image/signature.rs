@@ -0,0 +1,117 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::elf::find_section;
+use crate::base::runner::Runner;
+use crate::error::{Error, ErrorKind};
+use crate::sign::{gpg_detach_sign, gpg_export_key, signing_key, SignConfig};
+
+/// ELF section reserved by `AppImage` runtime to store armored signature.
+const SIGNATURE_SECTION: &str = ".sha256_sig";
+
+/// ELF section reserved by `AppImage` runtime to store armored public key.
+const KEY_SECTION: &str = ".sig_key";
+
+fn section_range(data: &[u8], file: &Path, name: &str) -> Result<Range<usize>, Error> {
+    find_section(data, name).ok_or_else(|| {
+        Error::from_string(
+            ErrorKind::AppImageCompilerError,
+            format!("No `{}` section found in AppImage runtime", name),
+        )
+        .with_path(file)
+    })
+}
+
+/// Write `content` to section at `range`, the rest of section is filled with zeros.
+fn write_section(
+    data: &mut [u8],
+    range: Range<usize>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), Error> {
+    if content.len() >= range.len() {
+        return Err(Error::from_string(
+            ErrorKind::SignError,
+            format!(
+                "Content of `{}` is too long: {} bytes, at most {} bytes",
+                name,
+                content.len(),
+                range.len() - 1
+            ),
+        ));
+    }
+    let section = &mut data[range];
+    section.fill(0);
+    section[..content.len()].copy_from_slice(content);
+    Ok(())
+}
+
+/// SHA256 digest of `AppImage` file in hex, with signature sections filled with zeros.
+#[must_use]
+pub fn app_image_digest(data: &[u8], sig_range: Range<usize>, key_range: Range<usize>) -> String {
+    let mut hasher = Sha256::new();
+    let mut offset = 0;
+    let mut skipped = [sig_range, key_range];
+    skipped.sort_by_key(|range| range.start);
+    for range in skipped {
+        if range.start < offset {
+            continue;
+        }
+        hasher.update(&data[offset..range.start]);
+        hasher.update(vec![0; range.len()]);
+        offset = range.end;
+    }
+    hasher.update(&data[offset..]);
+    hex::encode(hasher.finalize())
+}
+
+/// Sign `AppImage` file with gpg, signature of digest is written into `.sha256_sig`
+/// section and public key into `.sig_key` section, as `appimagetool --sign` does.
+pub fn sign_app_image(file: &Path, sign_conf: &SignConfig, runner: &Runner) -> Result<(), Error> {
+    let mut data = fs::read(file).map_err(|err| Error::from(err).with_path(file))?;
+    let sig_range = section_range(&data, file, SIGNATURE_SECTION)?;
+    let key_range = section_range(&data, file, KEY_SECTION)?;
+
+    let key = signing_key(sign_conf, runner)?;
+    let public_key = gpg_export_key(sign_conf, &key, runner)?;
+
+    let digest = app_image_digest(&data, sig_range.clone(), key_range.clone());
+    let mut digest_file = file.as_os_str().to_owned();
+    digest_file.push(".digest");
+    let digest_file = PathBuf::from(digest_file);
+    fs::write(&digest_file, &digest)?;
+    let sig_file = gpg_detach_sign(sign_conf, &digest_file, &key, runner)?;
+    let signature = fs::read(&sig_file)?;
+    fs::remove_file(&digest_file)?;
+    fs::remove_file(&sig_file)?;
+
+    write_section(&mut data, sig_range, SIGNATURE_SECTION, &signature)?;
+    write_section(&mut data, key_range, KEY_SECTION, public_key.as_bytes())?;
+    fs::write(file, data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_image_digest() {
+        let data = b"header-SIGNATURE-middle-KEY-footer";
+        let sig_range = 7..16;
+        let key_range = 24..27;
+        let mut zeroed = data.to_vec();
+        zeroed[sig_range.clone()].fill(0);
+        zeroed[key_range.clone()].fill(0);
+        assert_eq!(
+            app_image_digest(data, key_range, sig_range),
+            hex::encode(Sha256::digest(&zeroed))
+        );
+    }
+}
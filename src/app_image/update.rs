@@ -0,0 +1,99 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::Path;
+
+use super::elf::find_section;
+use crate::error::{Error, ErrorKind};
+
+/// ELF section reserved by `AppImage` runtime to store update information.
+const UPDATE_INFO_SECTION: &str = ".upd_info";
+
+/// Validate update information against `AppImage` specification.
+///
+/// Supported transports are:
+/// - `zsync|<url>`
+/// - `gh-releases-zsync|<user>|<repo>|<tag>|<filename>`
+/// - `pling-v1-zsync|<product id>|<filename>`
+pub fn validate_update_information(info: &str) -> Result<(), String> {
+    let parts: Vec<&str> = info.split('|').collect();
+    let expected = match parts[0] {
+        "zsync" => 2,
+        "gh-releases-zsync" => 5,
+        "pling-v1-zsync" => 3,
+        transport => {
+            return Err(format!(
+                "has unsupported transport {:?}, use `zsync`, `gh-releases-zsync` or `pling-v1-zsync`",
+                transport
+            ));
+        }
+    };
+    if parts.len() != expected || parts.iter().any(|part| part.is_empty()) {
+        return Err(format!(
+            "{:?} shall have {} non-empty fields separated by `|`",
+            info, expected
+        ));
+    }
+    if Path::new(parts[expected - 1])
+        .extension()
+        .is_none_or(|ext| ext != "zsync")
+    {
+        return Err(format!("{:?} shall refer to a `.zsync` file", info));
+    }
+    Ok(())
+}
+
+/// Write update information to `.upd_info` section of `AppImage` file.
+pub fn embed_update_information(file: &Path, info: &str) -> Result<(), Error> {
+    validate_update_information(info).map_err(|message| {
+        Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("`linux.app_image.update_information` {}", message),
+        )
+    })?;
+
+    let mut data = fs::read(file).map_err(|err| Error::from(err).with_path(file))?;
+    let range = find_section(&data, UPDATE_INFO_SECTION).ok_or_else(|| {
+        Error::new(
+            ErrorKind::AppImageCompilerError,
+            "No `.upd_info` section found in AppImage runtime",
+        )
+        .with_path(file)
+    })?;
+    if info.len() >= range.len() {
+        return Err(Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!(
+                "`linux.app_image.update_information` is too long, at most {} bytes",
+                range.len() - 1
+            ),
+        ));
+    }
+
+    let section = &mut data[range];
+    section.fill(0);
+    section[..info.len()].copy_from_slice(info.as_bytes());
+    fs::write(file, data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_update_information() {
+        assert!(validate_update_information(
+            "gh-releases-zsync|PifuProject|pifu|latest|pifu-*-x86_64.AppImage.zsync"
+        )
+        .is_ok());
+        assert!(
+            validate_update_information("zsync|https://example.com/hello.AppImage.zsync").is_ok()
+        );
+        assert!(validate_update_information("gh-releases-zsync|PifuProject|pifu").is_err());
+        assert!(validate_update_information("zsync|https://example.com/hello.AppImage").is_err());
+        assert!(validate_update_information("http|hello").is_err());
+    }
+}
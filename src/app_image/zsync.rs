@@ -0,0 +1,180 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Generate zsync control file, compatible with `zsyncmake` 0.6.2.
+
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::base::hash::sha1_hex;
+use crate::error::Error;
+
+const ZSYNC_VERSION: &str = "0.6.2";
+
+/// Same as `zsyncmake`, larger blocks for files larger than 100MB.
+const fn block_size(len: usize) -> usize {
+    if len < 100_000_000 {
+        2048
+    } else {
+        4096
+    }
+}
+
+/// Get number of sequential matches, bytes of rolling checksum and bytes of MD4 checksum
+/// stored for each block.
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn hash_lengths(len: usize, block_size: usize) -> (usize, usize, usize) {
+    let seq_matches = if len > block_size { 2 } else { 1 };
+    let len_f = len.max(1) as f64;
+    let blocks_f = (1 + len / block_size) as f64;
+    let seq_f = seq_matches as f64;
+
+    let rsum_len = (((len_f.log2() + (block_size as f64).log2()) - 8.6) / seq_f / 8.0).ceil();
+    let rsum_len = (rsum_len as usize).clamp(2, 4);
+
+    let checksum_len = ((20.0 + len_f.log2() + blocks_f.log2()) / seq_f / 8.0).ceil() as usize;
+    let checksum_len2 = ((7.9 + 20.0 + blocks_f.log2()) / 8.0) as usize;
+    let checksum_len = checksum_len.max(checksum_len2).min(16);
+    (seq_matches, rsum_len, checksum_len)
+}
+
+/// Rolling checksum of block, `a` and `b` in big endian.
+fn rsum(block: &[u8]) -> [u8; 4] {
+    let mut a: u16 = 0;
+    let mut b: u16 = 0;
+    let len = block.len();
+    for (i, byte) in block.iter().enumerate() {
+        a = a.wrapping_add(u16::from(*byte));
+        #[allow(clippy::cast_possible_truncation)]
+        let weight = (len - i) as u16;
+        b = b.wrapping_add(weight.wrapping_mul(u16::from(*byte)));
+    }
+    let mut sum = [0; 4];
+    sum[..2].copy_from_slice(&a.to_be_bytes());
+    sum[2..].copy_from_slice(&b.to_be_bytes());
+    sum
+}
+
+/// MD4 digest (RFC 1320), used as strong checksum of blocks by zsync.
+///
+/// Variable names follow the RFC.
+#[allow(clippy::many_single_char_names)]
+fn md4(data: &[u8]) -> [u8; 16] {
+    const ROUND2: [usize; 16] = [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15];
+    const ROUND3: [usize; 16] = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for chunk in message.chunks_exact(64) {
+        let mut x = [0_u32; 16];
+        for (word, bytes) in x.iter_mut().zip(chunk.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..48 {
+            let (f, k, constant, shifts) = match i / 16 {
+                0 => ((b & c) | (!b & d), i, 0, [3, 7, 11, 19]),
+                1 => (
+                    (b & c) | (b & d) | (c & d),
+                    ROUND2[i % 16],
+                    0x5a82_7999,
+                    [3, 5, 9, 13],
+                ),
+                _ => (b ^ c ^ d, ROUND3[i % 16], 0x6ed9_eba1, [3, 9, 11, 15]),
+            };
+            let value = a
+                .wrapping_add(f)
+                .wrapping_add(x[k])
+                .wrapping_add(constant)
+                .rotate_left(shifts[i % 4]);
+            (a, b, c, d) = (d, value, b, c);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+/// Generate zsync control file of `file` at `<file>.zsync`.
+///
+/// `url` is where `file` can be downloaded, relative to the control file.
+pub fn write_zsync(file: &Path, url: &str) -> Result<PathBuf, Error> {
+    let data = fs::read(file).map_err(|err| Error::from(err).with_path(file))?;
+    let mtime: DateTime<Utc> = fs::metadata(file)?.modified()?.into();
+    let filename = file
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+
+    let len = data.len();
+    let block_size = block_size(len);
+    let (seq_matches, rsum_len, checksum_len) = hash_lengths(len, block_size);
+
+    let mut content = Vec::new();
+    writeln!(content, "zsync: {}", ZSYNC_VERSION)?;
+    writeln!(content, "Filename: {}", filename)?;
+    writeln!(
+        content,
+        "MTime: {}",
+        mtime.format("%a, %d %b %Y %H:%M:%S %z")
+    )?;
+    writeln!(content, "Blocksize: {}", block_size)?;
+    writeln!(content, "Length: {}", len)?;
+    writeln!(
+        content,
+        "Hash-Lengths: {},{},{}",
+        seq_matches, rsum_len, checksum_len
+    )?;
+    writeln!(content, "URL: {}", url)?;
+    writeln!(content, "SHA-1: {}", sha1_hex(&data))?;
+    writeln!(content)?;
+
+    // Last block is padded with zeros.
+    let mut block = vec![0; block_size];
+    for chunk in data.chunks(block_size) {
+        block[..chunk.len()].copy_from_slice(chunk);
+        block[chunk.len()..].fill(0);
+        content.extend_from_slice(&rsum(&block)[4 - rsum_len..]);
+        content.extend_from_slice(&md4(&block)[..checksum_len]);
+    }
+
+    let mut zsync_file = file.as_os_str().to_owned();
+    zsync_file.push(".zsync");
+    let zsync_file = PathBuf::from(zsync_file);
+    fs::write(&zsync_file, content)?;
+    Ok(zsync_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(hex::encode(md4(b"")), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        assert_eq!(hex::encode(md4(b"abc")), "a448017aaf21d8525fc10ae87aa6729d");
+        assert_eq!(
+            hex::encode(md4("1234567890".repeat(8).as_bytes())),
+            "e33b4ddc9c38f2199c3e7b164fcc0536"
+        );
+        assert_eq!(rsum(&[1, 2, 3]), [0, 6, 0, 10]);
+        assert_eq!(hash_lengths(100_000, 2048), (2, 2, 4));
+    }
+}
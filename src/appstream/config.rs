@@ -0,0 +1,80 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Generate `AppStream` metainfo file for software centers.
+///
+/// Id, name, homepage and license are read from metadata.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppStreamConfig {
+    /// String - One line summary, default is `description` of metadata.
+    pub summary: Option<String>,
+
+    /// String - Long description, paragraphs are separated by empty lines.
+    /// Default is `description` of metadata.
+    pub description: Option<String>,
+
+    /// String - License of metainfo file itself, default is `CC0-1.0`.
+    #[serde(default = "default_metadata_license")]
+    pub metadata_license: String,
+
+    /// String - Developer name, default is `company` or `author` of metadata.
+    pub developer: Option<String>,
+
+    #[serde(default)]
+    pub screenshots: Vec<Screenshot>,
+
+    /// Releases, newest first. Default is current version released at
+    /// `SOURCE_DATE_EPOCH` if it is set.
+    #[serde(default)]
+    pub releases: Vec<Release>,
+
+    /// OARS content rating attributes, like `{ "social-chat" = "intense" }`.
+    /// Empty rating means no objectionable content.
+    #[serde(default)]
+    pub content_rating: BTreeMap<String, String>,
+}
+
+impl Default for AppStreamConfig {
+    fn default() -> Self {
+        Self {
+            summary: None,
+            description: None,
+            metadata_license: default_metadata_license(),
+            developer: None,
+            screenshots: Vec::new(),
+            releases: Vec::new(),
+            content_rating: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_metadata_license() -> String {
+    "CC0-1.0".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Screenshot {
+    /// String - Url of screenshot image.
+    pub image: String,
+
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Release {
+    pub version: String,
+
+    /// String - Release date in `YYYY-MM-DD` format.
+    pub date: String,
+
+    /// String - Release notes, paragraphs are separated by empty lines.
+    pub description: Option<String>,
+}
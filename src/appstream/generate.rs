@@ -0,0 +1,406 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use chrono::{DateTime, NaiveDate};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use super::config::AppStreamConfig;
+use crate::base::utils::escape_xml;
+use crate::check::{reject_errors, Issue};
+use crate::config::Config;
+use crate::error::Error;
+
+/// Licenses allowed for metainfo file itself.
+const METADATA_LICENSES: &[&str] = &[
+    "FSFAP",
+    "MIT",
+    "0BSD",
+    "CC0-1.0",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "CC-BY-SA-3.0",
+    "CC-BY-SA-4.0",
+    "GFDL-1.1",
+    "GFDL-1.2",
+    "GFDL-1.3",
+    "BSL-1.0",
+    "FTL",
+    "FSFUL",
+];
+
+const CONTENT_RATING_VALUES: &[&str] = &["none", "mild", "moderate", "intense"];
+
+/// Summary longer than this is truncated by software centers.
+const MAX_SUMMARY_LEN: usize = 100;
+
+fn summary<'a>(conf: &'a Config, appstream_conf: &'a AppStreamConfig) -> &'a str {
+    appstream_conf
+        .summary
+        .as_deref()
+        .unwrap_or(&conf.metadata.description)
+}
+
+fn description<'a>(conf: &'a Config, appstream_conf: &'a AppStreamConfig) -> &'a str {
+    appstream_conf
+        .description
+        .as_deref()
+        .unwrap_or(&conf.metadata.description)
+}
+
+/// Validate required tags and values of metainfo.
+#[must_use]
+pub fn validate_appstream(conf: &Config, appstream_conf: &AppStreamConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let summary = summary(conf, appstream_conf);
+    if summary.trim().is_empty() {
+        issues.push(Issue::error(
+            "summary",
+            "is empty, and `metadata.description` is also empty".to_string(),
+        ));
+    } else {
+        if summary.chars().count() > MAX_SUMMARY_LEN {
+            issues.push(Issue::warning(
+                "summary",
+                format!("is longer than {} characters", MAX_SUMMARY_LEN),
+            ));
+        }
+        if summary.ends_with('.') {
+            issues.push(Issue::warning(
+                "summary",
+                "shall not end with a dot".to_string(),
+            ));
+        }
+    }
+    if description(conf, appstream_conf).trim().is_empty() {
+        issues.push(Issue::error(
+            "description",
+            "is empty, and `metadata.description` is also empty".to_string(),
+        ));
+    }
+
+    if !METADATA_LICENSES.contains(&appstream_conf.metadata_license.as_str()) {
+        issues.push(Issue::error(
+            "metadata_license",
+            format!(
+                "shall be one of permissive licenses: {}",
+                METADATA_LICENSES.join(", ")
+            ),
+        ));
+    }
+
+    for screenshot in &appstream_conf.screenshots {
+        if !screenshot.image.starts_with("https://") && !screenshot.image.starts_with("http://") {
+            issues.push(Issue::error(
+                "screenshots",
+                format!("image {:?} shall be a http(s) url", screenshot.image),
+            ));
+        }
+    }
+
+    for release in &appstream_conf.releases {
+        if release.version.is_empty() {
+            issues.push(Issue::error("releases", "version is empty".to_string()));
+        }
+        if NaiveDate::parse_from_str(&release.date, "%Y-%m-%d").is_err() {
+            issues.push(Issue::error(
+                "releases",
+                format!("date {:?} is not in `YYYY-MM-DD` format", release.date),
+            ));
+        }
+    }
+
+    for (id, value) in &appstream_conf.content_rating {
+        if !CONTENT_RATING_VALUES.contains(&value.as_str()) {
+            issues.push(Issue::error(
+                "content_rating",
+                format!(
+                    "{:?} of {:?} shall be one of {}",
+                    value,
+                    id,
+                    CONTENT_RATING_VALUES.join(", ")
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+/// Write `text` as `<p>` elements, paragraphs are separated by empty lines.
+fn write_paragraphs(xml: &mut String, text: &str, indent: &str) -> Result<(), Error> {
+    for paragraph in text.split("\n\n") {
+        let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !paragraph.is_empty() {
+            writeln!(xml, "{}<p>{}</p>", indent, escape_xml(&paragraph))?;
+        }
+    }
+    Ok(())
+}
+
+/// Get developer id from reversed domain of `app_id`, like `org.biofan`.
+fn developer_id(app_id: &str) -> String {
+    let parts = app_id.split('.').collect::<Vec<_>>();
+    if parts.len() > 2 {
+        parts[..parts.len() - 1].join(".")
+    } else {
+        app_id.to_string()
+    }
+}
+
+/// Generate content of metainfo file.
+pub fn metainfo(conf: &Config, appstream_conf: &AppStreamConfig) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let has_desktop = conf
+        .linux
+        .as_ref()
+        .is_some_and(|linux| linux.desktop.is_some());
+    let component_type = if has_desktop {
+        "desktop-application"
+    } else {
+        "console-application"
+    };
+
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(xml, r#"<component type="{}">"#, component_type)?;
+    writeln!(xml, "  <id>{}</id>", escape_xml(&metadata.app_id))?;
+    writeln!(
+        xml,
+        "  <metadata_license>{}</metadata_license>",
+        escape_xml(&appstream_conf.metadata_license)
+    )?;
+    if !metadata.license.is_empty() {
+        writeln!(
+            xml,
+            "  <project_license>{}</project_license>",
+            escape_xml(&metadata.license)
+        )?;
+    }
+    writeln!(xml, "  <name>{}</name>", escape_xml(&metadata.product_name))?;
+    writeln!(
+        xml,
+        "  <summary>{}</summary>",
+        escape_xml(summary(conf, appstream_conf))
+    )?;
+
+    let developer = appstream_conf
+        .developer
+        .as_ref()
+        .or(metadata.company.as_ref())
+        .map_or_else(
+            // Remove email from `Name <email>`.
+            || metadata.author.split('<').next().unwrap_or_default().trim(),
+            String::as_str,
+        );
+    if !developer.is_empty() {
+        writeln!(
+            xml,
+            r#"  <developer id="{}">"#,
+            escape_xml(&developer_id(&metadata.app_id))
+        )?;
+        writeln!(xml, "    <name>{}</name>", escape_xml(developer))?;
+        writeln!(xml, "  </developer>")?;
+    }
+
+    writeln!(xml, "  <description>")?;
+    write_paragraphs(&mut xml, description(conf, appstream_conf), "    ")?;
+    writeln!(xml, "  </description>")?;
+
+    if has_desktop {
+        writeln!(
+            xml,
+            r#"  <launchable type="desktop-id">{}.desktop</launchable>"#,
+            escape_xml(&metadata.app_id)
+        )?;
+    } else {
+        writeln!(xml, "  <provides>")?;
+        writeln!(xml, "    <binary>{}</binary>", escape_xml(&metadata.name))?;
+        writeln!(xml, "  </provides>")?;
+    }
+    if !metadata.homepage.is_empty() {
+        writeln!(
+            xml,
+            r#"  <url type="homepage">{}</url>"#,
+            escape_xml(&metadata.homepage)
+        )?;
+    }
+
+    write_screenshots(&mut xml, appstream_conf)?;
+    write_releases(&mut xml, conf, appstream_conf, source_date())?;
+    write_content_rating(&mut xml, appstream_conf)?;
+    writeln!(xml, "</component>")?;
+    Ok(xml)
+}
+
+fn write_screenshots(xml: &mut String, appstream_conf: &AppStreamConfig) -> Result<(), Error> {
+    if appstream_conf.screenshots.is_empty() {
+        return Ok(());
+    }
+    writeln!(xml, "  <screenshots>")?;
+    for (index, screenshot) in appstream_conf.screenshots.iter().enumerate() {
+        if index == 0 {
+            writeln!(xml, r#"    <screenshot type="default">"#)?;
+        } else {
+            writeln!(xml, "    <screenshot>")?;
+        }
+        writeln!(
+            xml,
+            "      <image>{}</image>",
+            escape_xml(&screenshot.image)
+        )?;
+        if let Some(caption) = screenshot.caption.as_ref() {
+            writeln!(xml, "      <caption>{}</caption>", escape_xml(caption))?;
+        }
+        writeln!(xml, "    </screenshot>")?;
+    }
+    writeln!(xml, "  </screenshots>")?;
+    Ok(())
+}
+
+/// Date of `SOURCE_DATE_EPOCH` environment variable, used by reproducible builds.
+fn source_date() -> Option<NaiveDate> {
+    let epoch = env::var("SOURCE_DATE_EPOCH")
+        .ok()?
+        .trim()
+        .parse::<i64>()
+        .ok()?;
+    DateTime::from_timestamp(epoch, 0).map(|time| time.date_naive())
+}
+
+/// If `releases` is empty, current version released at `source_date` is written,
+/// and `<releases>` is omitted without `source_date`.
+fn write_releases(
+    xml: &mut String,
+    conf: &Config,
+    appstream_conf: &AppStreamConfig,
+    source_date: Option<NaiveDate>,
+) -> Result<(), Error> {
+    if appstream_conf.releases.is_empty() {
+        if let Some(date) = source_date {
+            writeln!(xml, "  <releases>")?;
+            writeln!(
+                xml,
+                r#"    <release version="{}" date="{}"/>"#,
+                escape_xml(&conf.metadata.version),
+                date.format("%Y-%m-%d")
+            )?;
+            writeln!(xml, "  </releases>")?;
+        }
+        return Ok(());
+    }
+
+    writeln!(xml, "  <releases>")?;
+    for release in &appstream_conf.releases {
+        let attrs = format!(
+            r#"version="{}" date="{}""#,
+            escape_xml(&release.version),
+            escape_xml(&release.date)
+        );
+        if let Some(description) = release.description.as_ref() {
+            writeln!(xml, "    <release {}>", attrs)?;
+            writeln!(xml, "      <description>")?;
+            write_paragraphs(xml, description, "        ")?;
+            writeln!(xml, "      </description>")?;
+            writeln!(xml, "    </release>")?;
+        } else {
+            writeln!(xml, "    <release {}/>", attrs)?;
+        }
+    }
+    writeln!(xml, "  </releases>")?;
+    Ok(())
+}
+
+fn write_content_rating(xml: &mut String, appstream_conf: &AppStreamConfig) -> Result<(), Error> {
+    if appstream_conf.content_rating.is_empty() {
+        writeln!(xml, r#"  <content_rating type="oars-1.1"/>"#)?;
+    } else {
+        writeln!(xml, r#"  <content_rating type="oars-1.1">"#)?;
+        for (id, value) in &appstream_conf.content_rating {
+            writeln!(
+                xml,
+                r#"    <content_attribute id="{}">{}</content_attribute>"#,
+                escape_xml(id),
+                escape_xml(value)
+            )?;
+        }
+        writeln!(xml, "  </content_rating>")?;
+    }
+    Ok(())
+}
+
+/// Write metainfo file to `usr/share/metainfo` of `dest_dir`.
+pub fn install_metainfo(
+    conf: &Config,
+    appstream_conf: &AppStreamConfig,
+    dest_dir: &Path,
+) -> Result<(), Error> {
+    reject_errors("linux.appstream", &validate_appstream(conf, appstream_conf))?;
+    let xml = metainfo(conf, appstream_conf)?;
+    let metainfo_dir = dest_dir.join("usr/share/metainfo");
+    fs::create_dir_all(&metainfo_dir)?;
+    fs::write(
+        metainfo_dir.join(format!("{}.metainfo.xml", conf.metadata.app_id)),
+        xml,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::appstream::config::Release;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_metainfo() {
+        let mut metadata = Metadata::new("hello", "1.0.0");
+        metadata.product_name = "Hello".to_string();
+        metadata.description = "Say hello & exit".to_string();
+        metadata.author = "Foo Bar <foo@example.com>".to_string();
+        metadata.license = "GPL-3.0".to_string();
+        let conf = Config::new(metadata);
+        let appstream_conf = AppStreamConfig {
+            releases: vec![Release {
+                version: "1.0.0".to_string(),
+                date: "2022-07-01".to_string(),
+                description: None,
+            }],
+            ..AppStreamConfig::default()
+        };
+        assert!(validate_appstream(&conf, &appstream_conf).is_empty());
+        let xml = metainfo(&conf, &appstream_conf).unwrap();
+        assert!(xml.contains(r#"<component type="console-application">"#));
+        assert!(xml.contains("<summary>Say hello &amp; exit</summary>"));
+        assert!(xml.contains(r#"<developer id="org.example">"#));
+        assert!(xml.contains("<name>Foo Bar</name>"));
+        assert!(xml.contains(r#"<release version="1.0.0" date="2022-07-01"/>"#));
+
+        let appstream_conf = AppStreamConfig {
+            metadata_license: "GPL-3.0".to_string(),
+            releases: vec![Release {
+                version: "1.0.0".to_string(),
+                date: "07/01/2022".to_string(),
+                description: None,
+            }],
+            ..AppStreamConfig::default()
+        };
+        assert_eq!(validate_appstream(&conf, &appstream_conf).len(), 2);
+    }
+
+    #[test]
+    fn test_write_releases() {
+        let conf = Config::new(Metadata::new("hello", "1.0.0"));
+        let appstream_conf = AppStreamConfig::default();
+        let mut xml = String::new();
+        write_releases(&mut xml, &conf, &appstream_conf, None).unwrap();
+        assert!(xml.is_empty());
+
+        let date = NaiveDate::from_ymd_opt(2022, 7, 1);
+        write_releases(&mut xml, &conf, &appstream_conf, date).unwrap();
+        assert!(xml.contains(r#"<release version="1.0.0" date="2022-07-01"/>"#));
+    }
+}
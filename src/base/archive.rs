@@ -82,7 +82,7 @@ pub fn create_tar_chown(dir: &Path, to: &Path) -> Result<(), Error> {
             if !path_str.ends_with('/') {
                 path_str += "/";
             }
-            header.set_path(&filename)?;
+            header.set_path(filename)?;
             header.set_entry_type(tar::EntryType::Directory);
             header.set_cksum();
             builder.append(&header, &mut io::empty())?;
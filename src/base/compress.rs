@@ -28,35 +28,29 @@ pub fn create_xz2(in_path: &Path, out_path: &Path) -> Result<(), Error> {
     let xz_level = 6;
     let stream = MtStreamBuilder::new()
         .preset(xz_level)
-        .threads(num_cpus::get() as u32)
+        .threads(u32::try_from(num_cpus::get()).unwrap_or(1))
         .encoder()?;
 
     let out_file = File::create(out_path).map_err(|err| {
-        Error::from_string(
-            ErrorKind::IoError,
-            format!(
-                "Failed to create out file: {:?}, error: {:?}",
-                out_path, err
-            ),
-        )
+        Error::new(ErrorKind::IoError, "Failed to create out file")
+            .with_source(err)
+            .with_path(out_path)
     })?;
 
     log::info!("Create xz encoder");
     let mut encoder = XzEncoder::new_stream(out_file, stream);
     let mut in_file = File::open(in_path).map_err(|err| {
-        Error::from_string(
-            ErrorKind::IoError,
-            format!("Failed to open file {:?}, err: {:?}", in_path, err),
-        )
+        Error::new(ErrorKind::IoError, "Failed to open file")
+            .with_source(err)
+            .with_path(in_path)
     })?;
     io::copy(&mut in_file, &mut encoder).map_err(|err| {
         Error::from_string(
             ErrorKind::IoError,
-            format!(
-                "Failed to copy file from {:?} to {:?}, err: {:?}",
-                in_file, out_path, err
-            ),
+            format!("Failed to compress file {:?}", in_path),
         )
+        .with_source(err)
+        .with_path(out_path)
     })?;
     encoder.finish()?;
 
@@ -2,22 +2,23 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use serde::{Deserialize, Serialize};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+use super::named_enum::{deserialize_named, NamedEnum};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
 pub enum Arch {
-    #[serde(alias = "x86")]
     X86,
 
-    #[serde(alias = "x86_64")]
     X86_64,
 
-    #[serde(alias = "aarch64")]
     AArch64,
 
-    #[serde(alias = "mips64")]
     Mips64,
 }
 
@@ -38,14 +39,38 @@ pub const fn get_target_arch() -> Option<Arch> {
     None
 }
 
+impl NamedEnum for Arch {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::X86, "x86"),
+        (Self::X86, "i386"),
+        (Self::X86, "i686"),
+        (Self::X86_64, "x86_64"),
+        (Self::X86_64, "amd64"),
+        (Self::AArch64, "aarch64"),
+        (Self::AArch64, "arm64"),
+        (Self::Mips64, "mips64"),
+    ];
+}
+
 impl fmt::Display for Arch {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::X86 => write!(f, "x86"),
-            Self::X86_64 => write!(f, "x86_64"),
-            Self::AArch64 => write!(f, "aarch64"),
-            Self::Mips64 => write!(f, "mips64"),
-        }
+        f.write_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Arch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_named(deserializer)
+    }
+}
+
+impl JsonSchema for Arch {
+    fn schema_name() -> String {
+        "Arch".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        Self::schema("CPU architecture")
     }
 }
 
@@ -53,77 +78,182 @@ impl FromStr for Arch {
     type Err = ();
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        match input {
-            "x86" | "i386" | "i686" => Ok(Self::X86),
-            "x86_64" | "amd64" => Ok(Self::X86_64),
-            "arm64" | "aarch64" => Ok(Self::AArch64),
-            "mips64" => Ok(Self::Mips64),
-            _ => Err(()),
-        }
+        Self::from_name(input).ok_or(())
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
 pub struct GlobPatterns(Vec<String>);
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Metadata {
+    /// String - Package name, used as file name of packages.
     pub name: String,
+
+    /// String - Human readable application name.
     pub product_name: String,
+
+    /// String - Application id in reverse domain name, like `org.biofan.pifu`.
     pub app_id: String,
+
     pub description: String,
     pub homepage: String,
+
+    /// String - Author or maintainer, like `Name <email@example.com>`.
     pub author: String,
+
     pub copyright: Option<String>,
     pub company: Option<String>,
     pub version: String,
+
+    /// String - Build number, file macros like `${date}` or `${git}` are supported.
     pub build_id: String,
+
+    /// String - License name, like `GPL-3.0`.
     pub license: String,
+
+    /// String - The path to license file, relative to `src_dir`.
     pub license_file: Option<String>,
 
+    /// String - Directory to place generated packages and temporary files.
     pub workdir: String,
+
+    /// String - Base directory of `from` property of file sets.
     pub src_dir: String,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+impl Metadata {
+    /// Create metadata with required `name` and `version`, other fields are
+    /// set to defaults or empty.
+    #[must_use]
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            product_name: name.to_string(),
+            app_id: format!("org.example.{}", name),
+            description: String::new(),
+            homepage: String::new(),
+            author: String::new(),
+            copyright: None,
+            company: None,
+            version: version.to_string(),
+            build_id: "0".to_string(),
+            license: String::new(),
+            license_file: None,
+            workdir: "target/pifu".to_string(),
+            src_dir: ".".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
 pub enum PlatformTarget {
-    #[serde(alias = "deb")]
     Deb,
 
-    #[serde(alias = "rpm")]
     Rpm,
 
-    #[serde(alias = "app_image")]
     AppImage,
 
+    /// Flatpak bundle, or flatpak-builder manifest only.
+    Flatpak,
+
+    Snap,
+
+    /// Alpine package.
+    Apk,
+
+    /// `OpenWrt` package.
+    Ipk,
+
+    /// Portable gzipped tarball.
+    Tarball,
+
+    /// `macOS` application bundle.
+    App,
+
+    /// `macOS` disk image containing application bundle.
+    Dmg,
+
+    /// `macOS` flat installer package.
+    MacosPkg,
+
     /// For windows exe file.
-    #[serde(alias = "nsis")]
     Nsis,
+
+    /// Custom formats built by external plugins, see `[plugins]` section.
+    Plugin,
+}
+
+impl NamedEnum for PlatformTarget {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Deb, "deb"),
+        (Self::Rpm, "rpm"),
+        (Self::AppImage, "app_image"),
+        (Self::AppImage, "appImage"),
+        (Self::Flatpak, "flatpak"),
+        (Self::Snap, "snap"),
+        (Self::Apk, "apk"),
+        (Self::Ipk, "ipk"),
+        (Self::Tarball, "tarball"),
+        (Self::App, "app"),
+        (Self::Dmg, "dmg"),
+        (Self::MacosPkg, "macos_pkg"),
+        (Self::Nsis, "nsis"),
+        (Self::Plugin, "plugin"),
+    ];
+}
+
+impl<'de> Deserialize<'de> for PlatformTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_named(deserializer)
+    }
+}
+
+impl JsonSchema for PlatformTarget {
+    fn schema_name() -> String {
+        "PlatformTarget".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        Self::schema("Package format")
+    }
+}
+
+impl fmt::Display for PlatformTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl FromStr for PlatformTarget {
     type Err = ();
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        match input {
-            "deb" => Ok(Self::Deb),
-            "rpm" => Ok(Self::Rpm),
-            "app_image" | "appImage" | "AppImage" => Ok(Self::AppImage),
-            "nsis" => Ok(Self::Nsis),
-            _ => Err(()),
-        }
+        Self::from_name(input).ok_or(())
     }
 }
 
 impl PlatformTarget {
     /// Returns extension name of generated artifcate files.
+    ///
+    /// It is empty for plugins as their file formats are unknown.
     #[must_use]
     pub const fn extension(&self) -> &'static str {
         match self {
             Self::Deb => "deb",
             Self::Rpm => "rpm",
             Self::AppImage => "AppImage",
+            Self::Flatpak => "flatpak",
+            Self::Snap => "snap",
+            Self::Apk => "apk",
+            Self::Ipk => "ipk",
+            Self::Tarball => "tar.gz",
+            Self::App => "app",
+            Self::Dmg => "dmg",
+            Self::MacosPkg => "pkg",
             Self::Nsis => "exe",
+            Self::Plugin => "",
         }
     }
 }
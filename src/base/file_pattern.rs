@@ -8,6 +8,7 @@ use std::env;
 use std::process::Command;
 
 use super::config::{Arch, PlatformTarget};
+use crate::cargo::BuildConfig;
 use crate::config::Config;
 use crate::error::{Error, ErrorKind};
 
@@ -17,7 +18,19 @@ pub fn get_git_hash() -> Result<String, Error> {
         .arg("rev-parse")
         .arg("--short")
         .arg("HEAD")
-        .output()?;
+        .output()
+        .map_err(|err| {
+            Error::new(ErrorKind::GitHashError, "Failed to run `git` command")
+                .with_source(err)
+                .with_command("git rev-parse --short HEAD")
+        })?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::GitHashError,
+            "Failed to get commit hash, is this a git repository?",
+        )
+        .with_command("git rev-parse --short HEAD"));
+    }
     let hash = String::from_utf8(output.stdout)?;
     Ok(hash.trim().to_string())
 }
@@ -107,6 +120,21 @@ pub fn expand_file_macro(
     }
     // TODO(Shaohua): Support ${os} macro
 
+    let content_triple = "${triple}";
+    let content_target_dir = "${target_dir}";
+    if content.contains(content_triple) || content.contains(content_target_dir) {
+        let default_build_conf = BuildConfig::default();
+        let build_conf = conf.build.as_ref().unwrap_or(&default_build_conf);
+        let triple = build_conf.triple(arch, target).ok_or_else(|| {
+            Error::from_string(
+                ErrorKind::InvalidConfError,
+                format!("No target triple found for {} {:?}", arch, target),
+            )
+        })?;
+        content = content.replace(content_triple, &triple);
+        content = content.replace(content_target_dir, &build_conf.output_dir(&triple));
+    }
+
     let key_pattern = Regex::new(r"\$\{(\w+)\}")?;
     let mut new_content = content.clone();
     if key_pattern.is_match(&content) {
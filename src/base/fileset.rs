@@ -2,22 +2,52 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
-use crate::base::GlobPatterns;
+use crate::base::{expand_file_macro, Arch, GlobPatterns, PlatformTarget};
+use crate::config::Config;
 use crate::error::{Error, ErrorKind};
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct FileSet {
     pub from: String,
     pub to: String,
     pub filter: Option<GlobPatterns>,
     pub mode: Option<i32>,
+
+    /// Only copy this file set when building for these architectures.
+    pub arch: Option<Vec<Arch>>,
+
+    /// Only copy this file set when building these targets.
+    pub targets: Option<Vec<PlatformTarget>>,
 }
 
 impl FileSet {
+    /// Returns true if this file set shall be copied for `arch` and `target`.
+    #[must_use]
+    pub fn matches(&self, arch: Arch, target: PlatformTarget) -> bool {
+        self.arch
+            .as_ref()
+            .is_none_or(|arches| arches.contains(&arch))
+            && self
+                .targets
+                .as_ref()
+                .is_none_or(|targets| targets.contains(&target))
+    }
+
+    /// Expand file macros in `from` and `to` properties, like `${arch}` or `${target_dir}`.
+    pub fn expand(&self, conf: &Config, arch: Arch, target: PlatformTarget) -> Result<Self, Error> {
+        Ok(Self {
+            from: expand_file_macro(&self.from, conf, arch, target)?,
+            to: expand_file_macro(&self.to, conf, arch, target)?,
+            ..self.clone()
+        })
+    }
+
     pub fn copy_to(&self, src: &str, dest: &Path) -> Result<(), Error> {
         log::info!("FileSet::copy_to() src: {:?}, dest: {:?}", src, dest);
         let dest_path = dest.join(&self.to);
@@ -28,23 +58,17 @@ impl FileSet {
             )
         })?;
         fs::create_dir_all(dest_dir).map_err(|err| {
-            Error::from_string(
-                ErrorKind::IoError,
-                format!(
-                    "Failed to create directory `{:?}`, error: {:?}",
-                    dest_dir, err
-                ),
-            )
+            Error::new(ErrorKind::IoError, "Failed to create directory")
+                .with_source(err)
+                .with_path(dest_dir)
         })?;
         let src_pattern = format!("{}/{}", src, &self.from);
         let entries = glob::glob(&src_pattern).map_err(|err| {
             Error::from_string(
                 ErrorKind::GlobPatternError,
-                format!(
-                    "Failed to crate glob pattern for {:?}, error: {:?}",
-                    &src_pattern, err
-                ),
+                format!("Failed to create glob pattern for {:?}", &src_pattern),
             )
+            .with_source(err)
         })?;
         let mut entry_not_match = true;
         for entry in entries {
@@ -55,22 +79,17 @@ impl FileSet {
                 fs::copy(&entry, &dest_path).map_err(|err| {
                     Error::from_string(
                         ErrorKind::IoError,
-                        format!(
-                            "Failed to copy file from `{:?}` to `{:?}`, err: {:?}",
-                            &entry, &dest_path, err
-                        ),
+                        format!("Failed to copy file from {:?}", &entry),
                     )
+                    .with_source(err)
+                    .with_path(&dest_path)
                 })?;
             } else if metadata.is_dir() {
                 // `dest_path` must be a directory.
                 fs::create_dir_all(&dest_path).map_err(|err| {
-                    Error::from_string(
-                        ErrorKind::IoError,
-                        format!(
-                            "Failed to create directory `{:?}`, error: {:?}",
-                            &dest_path, err
-                        ),
-                    )
+                    Error::new(ErrorKind::IoError, "Failed to create directory")
+                        .with_source(err)
+                        .with_path(&dest_path)
                 })?;
 
                 let mut options = fs_extra::dir::CopyOptions::new();
@@ -79,11 +98,10 @@ impl FileSet {
                 fs_extra::dir::copy(&entry, &dest_path, &options).map_err(|err| {
                     Error::from_string(
                         ErrorKind::IoError,
-                        format!(
-                            "Failed to copy folder from `{:?}` to `{:?}`, err: {:?}",
-                            &entry, &dest_path, err
-                        ),
+                        format!("Failed to copy folder from {:?}", &entry),
                     )
+                    .with_source(err)
+                    .with_path(&dest_path)
                 })?;
             } else {
                 return Err(Error::from_string(
@@ -103,16 +121,54 @@ impl FileSet {
     }
 }
 
-pub fn copy_filesets(files: &[FileSet], src: &str, dest: &Path) -> Result<(), Error> {
+pub fn copy_filesets(
+    files: &[FileSet],
+    conf: &Config,
+    arch: Arch,
+    target: PlatformTarget,
+    dest: &Path,
+) -> Result<(), Error> {
     log::info!(
-        "copy_filesets() files: {:?}, src: {:?}, dest: {:?}",
+        "copy_filesets() files: {:?}, arch: {}, target: {:?}, dest: {:?}",
         files,
-        src,
+        arch,
+        target,
         dest
     );
-    for file in files {
-        file.copy_to(src, dest)?;
+    for file in files.iter().filter(|file| file.matches(arch, target)) {
+        let file = file.expand(conf, arch, target)?;
+        file.copy_to(&conf.metadata.src_dir, dest)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let file: FileSet = toml::from_str(
+            r#"
+from = "target/${triple}/release/hello"
+to = "usr/bin/hello"
+arch = ["x86_64"]
+targets = ["deb", "rpm"]
+"#,
+        )
+        .unwrap();
+        assert!(file.matches(Arch::X86_64, PlatformTarget::Deb));
+        assert!(!file.matches(Arch::X86, PlatformTarget::Deb));
+        assert!(!file.matches(Arch::X86_64, PlatformTarget::AppImage));
+
+        let file: FileSet = toml::from_str(
+            r#"
+from = "LICENSE"
+to = "LICENSE.txt"
+"#,
+        )
+        .unwrap();
+        assert!(file.matches(Arch::AArch64, PlatformTarget::Nsis));
+    }
+}
@@ -2,6 +2,7 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+use sha1::Sha1;
 use sha2::Digest;
 use std::fs::File;
 use std::io;
@@ -24,3 +25,22 @@ pub fn sha256sum<P: AsRef<Path>>(file: P) -> Result<String, io::Error> {
     let result = digest.finalize();
     Ok(hex::encode(result))
 }
+
+/// Hex encoded sha1 checksum of `data`, used by package formats which require it.
+#[must_use]
+pub fn sha1_hex(data: &[u8]) -> String {
+    hex::encode(Sha1::digest(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha1_hex() {
+        assert_eq!(
+            sha1_hex(b"hello"),
+            "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"
+        );
+    }
+}
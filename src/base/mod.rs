@@ -8,7 +8,10 @@ pub mod config;
 mod file_pattern;
 pub mod fileset;
 pub mod hash;
+mod named_enum;
+pub mod runner;
 pub mod utils;
 
 pub use config::{Arch, GlobPatterns, Metadata, PlatformTarget};
 pub use file_pattern::{expand_file_macro, expand_file_macro_simple};
+pub use named_enum::{deserialize_named, NamedEnum};
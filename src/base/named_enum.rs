@@ -0,0 +1,120 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::schema::Schema;
+use serde::de::{self, Deserialize, Deserializer};
+use std::fmt;
+
+use crate::schema::enum_schema;
+
+/// Enum written as string in config file and command line.
+///
+/// `NAMES` is the only list of accepted names, it is shared by `Deserialize`,
+/// `FromStr`, `Display` and JSON schema of the enum. Variant names like
+/// `AppImage` are accepted too, as they are written by `Serialize`.
+pub trait NamedEnum: Copy + PartialEq + fmt::Debug + 'static {
+    /// Pairs of variant and name, the first name of each variant is its canonical name.
+    const NAMES: &'static [(Self, &'static str)];
+
+    /// Canonical name of variant.
+    fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(variant, _name)| *variant == self)
+            .map_or("", |(_variant, name)| name)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(variant, alias)| *alias == name || format!("{:?}", variant) == name)
+            .map(|(variant, _name)| *variant)
+    }
+
+    /// Canonical names of all variants, quoted like `deb`, used in error messages.
+    fn quoted_names() -> String {
+        Self::NAMES
+            .iter()
+            .filter(|(variant, name)| variant.name() == *name)
+            .map(|(_variant, name)| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// All of accepted names, including variant names.
+    fn all_names() -> Vec<String> {
+        let mut names = Self::NAMES
+            .iter()
+            .map(|(_variant, name)| (*name).to_string())
+            .collect::<Vec<_>>();
+        for (variant, _name) in Self::NAMES {
+            let variant_name = format!("{:?}", variant);
+            if !names.contains(&variant_name) {
+                names.push(variant_name);
+            }
+        }
+        names
+    }
+
+    fn schema(description: &str) -> Schema {
+        let names = Self::all_names();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+        enum_schema(description, &names)
+    }
+}
+
+/// Deserialize enum from one of its names, used to implement `Deserialize`.
+pub fn deserialize_named<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: NamedEnum,
+{
+    let name = String::deserialize(deserializer)?;
+    T::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown variant `{}`, expected one of {}",
+            name,
+            T::quoted_names()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_image::Framework;
+    use crate::base::{Arch, PlatformTarget};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_named_enum() {
+        assert_eq!(
+            PlatformTarget::from_str("AppImage"),
+            Ok(PlatformTarget::AppImage)
+        );
+        assert_eq!(PlatformTarget::AppImage.to_string(), "app_image");
+        assert_eq!(Arch::from_str("amd64"), Ok(Arch::X86_64));
+
+        // Every variant accepted by `FromStr` is listed in schema, and is
+        // deserialized to the same variant.
+        for name in PlatformTarget::all_names() {
+            let target = PlatformTarget::from_str(&name).unwrap();
+            let value: PlatformTarget = serde_json::from_str(&format!("{:?}", name)).unwrap();
+            assert_eq!(value, target);
+        }
+
+        let err = serde_json::from_str::<Arch>(r#""x64""#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unknown variant `x64`, expected one of `x86`, `x86_64`"));
+
+        let value: Framework = serde_json::from_str(r#""gstreamer""#).unwrap();
+        assert_eq!(value, Framework::Gstreamer);
+        assert_eq!(Framework::Qt6.name(), "qt6");
+        let err = serde_json::from_str::<Framework>(r#""qt4""#).unwrap_err();
+        assert!(err.to_string().starts_with(
+            "unknown variant `qt4`, expected one of `qt5`, `qt6`, `gtk3`, `gstreamer`"
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+
+use super::utils::command_line;
+use crate::error::{Error, ErrorKind};
+
+/// Number of last stderr lines attached to error.
+pub const STDERR_TAIL_LINES: usize = 20;
+
+/// Run external tools, their output is written to a log file, and printed
+/// to console in verbose mode.
+#[derive(Debug)]
+pub struct Runner {
+    log_file: PathBuf,
+    verbose: bool,
+}
+
+impl Runner {
+    /// Create a runner which writes to `<workdir>/logs/<name>.log`.
+    ///
+    /// Old log file is truncated.
+    pub fn new(workdir: &Path, name: &str, verbose: bool) -> Result<Self, Error> {
+        let log_dir = workdir.join("logs");
+        fs::create_dir_all(&log_dir)?;
+        let log_file = log_dir.join(format!("{}.log", name));
+        File::create(&log_file).map_err(|err| {
+            Error::new(ErrorKind::IoError, "Failed to create log file")
+                .with_source(err)
+                .with_path(&log_file)
+        })?;
+        Ok(Self { log_file, verbose })
+    }
+
+    #[must_use]
+    pub fn log_file(&self) -> &Path {
+        &self.log_file
+    }
+
+    /// Run `cmd` and returns its stdout.
+    ///
+    /// `kind` is used if command failed.
+    pub fn run(&self, cmd: &mut Command, kind: ErrorKind) -> Result<String, Error> {
+        self.run_with_input(cmd, None, kind)
+    }
+
+    /// Same as `run()`, and writes `input` to stdin of `cmd`.
+    pub fn run_with_input(
+        &self,
+        cmd: &mut Command,
+        input: Option<&[u8]>,
+        kind: ErrorKind,
+    ) -> Result<String, Error> {
+        self.run_with_stderr(cmd, input, kind)
+            .map(|(stdout, _stderr)| stdout)
+    }
+
+    /// Same as `run_with_input()`, and returns last lines of stderr with stdout.
+    pub fn run_with_stderr(
+        &self,
+        cmd: &mut Command,
+        input: Option<&[u8]>,
+        kind: ErrorKind,
+    ) -> Result<(String, String), Error> {
+        let command = command_line(cmd);
+        log::info!("Runner::run() command: {}", command);
+        let mut log_fd = OpenOptions::new().append(true).open(&self.log_file)?;
+        writeln!(log_fd, "$ {}", command)?;
+        let log_fd = Mutex::new(log_fd);
+
+        let stdin = if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let mut child = cmd
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                let program = cmd.get_program().to_string_lossy().to_string();
+                Error::from_string(
+                    kind,
+                    format!(
+                        "Failed to run `{}` command, run `pifu doctor` to check required tools",
+                        program
+                    ),
+                )
+                .with_source(err)
+                .with_command(&command)
+            })?;
+
+        let child_stdin = child.stdin.take();
+        let child_stdout = child.stdout.take();
+        let child_stderr = child.stderr.take();
+        let (stdout, stderr_tail) = thread::scope(|s| {
+            if let (Some(mut child_stdin), Some(input)) = (child_stdin, input) {
+                s.spawn(move || {
+                    // Child may exit without reading all input.
+                    let _ = child_stdin.write_all(input);
+                });
+            }
+            let stdout_handle = s.spawn(|| {
+                let mut stdout = String::new();
+                if let Some(child_stdout) = child_stdout {
+                    read_lines(child_stdout, &log_fd, |line| {
+                        if self.verbose {
+                            println!("{}", line);
+                        }
+                        stdout.push_str(line);
+                        stdout.push('\n');
+                    })?;
+                }
+                Ok::<_, io::Error>(stdout)
+            });
+            let mut stderr_tail = VecDeque::new();
+            if let Some(child_stderr) = child_stderr {
+                read_lines(child_stderr, &log_fd, |line| {
+                    if self.verbose {
+                        eprintln!("{}", line);
+                    }
+                    if stderr_tail.len() == STDERR_TAIL_LINES {
+                        stderr_tail.pop_front();
+                    }
+                    stderr_tail.push_back(line.to_string());
+                })?;
+            }
+            let stdout = stdout_handle
+                .join()
+                .map_err(|_err| io::Error::other("stdout reader panicked"))??;
+            Ok::<_, io::Error>((stdout, stderr_tail))
+        })?;
+
+        let status = child.wait()?;
+        let stderr = Vec::from(stderr_tail).join("\n");
+        if status.success() {
+            Ok((stdout, stderr))
+        } else {
+            let program = cmd.get_program().to_string_lossy().to_string();
+            Err(Error::from_string(
+                kind,
+                format!(
+                    "`{}` returns error: {}, see {:?} for full output",
+                    program, status, self.log_file
+                ),
+            )
+            .with_command(&command)
+            .with_stderr(&stderr))
+        }
+    }
+}
+
+/// Read `reader` line by line, write each line to log file and call `on_line`.
+fn read_lines<R, F>(reader: R, log_fd: &Mutex<File>, mut on_line: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(&str),
+{
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if let Ok(mut log_fd) = log_fd.lock() {
+            writeln!(log_fd, "{}", line)?;
+        }
+        on_line(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let workdir = tempfile::tempdir().unwrap();
+        let runner = Runner::new(workdir.path(), "test", false).unwrap();
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo hello; echo oops >&2; exit 3");
+        let err = runner.run(&mut cmd, ErrorKind::IoError).unwrap_err();
+        assert_eq!(err.context().stderr.as_deref(), Some("oops"));
+
+        let mut cmd = Command::new("cat");
+        let stdout = runner
+            .run_with_input(&mut cmd, Some(b"input"), ErrorKind::IoError)
+            .unwrap();
+        assert_eq!(stdout, "input\n");
+
+        let log = fs::read_to_string(runner.log_file()).unwrap();
+        assert!(log.contains("$ sh -c"));
+        assert!(log.contains("hello\noops\n") || log.contains("oops\nhello\n"));
+    }
+}
@@ -3,7 +3,8 @@
 // in the LICENSE file.
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use walkdir::WalkDir;
 
 use crate::Error;
@@ -31,11 +32,45 @@ pub const fn default_false() -> bool {
     false
 }
 
+/// Format command line of `cmd`, used in error messages.
+#[must_use]
+pub fn command_line(cmd: &Command) -> String {
+    let mut line = cmd.get_program().to_string_lossy().to_string();
+    for arg in cmd.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    line
+}
+
+/// Escape special characters in text and attribute values of XML.
+#[must_use]
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 pub fn rmdir<P: AsRef<Path>>(p: P) -> Result<(), Error> {
-    fs::remove_dir_all(p)?;
+    if p.as_ref().exists() {
+        fs::remove_dir_all(p)?;
+    }
     Ok(())
 }
 
-pub fn mv<P: AsRef<Path>>(_src_pathtern: &str, _dest: P) -> Result<(), Error> {
-    Ok(())
+/// Move files matching `src_pattern` into `dest` directory.
+///
+/// Returns new paths of these files.
+pub fn mv<P: AsRef<Path>>(src_pattern: &str, dest: P) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for entry in glob::glob(src_pattern)? {
+        let entry = entry?;
+        if let Some(filename) = entry.file_name() {
+            let new_path = dest.as_ref().join(filename);
+            fs::rename(&entry, &new_path)?;
+            files.push(new_path);
+        }
+    }
+    Ok(files)
 }
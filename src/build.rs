@@ -2,152 +2,475 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use colored::Colorize;
+use std::path::{Path, PathBuf};
 
-use crate::app_image::build_app_image;
-use crate::base::config::get_target_arch;
+use crate::apk::ApkPackager;
+use crate::app_image::AppImagePackager;
+use crate::base::runner::Runner;
 use crate::base::{Arch, PlatformTarget};
+use crate::cargo::{cargo_build, BuildTool};
 use crate::config::Config;
-use crate::deb::build_deb;
-use crate::nsis::build_nsis;
-use crate::rpm::build_rpm;
+use crate::deb::DebPackager;
+use crate::download;
+use crate::flatpak::FlatpakPackager;
+use crate::ipk::IpkPackager;
+use crate::macos::{AppPackager, DmgPackager};
+use crate::macos_pkg::MacosPkgPackager;
+use crate::nsis::NsisPackager;
+use crate::packager::{Artifact, Packager};
+use crate::plugin::PluginPackager;
+use crate::rpm::RpmPackager;
+use crate::snap::SnapPackager;
+use crate::tarball::TarballPackager;
 use crate::Error;
 
-#[derive(Debug)]
+/// Packagers of linux targets, in build order.
+const LINUX_PACKAGERS: &[&dyn Packager] = &[
+    &DebPackager,
+    &RpmPackager,
+    &AppImagePackager,
+    &FlatpakPackager,
+    &SnapPackager,
+    &ApkPackager,
+    &IpkPackager,
+    &TarballPackager,
+];
+
+/// Packagers of macos targets, in build order.
+const MACOS_PACKAGERS: &[&dyn Packager] = &[&AppPackager, &DmgPackager, &MacosPkgPackager];
+
+/// Packagers of windows targets, in build order.
+const WINDOWS_PACKAGERS: &[&dyn Packager] = &[&NsisPackager];
+
+#[derive(Debug, Clone)]
 pub struct BuildOptions {
     pub ignore_error: bool,
 
+    /// Do not run `cargo build` even if `[build]` section is set.
+    pub skip_build: bool,
+
+    /// Print output of external tools to console.
+    pub verbose: bool,
+
+    /// Package formats to build, `PlatformTarget::Plugin` enables all plugins.
     pub targets: Vec<PlatformTarget>,
 
-    pub arches: Vec<Arch>,
+    /// Names of plugins to build, even if `PlatformTarget::Plugin` is not selected.
+    pub plugins: Vec<String>,
+
+    /// Architectures to build, `None` means all arches in config file.
+    pub arches: Option<Vec<Arch>>,
+
+    /// Custom download list of tools, see `pifu --download --manifest`.
+    pub manifest: Option<PathBuf>,
 }
 
 impl Default for BuildOptions {
     fn default() -> Self {
-        let arches = get_target_arch().map_or_else(Vec::new, |arch| vec![arch]);
         Self {
             ignore_error: false,
+            skip_build: false,
+            verbose: false,
             targets: vec![
                 PlatformTarget::Deb,
                 PlatformTarget::Rpm,
                 PlatformTarget::AppImage,
+                PlatformTarget::Flatpak,
+                PlatformTarget::Snap,
+                PlatformTarget::Apk,
+                PlatformTarget::Ipk,
+                PlatformTarget::Tarball,
+                PlatformTarget::App,
+                PlatformTarget::Dmg,
+                PlatformTarget::MacosPkg,
                 PlatformTarget::Nsis,
+                PlatformTarget::Plugin,
             ],
-            arches,
+            plugins: Vec::new(),
+            arches: None,
+            manifest: None,
         }
     }
 }
 
-pub fn build(conf: &Config, options: &BuildOptions) -> Result<(), Error> {
+impl BuildOptions {
+    #[must_use]
+    pub fn builder() -> BuildOptionsBuilder {
+        BuildOptionsBuilder::default()
+    }
+
+    /// Create runner of external tools, which writes to log file of `name` and `arch`.
+    pub fn runner(&self, conf: &Config, name: &str, arch: Arch) -> Result<Runner, Error> {
+        let workdir = Path::new(&conf.metadata.workdir);
+        Runner::new(workdir, &format!("{}-{}", name, arch), self.verbose)
+    }
+
+    /// Get path to downloaded tool `name`, version pinned in `[tools]` section
+    /// of `conf` is used.
+    pub fn tool_path(&self, conf: &Config, name: &str, arch: Arch) -> Result<PathBuf, Error> {
+        download::tool_path(conf, name, arch, self.manifest.as_deref())
+    }
+
+    /// Check whether plugin with `name` is selected.
+    #[must_use]
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.targets.contains(&PlatformTarget::Plugin) || self.plugins.iter().any(|p| p == name)
+    }
+}
+
+/// Builder of `BuildOptions`, starts with default values.
+#[derive(Debug, Default, Clone)]
+pub struct BuildOptionsBuilder {
+    options: BuildOptions,
+}
+
+impl BuildOptionsBuilder {
+    /// Continue to build other packages if one fails.
+    #[must_use]
+    pub const fn ignore_error(mut self, ignore_error: bool) -> Self {
+        self.options.ignore_error = ignore_error;
+        self
+    }
+
+    /// Do not run `cargo build` even if `[build]` section is set.
+    #[must_use]
+    pub const fn skip_build(mut self, skip_build: bool) -> Self {
+        self.options.skip_build = skip_build;
+        self
+    }
+
+    /// Print output of external tools to console.
+    #[must_use]
+    pub const fn verbose(mut self, verbose: bool) -> Self {
+        self.options.verbose = verbose;
+        self
+    }
+
+    /// Package formats to build, other targets in config file are ignored.
+    #[must_use]
+    pub fn targets(mut self, targets: &[PlatformTarget]) -> Self {
+        self.options.targets = targets.to_vec();
+        self
+    }
+
+    /// Plugins to build, in addition to `targets`.
+    #[must_use]
+    pub fn plugins(mut self, plugins: &[&str]) -> Self {
+        self.options.plugins = plugins.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Architectures to build, other arches in config file are ignored.
+    #[must_use]
+    pub fn arches(mut self, arches: &[Arch]) -> Self {
+        self.options.arches = Some(arches.to_vec());
+        self
+    }
+
+    /// Custom download list of tools.
+    #[must_use]
+    pub fn manifest(mut self, manifest: &Path) -> Self {
+        self.options.manifest = Some(manifest.to_path_buf());
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> BuildOptions {
+        self.options
+    }
+}
+
+/// Build steps reported to caller of `build_with_progress()`.
+#[derive(Debug)]
+pub enum Progress<'a> {
+    /// Start to compile rust project.
+    Compile { triple: &'a str, tool: BuildTool },
+
+    /// Start to build package.
+    Start { name: &'a str, arch: Arch },
+
+    /// Package built successfully.
+    Done {
+        name: &'a str,
+        arch: Arch,
+        files: &'a [PathBuf],
+    },
+
+    /// Failed to build package, build stops unless `ignore_error` is set.
+    Failed {
+        name: &'a str,
+        arch: Arch,
+        error: &'a Error,
+    },
+}
+
+/// Build all packages selected in `options`, returns generated files.
+pub fn build(conf: &Config, options: &BuildOptions) -> Result<Vec<Artifact>, Error> {
+    build_with_progress(conf, options, |_progress| {})
+}
+
+/// Same as `build()`, but reports each step to `on_progress`.
+pub fn build_with_progress<F>(
+    conf: &Config,
+    options: &BuildOptions,
+    mut on_progress: F,
+) -> Result<Vec<Artifact>, Error>
+where
+    F: FnMut(&Progress),
+{
     log::debug!("build() conf: {:#?}", conf);
 
-    if let Err(err) = build_linux(&conf, options) {
-        if options.ignore_error {
-            log::error!("build_linux() failed: {:?}", err);
-        } else {
-            return Err(err);
+    if !options.skip_build {
+        compile_project(conf, options, &mut on_progress)?;
+    }
+
+    let mut artifacts = Vec::new();
+    if let Some(linux_conf) = conf.linux.as_ref() {
+        let packagers = enabled_packagers(LINUX_PACKAGERS, &linux_conf.targets, options);
+        let arches = filter_arches(&linux_conf.arch, options);
+        build_packages(
+            conf,
+            options,
+            &packagers,
+            &arches,
+            &mut artifacts,
+            &mut on_progress,
+        )?;
+    }
+
+    if let Some(macos_conf) = conf.macos.as_ref() {
+        let packagers = enabled_packagers(MACOS_PACKAGERS, &macos_conf.targets, options);
+        let arches = filter_arches(&macos_conf.arch, options);
+        build_packages(
+            conf,
+            options,
+            &packagers,
+            &arches,
+            &mut artifacts,
+            &mut on_progress,
+        )?;
+    }
+
+    if let Some(windows_conf) = conf.windows.as_ref() {
+        let packagers = enabled_packagers(WINDOWS_PACKAGERS, &windows_conf.targets, options);
+        let arches = filter_arches(&windows_conf.arch, options);
+        build_packages(
+            conf,
+            options,
+            &packagers,
+            &arches,
+            &mut artifacts,
+            &mut on_progress,
+        )?;
+    }
+
+    for (name, plugin_conf) in &conf.plugins {
+        if !options.has_plugin(name) {
+            continue;
         }
+        let packager = PluginPackager::new(name);
+        let arches = filter_arches(&plugin_conf.arch, options);
+        build_packages(
+            conf,
+            options,
+            &[&packager],
+            &arches,
+            &mut artifacts,
+            &mut on_progress,
+        )?;
     }
 
-    build_windows(&conf, options)
+    Ok(artifacts)
 }
 
-fn build_linux(conf: &Config, options: &BuildOptions) -> Result<(), Error> {
-    // Skip if `linux` section is not set.
-    let linux_conf = if let Some(linux_conf) = conf.linux.as_ref() {
-        linux_conf
-    } else {
-        return Ok(());
-    };
-
-    let arches = linux_conf
-        .arch
+/// Select packagers of `targets` which are also selected in `options`.
+fn enabled_packagers<'a>(
+    packagers: &[&'a dyn Packager],
+    targets: &[PlatformTarget],
+    options: &BuildOptions,
+) -> Vec<&'a dyn Packager> {
+    packagers
         .iter()
-        .filter(|a| options.arches.contains(a))
+        .filter(|packager| {
+            let target = packager.target();
+            targets.contains(&target) && options.targets.contains(&target)
+        })
         .copied()
-        .collect::<Vec<Arch>>();
-    let targets = linux_conf
-        .targets
+        .collect()
+}
+
+fn filter_arches(arches: &[Arch], options: &BuildOptions) -> Vec<Arch> {
+    arches
         .iter()
-        .filter(|t| options.targets.contains(t))
+        .filter(|a| {
+            options
+                .arches
+                .as_ref()
+                .is_none_or(|arches| arches.contains(a))
+        })
         .copied()
-        .collect::<Vec<PlatformTarget>>();
-
-    if targets.contains(&PlatformTarget::Deb) {
-        for arch in &arches {
-            print!("Build deb package for {}...", arch);
-            match build_deb(conf, linux_conf, *arch) {
-                Ok(_) => println!(" {}", "Ok".green()),
-                Err(err) => {
-                    println!(" {}", "Failed".red());
-                    if options.ignore_error {
-                        println!("{} {:?}", "Error:".red(), err);
-                    } else {
-                        return Err(err);
+        .collect()
+}
+
+/// Compile rust project once for each target triple required by selected packages.
+fn compile_project<F>(
+    conf: &Config,
+    options: &BuildOptions,
+    on_progress: &mut F,
+) -> Result<(), Error>
+where
+    F: FnMut(&Progress),
+{
+    let Some(build_conf) = conf.build.as_ref() else {
+        return Ok(());
+    };
+
+    let mut triples = Vec::new();
+    if let Some(linux_conf) = conf.linux.as_ref() {
+        for target in &linux_conf.targets {
+            if !options.targets.contains(target) {
+                continue;
+            }
+            for arch in filter_arches(&linux_conf.arch, options) {
+                if let Some(triple) = build_conf.triple(arch, *target) {
+                    if !triples.contains(&triple) {
+                        triples.push(triple);
                     }
                 }
             }
         }
     }
-    if targets.contains(&PlatformTarget::Rpm) {
-        for arch in &arches {
-            print!("Build rpm package for {}...", arch);
-            match build_rpm(conf, linux_conf, *arch) {
-                Ok(_) => println!(" {}", "Ok".green()),
-                Err(err) => {
-                    println!(" {}", "Failed".red());
-                    if options.ignore_error {
-                        println!("{} {:?}", "Error:".red(), err);
-                    } else {
-                        return Err(err);
+    if let Some(macos_conf) = conf.macos.as_ref() {
+        for target in &macos_conf.targets {
+            if !options.targets.contains(target) {
+                continue;
+            }
+            for arch in filter_arches(&macos_conf.arch, options) {
+                if let Some(triple) = build_conf.triple(arch, *target) {
+                    if !triples.contains(&triple) {
+                        triples.push(triple);
                     }
+                } else {
+                    log::warn!("No target triple found for macos {}", arch);
                 }
             }
         }
     }
-    if targets.contains(&PlatformTarget::AppImage) {
-        for arch in &arches {
-            print!("Build AppImage package for {}...", arch);
-            match build_app_image(conf, linux_conf, *arch) {
-                Ok(_) => println!(" {}", "Ok".green()),
-                Err(err) => {
-                    println!(" {}", "Failed".red());
-                    if options.ignore_error {
-                        println!("{} {:?}", "Error:".red(), err);
-                    } else {
-                        return Err(err);
+    if let Some(windows_conf) = conf.windows.as_ref() {
+        if windows_conf.targets.contains(&PlatformTarget::Nsis)
+            && options.targets.contains(&PlatformTarget::Nsis)
+        {
+            for arch in filter_arches(&windows_conf.arch, options) {
+                if let Some(triple) = build_conf.triple(arch, PlatformTarget::Nsis) {
+                    if !triples.contains(&triple) {
+                        triples.push(triple);
                     }
+                } else {
+                    log::warn!("No target triple found for windows {}", arch);
+                }
+            }
+        }
+    }
+
+    for (name, plugin_conf) in &conf.plugins {
+        if !options.has_plugin(name) {
+            continue;
+        }
+        for arch in filter_arches(&plugin_conf.arch, options) {
+            if let Some(triple) = build_conf.triple(arch, PlatformTarget::Plugin) {
+                if !triples.contains(&triple) {
+                    triples.push(triple);
                 }
             }
         }
     }
 
+    let workdir = Path::new(&conf.metadata.workdir);
+    for triple in &triples {
+        on_progress(&Progress::Compile {
+            triple,
+            tool: build_conf.tool,
+        });
+        let runner = Runner::new(workdir, &format!("cargo-{}", triple), options.verbose)?;
+        cargo_build(build_conf, triple, &runner)?;
+    }
+
     Ok(())
 }
 
-fn build_windows(conf: &Config, options: &BuildOptions) -> Result<(), Error> {
-    let windows_conf = if let Some(windows_conf) = conf.windows.as_ref() {
-        windows_conf
-    } else {
-        return Ok(());
-    };
-
-    if windows_conf.targets.contains(&PlatformTarget::Nsis) {
-        for arch in &windows_conf.arch {
-            print!("Build AppImage package for {}...", arch);
-            match build_nsis(conf, windows_conf, *arch) {
-                Ok(_) => println!(" {}", "Ok".green()),
-                Err(err) => {
-                    if options.ignore_error {
-                        println!(" {}", "Failed".red());
-                        println!("{} {:?}", "Error:".red(), err);
-                    } else {
-                        return Err(err);
+/// Build packages with each of `packagers` for each of `arches`.
+fn build_packages<F>(
+    conf: &Config,
+    options: &BuildOptions,
+    packagers: &[&dyn Packager],
+    arches: &[Arch],
+    artifacts: &mut Vec<Artifact>,
+    on_progress: &mut F,
+) -> Result<(), Error>
+where
+    F: FnMut(&Progress),
+{
+    for packager in packagers {
+        let target = packager.target();
+        let name = packager.name();
+        for &arch in arches {
+            on_progress(&Progress::Start { name: &name, arch });
+            match packager
+                .build(conf, arch, options)
+                .map_err(|err| err.with_target(&name).with_arch(arch))
+            {
+                Ok(files) => {
+                    on_progress(&Progress::Done {
+                        name: &name,
+                        arch,
+                        files: &files,
+                    });
+                    artifacts.extend(files.into_iter().map(|path| Artifact {
+                        target,
+                        name: name.clone(),
+                        arch,
+                        path,
+                    }));
+                }
+                Err(error) => {
+                    on_progress(&Progress::Failed {
+                        name: &name,
+                        arch,
+                        error: &error,
+                    });
+                    if !options.ignore_error {
+                        return Err(error);
                     }
+                    log::error!("Failed to build {} for {}: {}", name, arch, error.report());
                 }
             }
         }
     }
-
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_options_builder() {
+        let options = BuildOptions::builder()
+            .ignore_error(true)
+            .targets(&[PlatformTarget::Rpm])
+            .arches(&[Arch::AArch64])
+            .build();
+        assert!(options.ignore_error);
+        assert!(!options.skip_build);
+        assert_eq!(options.targets, vec![PlatformTarget::Rpm]);
+        assert_eq!(options.arches, Some(vec![Arch::AArch64]));
+        assert!(!options.has_plugin("firmware"));
+
+        let options = BuildOptions::builder()
+            .targets(&[PlatformTarget::Deb])
+            .plugins(&["firmware"])
+            .build();
+        assert!(options.has_plugin("firmware"));
+        assert!(!options.has_plugin("other"));
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::process::Command;
+
+use super::config::{BuildConfig, BuildTool};
+use crate::base::runner::Runner;
+use crate::error::{Error, ErrorKind};
+
+/// Compile rust project for `triple` target.
+pub fn cargo_build(build_conf: &BuildConfig, triple: &str, runner: &Runner) -> Result<(), Error> {
+    log::info!(
+        "cargo_build() tool: {}, triple: {}",
+        build_conf.tool,
+        triple
+    );
+
+    let mut cmd = match build_conf.tool {
+        BuildTool::Cargo => {
+            let mut cmd = Command::new("cargo");
+            cmd.arg("build");
+            cmd
+        }
+        BuildTool::Cross => {
+            let mut cmd = Command::new("cross");
+            cmd.arg("build");
+            cmd
+        }
+        BuildTool::Zigbuild => {
+            let mut cmd = Command::new("cargo");
+            cmd.arg("zigbuild");
+            cmd
+        }
+    };
+
+    cmd.arg("--target")
+        .arg(triple)
+        .arg("--target-dir")
+        .arg(&build_conf.target_dir);
+    if let Some(manifest_path) = build_conf.manifest_path.as_ref() {
+        cmd.arg("--manifest-path").arg(manifest_path);
+    }
+    match build_conf.profile.as_str() {
+        "dev" => (),
+        "release" => {
+            cmd.arg("--release");
+        }
+        profile => {
+            cmd.arg("--profile").arg(profile);
+        }
+    }
+    cmd.args(&build_conf.args);
+
+    runner.run(&mut cmd, ErrorKind::CargoBuildError)?;
+    Ok(())
+}
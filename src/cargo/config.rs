@@ -0,0 +1,264 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+use crate::base::{deserialize_named, Arch, NamedEnum, PlatformTarget};
+
+/// Config of `[build]` section, used to compile rust project before packaging.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildConfig {
+    /// Tool used to compile the project.
+    ///
+    /// Default is `cargo`.
+    #[serde(default = "default_tool")]
+    pub tool: BuildTool,
+
+    /// String - Cargo profile name, like `release`, `dev` or any custom profile.
+    ///
+    /// Default is `release`.
+    #[serde(default = "default_profile")]
+    pub profile: String,
+
+    /// String - Path to `Cargo.toml` of the project.
+    pub manifest_path: Option<String>,
+
+    /// String - Directory for all generated artifacts.
+    ///
+    /// Default is `target`.
+    #[serde(default = "default_target_dir")]
+    pub target_dir: String,
+
+    /// Extra arguments passed to build command, like `["--features", "foo"]`.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Rust target triples used for linux packages.
+    #[serde(default)]
+    pub linux: TargetTriples,
+
+    /// Rust target triples used for windows packages.
+    #[serde(default)]
+    pub windows: TargetTriples,
+
+    /// Rust target triples used for macos packages.
+    #[serde(default)]
+    pub macos: TargetTriples,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            tool: default_tool(),
+            profile: default_profile(),
+            manifest_path: None,
+            target_dir: default_target_dir(),
+            args: Vec::new(),
+            linux: TargetTriples::default(),
+            windows: TargetTriples::default(),
+            macos: TargetTriples::default(),
+        }
+    }
+}
+
+const fn default_tool() -> BuildTool {
+    BuildTool::Cargo
+}
+
+fn default_profile() -> String {
+    "release".to_string()
+}
+
+fn default_target_dir() -> String {
+    "target".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum BuildTool {
+    /// Run `cargo build`.
+    Cargo,
+
+    /// Run `cross build`, see <https://github.com/cross-rs/cross>.
+    Cross,
+
+    /// Run `cargo zigbuild`, see <https://github.com/rust-cross/cargo-zigbuild>.
+    Zigbuild,
+}
+
+impl NamedEnum for BuildTool {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::Cargo, "cargo"),
+        (Self::Cross, "cross"),
+        (Self::Zigbuild, "cargo-zigbuild"),
+        (Self::Zigbuild, "zigbuild"),
+    ];
+}
+
+impl fmt::Display for BuildTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for BuildTool {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_named(deserializer)
+    }
+}
+
+impl JsonSchema for BuildTool {
+    fn schema_name() -> String {
+        "BuildTool".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        Self::schema("Tool used to compile rust project")
+    }
+}
+
+/// Maps each architecture to a rust target triple.
+///
+/// Triples not set here are guessed from architecture and OS.
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetTriples {
+    pub x86: Option<String>,
+    pub x86_64: Option<String>,
+    pub aarch64: Option<String>,
+    pub mips64: Option<String>,
+}
+
+impl TargetTriples {
+    #[must_use]
+    pub const fn get(&self, arch: Arch) -> Option<&String> {
+        match arch {
+            Arch::X86 => self.x86.as_ref(),
+            Arch::X86_64 => self.x86_64.as_ref(),
+            Arch::AArch64 => self.aarch64.as_ref(),
+            Arch::Mips64 => self.mips64.as_ref(),
+        }
+    }
+}
+
+const fn default_linux_triple(arch: Arch) -> &'static str {
+    match arch {
+        Arch::X86 => "i686-unknown-linux-gnu",
+        Arch::X86_64 => "x86_64-unknown-linux-gnu",
+        Arch::AArch64 => "aarch64-unknown-linux-gnu",
+        Arch::Mips64 => "mips64-unknown-linux-gnuabi64",
+    }
+}
+
+const fn default_windows_triple(arch: Arch) -> Option<&'static str> {
+    match arch {
+        Arch::X86 => Some("i686-pc-windows-gnu"),
+        Arch::X86_64 => Some("x86_64-pc-windows-gnu"),
+        Arch::AArch64 => Some("aarch64-pc-windows-msvc"),
+        Arch::Mips64 => None,
+    }
+}
+
+const fn default_macos_triple(arch: Arch) -> Option<&'static str> {
+    match arch {
+        Arch::X86_64 => Some("x86_64-apple-darwin"),
+        Arch::AArch64 => Some("aarch64-apple-darwin"),
+        Arch::X86 | Arch::Mips64 => None,
+    }
+}
+
+impl BuildConfig {
+    /// Get rust target triple of `arch` for packages of `target` type.
+    #[must_use]
+    pub fn triple(&self, arch: Arch, target: PlatformTarget) -> Option<String> {
+        match target {
+            PlatformTarget::Nsis => self
+                .windows
+                .get(arch)
+                .cloned()
+                .or_else(|| default_windows_triple(arch).map(ToString::to_string)),
+            PlatformTarget::App | PlatformTarget::Dmg | PlatformTarget::MacosPkg => self
+                .macos
+                .get(arch)
+                .cloned()
+                .or_else(|| default_macos_triple(arch).map(ToString::to_string)),
+            _ => self
+                .linux
+                .get(arch)
+                .cloned()
+                .or_else(|| Some(default_linux_triple(arch).to_string())),
+        }
+    }
+
+    /// Name of folder in which cargo places artifacts of current profile.
+    #[must_use]
+    pub fn profile_dir(&self) -> &str {
+        match self.profile.as_str() {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            profile => profile,
+        }
+    }
+
+    /// Get output directory of `triple`, like `target/x86_64-pc-windows-gnu/release`.
+    #[must_use]
+    pub fn output_dir(&self, triple: &str) -> String {
+        // cargo-zigbuild accepts glibc version suffix, like `x86_64-unknown-linux-gnu.2.17`,
+        // which is not part of output directory.
+        let triple = if self.tool == BuildTool::Zigbuild {
+            triple
+                .split_once('.')
+                .map_or(triple, |(triple, _glibc)| triple)
+        } else {
+            triple
+        };
+        format!("{}/{}/{}", self.target_dir, triple, self.profile_dir())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triple() {
+        let mut conf = BuildConfig::default();
+        assert_eq!(
+            conf.triple(Arch::X86_64, PlatformTarget::Deb).as_deref(),
+            Some("x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(
+            conf.triple(Arch::X86, PlatformTarget::Nsis).as_deref(),
+            Some("i686-pc-windows-gnu")
+        );
+        assert_eq!(conf.triple(Arch::Mips64, PlatformTarget::Nsis), None);
+
+        conf.linux.aarch64 = Some("aarch64-unknown-linux-musl".to_string());
+        assert_eq!(
+            conf.triple(Arch::AArch64, PlatformTarget::AppImage)
+                .as_deref(),
+            Some("aarch64-unknown-linux-musl")
+        );
+    }
+
+    #[test]
+    fn test_output_dir() {
+        let mut conf = BuildConfig::default();
+        assert_eq!(
+            conf.output_dir("x86_64-pc-windows-gnu"),
+            "target/x86_64-pc-windows-gnu/release"
+        );
+
+        conf.profile = "dev".to_string();
+        conf.tool = BuildTool::Zigbuild;
+        assert_eq!(
+            conf.output_dir("x86_64-unknown-linux-gnu.2.17"),
+            "target/x86_64-unknown-linux-gnu/debug"
+        );
+    }
+}
@@ -0,0 +1,1585 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::schema::{Schema, SchemaObject, SingleOrVec};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::apk::validate_apk_version;
+use crate::app_image::{is_valid_env_name, validate_update_information, AppImageConfig, Framework};
+use crate::appstream::validate_appstream;
+use crate::base::fileset::FileSet;
+use crate::base::{Arch, NamedEnum, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, LinuxConfig};
+use crate::desktop::validate_desktop;
+use crate::download::read_task_list;
+use crate::error::{Error, ErrorKind};
+use crate::flatpak::{validate_app_id, validate_flatpak};
+use crate::macos::{validate_bundle_id, validate_bundle_version};
+use crate::schema::config_schema;
+use crate::snap::{snap_arch, validate_snap, validate_snap_name, validate_snap_version};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in config file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Dotted key path of the problematic property, like `windows.nsis.installer_icon`.
+    pub key: String,
+
+    pub message: String,
+
+    /// Line number, starts from 1.
+    pub line: Option<usize>,
+
+    /// Column number, starts from 1.
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    /// Format this diagnostic as `file:line:column: severity: message`.
+    #[must_use]
+    pub fn render(&self, file: &str) -> String {
+        let location = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(line), None) => format!("{}:{}", file, line),
+            _ => file.to_string(),
+        };
+        if self.key.is_empty() {
+            format!("{}: {}: {}", location, self.severity, self.message)
+        } else {
+            format!(
+                "{}: {}: `{}` {}",
+                location, self.severity, self.key, self.message
+            )
+        }
+    }
+}
+
+/// A problem found by validator of a config section, without location.
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+
+    /// Property name relative to the section, like `categories`.
+    pub key: &'static str,
+
+    pub message: String,
+}
+
+impl Issue {
+    #[must_use]
+    pub const fn error(key: &'static str, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            key,
+            message,
+        }
+    }
+
+    #[must_use]
+    pub const fn warning(key: &'static str, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            key,
+            message,
+        }
+    }
+}
+
+/// Returns error if `issues` of config `section` contain errors.
+pub fn reject_errors(section: &str, issues: &[Issue]) -> Result<(), Error> {
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| format!("`{}.{}` {}", section, issue.key, issue.message))
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Invalid config: {}", errors.join(", ")),
+        ))
+    }
+}
+
+/// Position of a key or table header in toml file.
+struct KeyPosition {
+    path: String,
+    line: usize,
+    column: usize,
+    is_table: bool,
+}
+
+/// Key positions of toml file.
+///
+/// Keys are located line by line, without a full toml parser, so:
+/// - Keys of `[[array.tables]]` are located in the first table which contains them.
+/// - Dotted keys with spaces or quoted parts, like `a . b = 1`, are not located.
+/// - Lines inside multi-line strings are skipped.
+///
+/// Keys which are not located fallback to their nearest located parent.
+struct SourceMap<'a> {
+    lines: Vec<&'a str>,
+    keys: Vec<KeyPosition>,
+}
+
+impl<'a> SourceMap<'a> {
+    fn new(content: &'a str) -> Self {
+        let lines = content.lines().collect::<Vec<_>>();
+        let mut keys = Vec::new();
+        let mut table = String::new();
+        let mut multiline_delimiter: Option<&str> = None;
+        for (index, line) in lines.iter().enumerate() {
+            if let Some(delimiter) = multiline_delimiter {
+                if line.contains(delimiter) {
+                    multiline_delimiter = None;
+                }
+                continue;
+            }
+            multiline_delimiter = ["\"\"\"", "'''"]
+                .into_iter()
+                .find(|delimiter| line.matches(delimiter).count() % 2 == 1);
+
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+            if trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed.starts_with('[') {
+                table = trimmed
+                    .trim_start_matches('[')
+                    .split(']')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                keys.push(KeyPosition {
+                    path: table.clone(),
+                    line: index + 1,
+                    column: indent + 1,
+                    is_table: true,
+                });
+            } else if let Some((key, _value)) = trimmed.split_once('=') {
+                let key = key.trim().trim_matches('"');
+                if key.is_empty() || key.contains(' ') || key.contains('{') {
+                    continue;
+                }
+                let path = if table.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", table, key)
+                };
+                keys.push(KeyPosition {
+                    path,
+                    line: index + 1,
+                    column: indent + 1,
+                    is_table: false,
+                });
+            }
+        }
+        Self { lines, keys }
+    }
+
+    fn find(&self, path: &str) -> Option<&KeyPosition> {
+        self.keys.iter().find(|key| key.path == path)
+    }
+
+    /// Get position of key path, fallback to position of its parent.
+    fn key(&self, path: &str) -> (Option<usize>, Option<usize>) {
+        let mut path = path;
+        loop {
+            if let Some(key) = self.find(path) {
+                return (Some(key.line), Some(key.column));
+            }
+            match path.rsplit_once('.') {
+                Some((parent, _key)) => path = parent,
+                None => return (None, None),
+            }
+        }
+    }
+
+    /// Get line range of `path`, starts from line of the key, or header of its
+    /// table, and ends before the next table header.
+    fn range(&self, path: &str) -> (usize, usize) {
+        let table = self
+            .keys
+            .iter()
+            .filter(|key| {
+                key.is_table && (path == key.path || path.starts_with(&format!("{}.", key.path)))
+            })
+            .max_by_key(|key| key.path.len());
+        let start = table.map_or(1, |table| table.line);
+        let end = self
+            .keys
+            .iter()
+            .find(|key| key.is_table && key.line > start)
+            .map_or(self.lines.len() + 1, |key| key.line);
+        let start = self
+            .keys
+            .iter()
+            .find(|key| key.path == path && key.line >= start && key.line < end)
+            .map_or(start, |key| key.line);
+        (start, end)
+    }
+
+    /// Get position of a quoted string value, searched in lines of `path`,
+    /// fallback to position of `path`.
+    fn value(&self, path: &str, value: &str) -> (Option<usize>, Option<usize>) {
+        let quoted = format!("{:?}", value);
+        let (start, end) = self.range(path);
+        for line in start..end {
+            if let Some(column) = self.lines[line - 1].find(&quoted) {
+                return (Some(line), Some(column + 1));
+            }
+        }
+        self.key(path)
+    }
+
+    /// Get position of `key` in `table`, which may be in an inline table.
+    fn table_key(&self, table: &str, key: &str) -> (Option<usize>, Option<usize>) {
+        let path = format!("{}.{}", table, key);
+        if let Some(key) = self.find(&path) {
+            return (Some(key.line), Some(key.column));
+        }
+        let (start, end) = self.range(table);
+        for line in start..end {
+            let text = self.lines[line - 1];
+            for (column, _) in text.match_indices(key) {
+                let before = text[..column].trim_end();
+                let after = text[column + key.len()..].trim_start();
+                let is_key = (before.is_empty() || before.ends_with('{') || before.ends_with(','))
+                    && after.starts_with('=');
+                if is_key {
+                    return (Some(line), Some(column + 1));
+                }
+            }
+        }
+        self.key(table)
+    }
+}
+
+struct Checker<'a> {
+    source_map: SourceMap<'a>,
+
+    /// Only targets selected in build options are checked, or all targets if not set.
+    options: Option<&'a BuildOptions>,
+
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn is_selected(&self, target: PlatformTarget) -> bool {
+        self.options
+            .is_none_or(|options| options.targets.contains(&target))
+    }
+
+    fn any_selected(&self, targets: &[PlatformTarget]) -> bool {
+        targets.iter().any(|target| self.is_selected(*target))
+    }
+
+    fn push(&mut self, severity: Severity, key: &str, message: String) {
+        let (line, column) = self.source_map.key(key);
+        self.diagnostics.push(Diagnostic {
+            severity,
+            key: key.to_string(),
+            message,
+            line,
+            column,
+        });
+    }
+
+    fn push_value(&mut self, severity: Severity, key: &str, value: &str, message: String) {
+        let (line, column) = self.source_map.value(key, value);
+        self.diagnostics.push(Diagnostic {
+            severity,
+            key: key.to_string(),
+            message,
+            line,
+            column,
+        });
+    }
+
+    /// Check that file at `path`, which is relative to current directory, exists.
+    fn check_file_exists(&mut self, key: &str, path: Option<&String>) {
+        if let Some(path) = path {
+            if !Path::new(path).exists() {
+                self.push_value(
+                    Severity::Error,
+                    key,
+                    path,
+                    format!("refers to file {:?} which does not exist", path),
+                );
+            }
+        }
+    }
+
+    /// Same as `check_file_exists()`, but `path` is relative to `src_dir`
+    /// and may contain macros.
+    fn check_src_file_exists(&mut self, key: &str, conf: &Config, path: Option<&String>) {
+        if let Some(path) = path {
+            let src_file = Path::new(&conf.metadata.src_dir).join(path);
+            if !path.contains("${") && !src_file.exists() {
+                self.push_value(
+                    Severity::Error,
+                    key,
+                    path,
+                    format!("refers to file {:?} which does not exist", src_file),
+                );
+            }
+        }
+    }
+
+    fn check_filesets(
+        &mut self,
+        key: &str,
+        conf: &Config,
+        files: Option<&Vec<FileSet>>,
+        fallback: Option<&Vec<FileSet>>,
+        target: PlatformTarget,
+    ) {
+        let (key, files) = match (files, fallback) {
+            (Some(files), _) => (key.to_string(), files),
+            (None, Some(files)) => {
+                // Fallback to `files` of `linux` or `windows` section.
+                let section = match key.split('.').next().unwrap_or_default() {
+                    "plugins" => "linux",
+                    section => section,
+                };
+                (format!("{}.files", section), files)
+            }
+            (None, None) => {
+                self.push(
+                    Severity::Error,
+                    key,
+                    format!("is required by {:?} target but not set", target),
+                );
+                return;
+            }
+        };
+
+        for file in files {
+            // Files generated by macros, like `${target_dir}`, do not exist until build.
+            if file.from.contains("${") {
+                continue;
+            }
+            let pattern = format!("{}/{}", conf.metadata.src_dir, file.from);
+            let matched = glob::glob(&pattern).is_ok_and(|mut paths| paths.next().is_some());
+            if !matched {
+                self.push_value(
+                    Severity::Warning,
+                    &key,
+                    &file.from,
+                    format!("no file is matched with pattern {:?}", pattern),
+                );
+            }
+        }
+    }
+
+    fn check_metadata(&mut self, conf: &Config) {
+        let metadata = &conf.metadata;
+        self.check_src_file_exists(
+            "metadata.license_file",
+            conf,
+            metadata.license_file.as_ref(),
+        );
+        if metadata.name.is_empty() {
+            self.push(Severity::Error, "metadata.name", "is empty".to_string());
+        }
+        if metadata.version.is_empty() {
+            self.push(Severity::Error, "metadata.version", "is empty".to_string());
+        }
+    }
+
+    fn check_linux(&mut self, conf: &Config) {
+        let Some(linux_conf) = conf.linux.as_ref() else {
+            return;
+        };
+        let version = &conf.metadata.version;
+
+        for target in &linux_conf.targets {
+            if !self.is_selected(*target) {
+                continue;
+            }
+            match target {
+                PlatformTarget::Deb => {
+                    if let Err(message) = validate_deb_version(version) {
+                        self.push(Severity::Error, "metadata.version", message);
+                    }
+                    self.check_filesets(
+                        "linux.deb.files",
+                        conf,
+                        linux_conf.deb.files.as_ref(),
+                        linux_conf.files.as_ref(),
+                        *target,
+                    );
+                }
+                PlatformTarget::Rpm => {
+                    if let Err(message) = validate_rpm_version(version) {
+                        self.push(Severity::Error, "metadata.version", message);
+                    }
+                    self.check_filesets(
+                        "linux.rpm.files",
+                        conf,
+                        linux_conf.rpm.files.as_ref(),
+                        linux_conf.files.as_ref(),
+                        *target,
+                    );
+                }
+                PlatformTarget::AppImage => self.check_app_image(conf, linux_conf),
+                PlatformTarget::Flatpak => self.check_flatpak(conf, linux_conf),
+                PlatformTarget::Snap => self.check_snap(conf, linux_conf),
+                PlatformTarget::Apk => self.check_apk(conf, linux_conf),
+                PlatformTarget::Ipk => self.check_ipk(conf, linux_conf),
+                PlatformTarget::Tarball => self.check_filesets(
+                    "linux.tarball.files",
+                    conf,
+                    linux_conf.tarball.files.as_ref(),
+                    linux_conf.files.as_ref(),
+                    *target,
+                ),
+                PlatformTarget::App | PlatformTarget::Dmg | PlatformTarget::MacosPkg => {
+                    self.push(
+                        Severity::Error,
+                        "linux.targets",
+                        format!(
+                            "contains `{}`, which is only available in `macos.targets`",
+                            target
+                        ),
+                    );
+                }
+                PlatformTarget::Nsis => {
+                    self.push(
+                        Severity::Error,
+                        "linux.targets",
+                        "contains `nsis`, which is only available in `windows.targets`".to_string(),
+                    );
+                }
+                PlatformTarget::Plugin => {
+                    self.push(
+                        Severity::Error,
+                        "linux.targets",
+                        "contains `plugin`, add `[plugins.<name>]` section instead".to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_app_image(&mut self, conf: &Config, linux_conf: &LinuxConfig) {
+        self.check_filesets(
+            "linux.app_image.files",
+            conf,
+            linux_conf.app_image.files.as_ref(),
+            linux_conf.files.as_ref(),
+            PlatformTarget::AppImage,
+        );
+        if let Some(info) = linux_conf.app_image.update_information.as_ref() {
+            if let Err(message) = validate_update_information(info) {
+                self.push(
+                    Severity::Error,
+                    "linux.app_image.update_information",
+                    message,
+                );
+            }
+        }
+        if linux_conf.app_image.sign && conf.sign.is_none() {
+            self.push(
+                Severity::Error,
+                "linux.app_image.sign",
+                "is enabled but `[sign]` section is not set".to_string(),
+            );
+        }
+        self.check_frameworks(&linux_conf.app_image);
+        for name in linux_conf.app_image.env.keys() {
+            if !is_valid_env_name(name) {
+                self.push(
+                    Severity::Error,
+                    "linux.app_image.env",
+                    format!("{:?} is not a valid environment variable name", name),
+                );
+            }
+        }
+    }
+
+    fn check_flatpak(&mut self, conf: &Config, linux_conf: &LinuxConfig) {
+        self.check_filesets(
+            "linux.flatpak.files",
+            conf,
+            linux_conf.flatpak.files.as_ref(),
+            linux_conf.files.as_ref(),
+            PlatformTarget::Flatpak,
+        );
+        if let Err(message) = validate_app_id(&conf.metadata.app_id) {
+            self.push(Severity::Error, "metadata.app_id", message);
+        }
+        for issue in validate_flatpak(&linux_conf.flatpak) {
+            self.push(
+                issue.severity,
+                &format!("linux.flatpak.{}", issue.key),
+                issue.message,
+            );
+        }
+    }
+
+    fn check_snap(&mut self, conf: &Config, linux_conf: &LinuxConfig) {
+        self.check_filesets(
+            "linux.snap.files",
+            conf,
+            linux_conf.snap.files.as_ref(),
+            linux_conf.files.as_ref(),
+            PlatformTarget::Snap,
+        );
+        if let Err(message) = validate_snap_name(&conf.metadata.name) {
+            self.push(Severity::Error, "metadata.name", message);
+        }
+        if let Err(message) = validate_snap_version(&conf.metadata.version) {
+            self.push(Severity::Error, "metadata.version", message);
+        }
+        for arch in &linux_conf.arch {
+            if snap_arch(*arch).is_none() {
+                self.push(
+                    Severity::Error,
+                    "linux.arch",
+                    format!("{} is not supported by snap", arch),
+                );
+            }
+        }
+        for issue in validate_snap(conf, &linux_conf.snap) {
+            self.push(
+                issue.severity,
+                &format!("linux.snap.{}", issue.key),
+                issue.message,
+            );
+        }
+    }
+
+    fn check_apk(&mut self, conf: &Config, linux_conf: &LinuxConfig) {
+        self.check_filesets(
+            "linux.apk.files",
+            conf,
+            linux_conf.apk.files.as_ref(),
+            linux_conf.files.as_ref(),
+            PlatformTarget::Apk,
+        );
+        if let Err(message) = validate_apk_version(&conf.metadata.version) {
+            self.push(Severity::Error, "metadata.version", message);
+        }
+        if linux_conf.apk.sign {
+            match conf.sign.as_ref() {
+                None => self.push(
+                    Severity::Error,
+                    "linux.apk.sign",
+                    "is enabled but `[sign]` section is not set".to_string(),
+                ),
+                Some(sign_conf) if sign_conf.abuild_key.is_none() => self.push(
+                    Severity::Error,
+                    "sign.abuild_key",
+                    "is required by `linux.apk.sign`".to_string(),
+                ),
+                Some(sign_conf) => {
+                    self.check_file_exists("sign.abuild_key", sign_conf.abuild_key.as_ref());
+                }
+            }
+        }
+    }
+
+    fn check_ipk(&mut self, conf: &Config, linux_conf: &LinuxConfig) {
+        let ipk_conf = &linux_conf.ipk;
+        self.check_filesets(
+            "linux.ipk.files",
+            conf,
+            ipk_conf.files.as_ref(),
+            linux_conf.files.as_ref(),
+            PlatformTarget::Ipk,
+        );
+        if let Err(message) = validate_deb_version(&conf.metadata.version) {
+            self.push(Severity::Error, "metadata.version", message);
+        }
+        for conffile in &ipk_conf.conffiles {
+            if !conffile.starts_with('/') {
+                self.push_value(
+                    Severity::Error,
+                    "linux.ipk.conffiles",
+                    conffile,
+                    format!("{:?} is not an absolute path", conffile),
+                );
+            }
+        }
+        for (name, path) in ipk_conf.scripts() {
+            self.check_file_exists(&format!("linux.ipk.{}", name), Some(&path.to_string()));
+        }
+        for name in ipk_conf.arch_names.keys() {
+            if Arch::from_str(name).is_err() {
+                self.push(
+                    Severity::Error,
+                    "linux.ipk.arch_names",
+                    format!("{:?} is not a valid arch", name),
+                );
+            } else if !linux_conf.arch.iter().any(|arch| arch.to_string() == *name) {
+                self.push(
+                    Severity::Warning,
+                    "linux.ipk.arch_names",
+                    format!("{:?} is not in `linux.arch`", name),
+                );
+            }
+        }
+    }
+
+    fn check_frameworks(&mut self, app_image_conf: &AppImageConfig) {
+        if app_image_conf.gstreamer_plugins.is_some()
+            && !app_image_conf
+                .bundle_frameworks
+                .contains(&Framework::Gstreamer)
+        {
+            self.push(
+                Severity::Warning,
+                "linux.app_image.gstreamer_plugins",
+                "`gstreamer` is not in `bundle_frameworks`".to_string(),
+            );
+        }
+        for (name, path) in &app_image_conf.framework_paths {
+            // Plugin directories are looked up with canonical names.
+            let framework = Framework::from_name(name).filter(|framework| framework.name() == name);
+            if framework.is_none() {
+                self.push(
+                    Severity::Error,
+                    "linux.app_image.framework_paths",
+                    format!(
+                        "{:?} is not a framework, expected one of {}",
+                        name,
+                        Framework::quoted_names()
+                    ),
+                );
+            } else if !app_image_conf
+                .bundle_frameworks
+                .iter()
+                .any(|framework| framework.name() == name)
+            {
+                self.push(
+                    Severity::Warning,
+                    "linux.app_image.framework_paths",
+                    format!("{:?} is not in `bundle_frameworks`", name),
+                );
+            } else if !Path::new(path).is_dir() {
+                self.push_value(
+                    Severity::Error,
+                    "linux.app_image.framework_paths",
+                    path,
+                    "is not a directory".to_string(),
+                );
+            }
+        }
+    }
+
+    fn check_desktop(&mut self, conf: &Config) {
+        let Some(linux_conf) = conf.linux.as_ref() else {
+            return;
+        };
+        let Some(desktop_conf) = linux_conf.desktop.as_ref() else {
+            return;
+        };
+        if !self.any_selected(&linux_conf.targets) {
+            return;
+        }
+        for issue in validate_desktop(desktop_conf) {
+            self.push(
+                issue.severity,
+                &format!("linux.desktop.{}", issue.key),
+                issue.message,
+            );
+        }
+        self.check_src_file_exists("linux.desktop.icon", conf, desktop_conf.icon.as_ref());
+    }
+
+    fn check_appstream(&mut self, conf: &Config) {
+        let Some(linux_conf) = conf.linux.as_ref() else {
+            return;
+        };
+        let Some(appstream_conf) = linux_conf.appstream.as_ref() else {
+            return;
+        };
+        if !self.any_selected(&linux_conf.targets) {
+            return;
+        }
+        for issue in validate_appstream(conf, appstream_conf) {
+            self.push(
+                issue.severity,
+                &format!("linux.appstream.{}", issue.key),
+                issue.message,
+            );
+        }
+        if conf.metadata.app_id.split('.').count() < 3 {
+            self.push(
+                Severity::Warning,
+                "metadata.app_id",
+                "shall be reverse domain name with at least 3 parts, like `org.example.app`"
+                    .to_string(),
+            );
+        }
+    }
+
+    fn check_plugins(&mut self, conf: &Config) {
+        for (name, plugin_conf) in &conf.plugins {
+            if !self.options.is_none_or(|options| options.has_plugin(name)) {
+                continue;
+            }
+            let key = format!("plugins.{}", name);
+            let is_valid_name = name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !is_valid_name {
+                self.push(
+                    Severity::Error,
+                    &key,
+                    "name shall only contain ascii letters, digits, `-` and `_`".to_string(),
+                );
+            }
+            if PlatformTarget::from_str(name).is_ok() {
+                self.push(
+                    Severity::Error,
+                    &key,
+                    format!("name conflicts with builtin target `{}`", name),
+                );
+            }
+            // Command without path separator is searched in $PATH.
+            let command = plugin_conf
+                .command
+                .as_ref()
+                .filter(|command| command.contains('/'));
+            self.check_file_exists(&format!("{}.command", key), command);
+            self.check_filesets(
+                &format!("{}.files", key),
+                conf,
+                plugin_conf.files.as_ref(),
+                conf.linux.as_ref().and_then(|linux| linux.files.as_ref()),
+                PlatformTarget::Plugin,
+            );
+        }
+    }
+
+    /// Pinned versions may be defined in custom download list, so only warn here.
+    fn check_tools(&mut self, conf: &Config) {
+        let Ok(task_list) = read_task_list(None) else {
+            return;
+        };
+        for (name, version) in &conf.tools {
+            let key = format!("tools.{}", name);
+            if task_list.default_version(name).is_none() {
+                self.push(
+                    Severity::Warning,
+                    &key,
+                    format!("`{}` not found in builtin download list", name),
+                );
+            } else if !task_list
+                .tool
+                .iter()
+                .any(|task| &task.name == name && &task.version == version)
+            {
+                self.push(
+                    Severity::Warning,
+                    &key,
+                    format!(
+                        "version {} of `{}` not found in builtin download list",
+                        version, name
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_macos(&mut self, conf: &Config) {
+        let Some(macos_conf) = conf.macos.as_ref() else {
+            return;
+        };
+
+        let targets = macos_conf
+            .targets
+            .iter()
+            .copied()
+            .filter(|target| self.is_selected(*target))
+            .collect::<Vec<_>>();
+        for target in &targets {
+            match target {
+                PlatformTarget::App | PlatformTarget::Dmg | PlatformTarget::MacosPkg => {}
+                PlatformTarget::Plugin => self.push(
+                    Severity::Error,
+                    "macos.targets",
+                    "contains `plugin`, add `[plugins.<name>]` section instead".to_string(),
+                ),
+                PlatformTarget::Nsis => self.push(
+                    Severity::Error,
+                    "macos.targets",
+                    "contains `nsis`, which is only available in `windows.targets`".to_string(),
+                ),
+                _ => self.push(
+                    Severity::Error,
+                    "macos.targets",
+                    format!(
+                        "contains `{}`, which is only available in `linux.targets`",
+                        target
+                    ),
+                ),
+            }
+        }
+        if targets.is_empty() {
+            return;
+        }
+
+        for arch in &macos_conf.arch {
+            if !matches!(arch, Arch::X86_64 | Arch::AArch64) {
+                self.push(
+                    Severity::Error,
+                    "macos.arch",
+                    format!("{} is not supported by macos", arch),
+                );
+            }
+        }
+        self.check_filesets(
+            "macos.files",
+            conf,
+            macos_conf.files.as_ref(),
+            None,
+            targets[0],
+        );
+        self.check_src_file_exists("macos.icon", conf, macos_conf.icon.as_ref());
+        if targets.contains(&PlatformTarget::MacosPkg)
+            && !macos_conf.macos_pkg.install_location.starts_with('/')
+        {
+            self.push(
+                Severity::Error,
+                "macos.macos_pkg.install_location",
+                "shall be an absolute path".to_string(),
+            );
+        }
+        if let Err(message) = validate_bundle_id(&conf.metadata.app_id) {
+            self.push(Severity::Error, "metadata.app_id", message);
+        }
+        let build_id = &conf.metadata.build_id;
+        if !build_id.contains("${") {
+            if let Err(message) = validate_bundle_version(build_id) {
+                self.push(Severity::Warning, "metadata.build_id", message);
+            }
+        }
+    }
+
+    fn check_windows(&mut self, conf: &Config) {
+        let Some(windows_conf) = conf.windows.as_ref() else {
+            return;
+        };
+
+        for target in &windows_conf.targets {
+            if !self.is_selected(*target) {
+                continue;
+            }
+            if *target == PlatformTarget::Plugin {
+                self.push(
+                    Severity::Error,
+                    "windows.targets",
+                    "contains `plugin`, add `[plugins.<name>]` section instead".to_string(),
+                );
+                continue;
+            }
+            if *target != PlatformTarget::Nsis {
+                let section = if matches!(
+                    target,
+                    PlatformTarget::App | PlatformTarget::Dmg | PlatformTarget::MacosPkg
+                ) {
+                    "macos"
+                } else {
+                    "linux"
+                };
+                self.push(
+                    Severity::Error,
+                    "windows.targets",
+                    format!(
+                        "contains {:?}, which is only available in `{}.targets`",
+                        target, section
+                    ),
+                );
+                continue;
+            }
+
+            let Some(nsis_conf) = windows_conf.nsis.as_ref() else {
+                self.push(
+                    Severity::Error,
+                    "windows.nsis",
+                    "is required by `nsis` target but not set".to_string(),
+                );
+                continue;
+            };
+            self.check_src_file_exists(
+                "windows.nsis.installer_icon",
+                conf,
+                Some(&nsis_conf.installer_icon),
+            );
+            self.check_src_file_exists(
+                "windows.nsis.uninstaller_icon",
+                conf,
+                Some(&nsis_conf.uninstaller_icon),
+            );
+            self.check_src_file_exists(
+                "windows.nsis.installer_header",
+                conf,
+                nsis_conf.installer_header.as_ref(),
+            );
+            self.check_src_file_exists(
+                "windows.nsis.installer_header_icon",
+                conf,
+                nsis_conf.installer_header_icon.as_ref(),
+            );
+            self.check_src_file_exists(
+                "windows.nsis.installer_sidebar",
+                conf,
+                nsis_conf.installer_sidebar.as_ref(),
+            );
+            self.check_src_file_exists(
+                "windows.nsis.uninstaller_sidebar",
+                conf,
+                nsis_conf.uninstaller_sidebar.as_ref(),
+            );
+            self.check_src_file_exists("windows.nsis.include", conf, nsis_conf.include.as_ref());
+            self.check_src_file_exists("windows.nsis.script", conf, nsis_conf.script.as_ref());
+            if nsis_conf.script.is_none() {
+                self.check_filesets(
+                    "windows.nsis.files",
+                    conf,
+                    nsis_conf.files.as_ref(),
+                    windows_conf.files.as_ref(),
+                    *target,
+                );
+            }
+            let build_id = &conf.metadata.build_id;
+            if !build_id.contains("${") {
+                let product_version = format!("{}.{}", conf.metadata.version, build_id);
+                if let Err(message) = validate_nsis_version(&product_version) {
+                    self.push(Severity::Warning, "metadata.build_id", message);
+                }
+            }
+        }
+    }
+}
+
+/// Convert toml parsing error to diagnostic.
+#[must_use]
+pub fn parse_error_diagnostic(err: &toml::de::Error) -> Diagnostic {
+    let message = err.to_string();
+    // Location is reported separately.
+    let message = message
+        .rfind(" at line ")
+        .map_or(message.as_str(), |index| &message[..index])
+        .to_string();
+    let (line, column) = err.line_col().map_or((None, None), |(line, column)| {
+        (Some(line + 1), Some(column + 1))
+    });
+    Diagnostic {
+        severity: Severity::Error,
+        key: String::new(),
+        message,
+        line,
+        column,
+    }
+}
+
+/// Collect schema objects of `schema`, following references and subschemas
+/// like `Option<T>`.
+fn schema_objects<'a>(
+    schema: &'a Schema,
+    definitions: &'a schemars::Map<String, Schema>,
+    objects: &mut Vec<&'a SchemaObject>,
+) {
+    let Schema::Object(object) = schema else {
+        return;
+    };
+    if let Some(reference) = object.reference.as_ref() {
+        if let Some(schema) = definitions.get(reference.trim_start_matches("#/definitions/")) {
+            schema_objects(schema, definitions, objects);
+        }
+    }
+    if let Some(subschemas) = object.subschemas.as_ref() {
+        let all_of = subschemas.all_of.iter().flatten();
+        let any_of = subschemas.any_of.iter().flatten();
+        let one_of = subschemas.one_of.iter().flatten();
+        for schema in all_of.chain(any_of).chain(one_of) {
+            schema_objects(schema, definitions, objects);
+        }
+    }
+    objects.push(object);
+}
+
+/// Remove keys of `value` which are not defined in `schema`.
+///
+/// Returns pairs of table path and key name, with expected key names.
+fn remove_unknown_keys(
+    value: &mut toml::Value,
+    schema: &Schema,
+    definitions: &schemars::Map<String, Schema>,
+    path: &str,
+    unknown: &mut Vec<(String, String, Vec<String>)>,
+) {
+    let mut objects = Vec::new();
+    schema_objects(schema, definitions, &mut objects);
+    match value {
+        toml::Value::Table(table) => {
+            let Some(validation) = objects.iter().find_map(|object| object.object.as_ref()) else {
+                return;
+            };
+            let mut unknown_keys = Vec::new();
+            for (key, value) in table.iter_mut() {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                let schema = validation
+                    .properties
+                    .get(key)
+                    .or(validation.additional_properties.as_deref());
+                match schema {
+                    Some(Schema::Bool(false)) => unknown_keys.push(key.clone()),
+                    Some(schema) => {
+                        remove_unknown_keys(value, schema, definitions, &key_path, unknown);
+                    }
+                    None => (),
+                }
+            }
+            for key in unknown_keys {
+                table.remove(&key);
+                let expected = validation.properties.keys().cloned().collect();
+                unknown.push((path.to_string(), key, expected));
+            }
+        }
+        toml::Value::Array(array) => {
+            let items = objects
+                .iter()
+                .filter_map(|object| object.array.as_ref())
+                .find_map(|validation| match validation.items.as_ref() {
+                    Some(SingleOrVec::Single(schema)) => Some(schema.as_ref()),
+                    _ => None,
+                });
+            if let Some(items) = items {
+                for value in array {
+                    remove_unknown_keys(value, items, definitions, path, unknown);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Report keys not defined in schema of config file, and remove them from `value`.
+fn check_unknown_keys(value: &mut toml::Value, source_map: &SourceMap) -> Vec<Diagnostic> {
+    let schema = config_schema();
+    let mut unknown = Vec::new();
+    remove_unknown_keys(
+        value,
+        &Schema::Object(schema.schema),
+        &schema.definitions,
+        "",
+        &mut unknown,
+    );
+    unknown
+        .into_iter()
+        .map(|(table, key, expected)| {
+            let (line, column) = if table.is_empty() {
+                source_map.key(&key)
+            } else {
+                source_map.table_key(&table, &key)
+            };
+            let expected = expected
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Diagnostic {
+                severity: Severity::Error,
+                key: if table.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", table, key)
+                },
+                message: format!("is unknown, expected one of {}", expected),
+                line,
+                column,
+            }
+        })
+        .collect()
+}
+
+/// Convert error of deserializing config from toml value to diagnostic,
+/// which is located with key path in error message.
+fn value_error_diagnostic(err: &toml::de::Error, source_map: &SourceMap) -> Diagnostic {
+    let message = err.to_string();
+    let (message, key) = message
+        .rsplit_once(" for key `")
+        .map_or((message.as_str(), ""), |(message, key)| {
+            (message, key.trim_end_matches('`'))
+        });
+    let variant = message
+        .strip_prefix("unknown variant `")
+        .and_then(|rest| rest.split('`').next());
+    let (line, column) = match variant {
+        Some(variant) if !key.is_empty() => source_map.value(key, variant),
+        _ => source_map.key(key),
+    };
+    Diagnostic {
+        severity: Severity::Error,
+        key: key.to_string(),
+        message: message.to_string(),
+        line,
+        column,
+    }
+}
+
+/// Remove key `path` from `value`, returns false if it is not found.
+fn remove_key(value: &mut toml::Value, path: &str) -> bool {
+    let (table, key) = path.rsplit_once('.').unwrap_or(("", path));
+    let mut value = value;
+    for part in table.split('.').filter(|part| !part.is_empty()) {
+        match value.get_mut(part) {
+            Some(child) => value = child,
+            None => return false,
+        }
+    }
+    value
+        .as_table_mut()
+        .and_then(|table| table.remove(key))
+        .is_some()
+}
+
+/// Deserialize config from `value`, all type errors are appended to `diagnostics`.
+///
+/// Key of each error is removed and deserialized again to find next error,
+/// until a required key is missing.
+fn deserialize_config(
+    mut value: toml::Value,
+    source_map: &SourceMap,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Config> {
+    let mut removed_keys = Vec::new();
+    loop {
+        let err = match value.clone().try_into::<Config>() {
+            Ok(conf) => return removed_keys.is_empty().then_some(conf),
+            Err(err) => err,
+        };
+        let diagnostic = value_error_diagnostic(&err, source_map);
+        if let Some(field) = diagnostic
+            .message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.split('`').next())
+        {
+            // Required key which is already reported and removed.
+            let path = if diagnostic.key.is_empty() {
+                field.to_string()
+            } else {
+                format!("{}.{}", diagnostic.key, field)
+            };
+            if !removed_keys.contains(&path) {
+                diagnostics.push(diagnostic);
+            }
+            return None;
+        }
+        if diagnostic.key.is_empty() || !remove_key(&mut value, &diagnostic.key) {
+            diagnostics.push(diagnostic);
+            return None;
+        }
+        removed_keys.push(diagnostic.key.clone());
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Check semantic problems in config.
+///
+/// `content` is source of config file, used to locate problematic properties.
+/// Only targets selected in `options` are checked, or all targets if it is `None`.
+#[must_use]
+pub fn check_config(
+    conf: &Config,
+    content: &str,
+    options: Option<&BuildOptions>,
+) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        source_map: SourceMap::new(content),
+        options,
+        diagnostics: Vec::new(),
+    };
+    checker.check_metadata(conf);
+    checker.check_linux(conf);
+    checker.check_desktop(conf);
+    checker.check_appstream(conf);
+    checker.check_macos(conf);
+    checker.check_windows(conf);
+    checker.check_plugins(conf);
+    checker.check_tools(conf);
+
+    let mut diagnostics = checker.diagnostics;
+    sort_diagnostics(&mut diagnostics);
+    diagnostics
+}
+
+fn sort_diagnostics(diagnostics: &mut Vec<Diagnostic>) {
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line.unwrap_or(usize::MAX));
+    // Fallback file sets are checked once per target.
+    diagnostics.dedup_by(|a, b| {
+        a.key == b.key && a.message == b.message && a.line == b.line && a.column == b.column
+    });
+}
+
+fn read_source(path: &str) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|err| {
+        Error::new(ErrorKind::IoError, "Failed to read config file")
+            .with_source(err)
+            .with_path(path)
+    })
+}
+
+/// Parse config, syntax errors, unknown keys and type errors are reported.
+fn parse_source(content: &str) -> (Option<Config>, Vec<Diagnostic>) {
+    let mut value = match toml::from_str::<toml::Value>(content) {
+        Ok(value) => value,
+        Err(err) => return (None, vec![parse_error_diagnostic(&err)]),
+    };
+
+    // Unknown keys are reported and removed, so that other problems are
+    // still checked.
+    let source_map = SourceMap::new(content);
+    let mut diagnostics = check_unknown_keys(&mut value, &source_map);
+    let conf = deserialize_config(value, &source_map, &mut diagnostics);
+    (conf, diagnostics)
+}
+
+/// Parse config file without semantic checks.
+///
+/// Returns parsed config if no syntax or type error found, and all diagnostics.
+pub fn parse_file(path: &str) -> Result<(Option<Config>, Vec<Diagnostic>), Error> {
+    let content = read_source(path)?;
+    let (conf, mut diagnostics) = parse_source(&content);
+    sort_diagnostics(&mut diagnostics);
+    Ok((conf, diagnostics))
+}
+
+/// Parse and check all targets in config file.
+///
+/// Returns parsed config if no syntax or type error found, and all diagnostics.
+pub fn check_file(path: &str) -> Result<(Option<Config>, Vec<Diagnostic>), Error> {
+    let content = read_source(path)?;
+    let (conf, mut diagnostics) = parse_source(&content);
+    if let Some(conf) = conf.as_ref() {
+        diagnostics.extend(check_config(conf, &content, None));
+    }
+    sort_diagnostics(&mut diagnostics);
+    Ok((conf, diagnostics))
+}
+
+/// Check targets selected in `options` of `conf`, which is read from file `path`.
+pub fn check_selected(
+    path: &str,
+    conf: &Config,
+    options: &BuildOptions,
+) -> Result<Vec<Diagnostic>, Error> {
+    let content = read_source(path)?;
+    Ok(check_config(conf, &content, Some(options)))
+}
+
+/// Debian version is `[epoch:]upstream_version[-debian_revision]`.
+fn validate_deb_version(version: &str) -> Result<(), String> {
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!(
+            "{:?} is invalid for deb package, it shall start with a digit",
+            version
+        ));
+    }
+    if let Some(c) = version
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !".+~-:".contains(*c))
+    {
+        return Err(format!(
+            "{:?} is invalid for deb package, character {:?} is not allowed",
+            version, c
+        ));
+    }
+    Ok(())
+}
+
+/// Rpm version shall not contain `-`, which separates version and release.
+fn validate_rpm_version(version: &str) -> Result<(), String> {
+    if let Some(c) = version
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !"._+~^".contains(*c))
+    {
+        return Err(format!(
+            "{:?} is invalid for rpm package, character {:?} is not allowed",
+            version, c
+        ));
+    }
+    Ok(())
+}
+
+/// `VIProductVersion` of nsis shall be in `X.X.X.X` format, each part is a 16-bit integer.
+fn validate_nsis_version(version: &str) -> Result<(), String> {
+    let parts = version.split('.').collect::<Vec<_>>();
+    if parts.len() != 4 || parts.iter().any(|part| part.parse::<u16>().is_err()) {
+        return Err(format!(
+            "nsis product version {:?} is not in `X.X.X.X` format, with each part in 0..65535",
+            version
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_version() {
+        assert!(validate_deb_version("1.0.0-1").is_ok());
+        assert!(validate_deb_version("v1.0").is_err());
+        assert!(validate_rpm_version("1.0.0").is_ok());
+        assert!(validate_rpm_version("1.0.0-1").is_err());
+        assert!(validate_nsis_version("1.0.0.12").is_ok());
+        assert!(validate_nsis_version("1.0.0.20210509").is_err());
+        assert!(validate_nsis_version("1.0.12").is_err());
+    }
+
+    #[test]
+    fn test_source_map() {
+        let content = r#"
+[metadata]
+name = "pifu"
+
+[windows.nsis]
+  installer_icon = "a.ico"
+  uninstaller_icon = "b.ico"
+
+[macos.app]
+icon = "b.ico"
+files = [
+  {from = "a.ico", to = "a.ico", mod = 1},
+]
+"#;
+        let source_map = SourceMap::new(content);
+        assert_eq!(source_map.key("metadata.name"), (Some(3), Some(1)));
+        assert_eq!(
+            source_map.key("windows.nsis.installer_icon"),
+            (Some(6), Some(3))
+        );
+        assert_eq!(
+            source_map.value("windows.nsis", "a.ico"),
+            (Some(6), Some(20))
+        );
+        // Values are searched in lines of the key.
+        assert_eq!(
+            source_map.value("macos.app.icon", "b.ico"),
+            (Some(10), Some(8))
+        );
+        assert_eq!(
+            source_map.value("macos.app.files", "a.ico"),
+            (Some(12), Some(11))
+        );
+        assert_eq!(
+            source_map.table_key("macos.app.files", "mod"),
+            (Some(12), Some(34))
+        );
+    }
+
+    #[test]
+    fn test_source_map_fallback() {
+        let content = r#"
+[metadata]
+description = """
+name = "fake"
+[fake]
+"""
+name = "pifu"
+
+[linux]
+deb.depends = ["libc6"]
+
+[[linux.appstream.releases]]
+version = "1.0.0"
+
+[[linux.appstream.releases]]
+version = "0.9.0"
+date = "2021-01-01"
+"#;
+        let source_map = SourceMap::new(content);
+        // Lines in multi-line strings are skipped.
+        assert_eq!(source_map.key("metadata.name"), (Some(7), Some(1)));
+        assert_eq!(source_map.key("fake"), (None, None));
+        assert_eq!(source_map.key("linux.deb.depends"), (Some(10), Some(1)));
+        // Keys of array tables are located in the first table containing them.
+        assert_eq!(
+            source_map.key("linux.appstream.releases.version"),
+            (Some(13), Some(1))
+        );
+        assert_eq!(
+            source_map.key("linux.appstream.releases.date"),
+            (Some(17), Some(1))
+        );
+        // Fallback to parent.
+        assert_eq!(
+            source_map.key("linux.appstream.releases.1.date"),
+            (Some(12), Some(1))
+        );
+        assert_eq!(source_map.key("linux.deb.files"), (Some(9), Some(1)));
+    }
+
+    #[test]
+    fn test_check_file() {
+        let content = r#"[metadata]
+name = "pifu"
+product_name = "Pifu"
+app_id = "org.biofan.pifu"
+description = ""
+homepage = ""
+author = ""
+version = "1.0.0"
+build_id = "1"
+license = "GPL-3.0"
+workdir = "target/pifu"
+src_dir = "."
+licence = "GPL-3.0"
+
+[linux]
+targets = ["deb"]
+files = [{from = "Cargo.toml", to = "Cargo.toml", mod = 1}]
+
+[linux.deb]
+depends = 3
+
+[plugins.deb]
+files = [{from = "Cargo.toml", to = "Cargo.toml"}]
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pifu.toml");
+        fs::write(&path, content).unwrap();
+        let path = path.to_string_lossy();
+        let (conf, diagnostics) = check_file(&path).unwrap();
+        assert!(conf.is_none());
+        let locations = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.key.as_str(), diagnostic.line, diagnostic.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            [
+                ("metadata.licence", Some(13), Some(1)),
+                ("linux.files.mod", Some(17), Some(51)),
+                ("linux.deb.depends", Some(20), Some(1)),
+            ]
+        );
+
+        // Semantic problems are still checked with unknown keys.
+        fs::write(&*path, content.replace("depends = 3", "")).unwrap();
+        let (conf, diagnostics) = check_file(&path).unwrap();
+        assert!(conf.is_some());
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[2].key, "plugins.deb");
+        assert_eq!(diagnostics[2].line, Some(22));
+    }
+
+    #[test]
+    fn test_check_selected_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("LICENSE"), "MIT").unwrap();
+        fs::write(dir.path().join("app.sh"), "").unwrap();
+        let content = format!(
+            r#"
+[metadata]
+name = "pifu"
+product_name = "Pifu"
+app_id = "org.biofan.pifu"
+description = ""
+homepage = ""
+author = ""
+version = "1.0.0"
+build_id = "1"
+license = "MIT"
+license_file = "LICENSE"
+workdir = "target/pifu"
+src_dir = {:?}
+
+[linux]
+targets = ["tarball"]
+files = [{{from = "app.sh", to = "app.sh"}}]
+
+[windows]
+exe_file = "app.exe"
+files = [{{from = "app.sh", to = "app.sh"}}]
+
+[windows.nsis]
+installer_icon = "install.ico"
+uninstaller_icon = "uninstall.ico"
+"#,
+            dir.path().to_string_lossy()
+        );
+        let conf: Config = toml::from_str(&content).unwrap();
+
+        // Files are relative to `src_dir`.
+        let diagnostics = check_config(&conf, &content, None);
+        let keys = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "windows.nsis.installer_icon",
+                "windows.nsis.uninstaller_icon"
+            ]
+        );
+
+        let options = BuildOptions::builder()
+            .targets(&[PlatformTarget::Tarball])
+            .build();
+        let diagnostics = check_config(&conf, &content, Some(&options));
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_check_plugins() {
+        let content = r#"
+[metadata]
+name = "pifu"
+product_name = "Pifu"
+app_id = "org.biofan.pifu"
+description = ""
+homepage = ""
+author = ""
+version = "1.0.0"
+build_id = "1"
+license = "GPL-3.0"
+workdir = "target/pifu"
+src_dir = "."
+
+[plugins.deb]
+files = [{from = "Cargo.toml", to = "Cargo.toml"}]
+"#;
+        let conf: Config = toml::from_str(content).unwrap();
+        let diagnostics = check_config(&conf, content, None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].key, "plugins.deb");
+        assert_eq!(diagnostics[0].line, Some(15));
+    }
+}
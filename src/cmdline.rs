@@ -2,26 +2,112 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgMatches, Command};
+use colored::Colorize;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::base::{expand_file_macro_simple, Arch, PlatformTarget};
-use crate::build;
+use crate::base::{Arch, NamedEnum, PlatformTarget};
+use crate::build::{self, Progress};
+use crate::check::{self, Severity};
 use crate::config::Config;
-use crate::download;
+use crate::doctor::{self, ToolStatus};
+use crate::download::{self, DownloadOptions, ToolState};
 use crate::error::{Error, ErrorKind};
+use crate::init;
+use crate::schema;
 
 const OPT_CONFIG: &str = "config";
 const OPT_OS: &str = "os";
 const OPT_TARGET: &str = "target";
 const OPT_ARCH: &str = "arch";
 const OPT_DOWNLOAD: &str = "download";
+const OPT_FROM_DIR: &str = "from-dir";
+const OPT_MANIFEST: &str = "manifest";
 const OPT_IGNORE_ERROR: &str = "ignore-error";
+const OPT_SKIP_BUILD: &str = "skip-build";
+const OPT_VERBOSE: &str = "verbose";
+const CMD_CHECK: &str = "check";
+const CMD_DOCTOR: &str = "doctor";
+const CMD_SCHEMA: &str = "schema";
+const CMD_INIT: &str = "init";
+const OPT_FORCE: &str = "force";
+const OPT_OUTPUT: &str = "output";
+const CMD_TOOLS: &str = "tools";
+const CMD_LIST: &str = "list";
+const CMD_VERIFY: &str = "verify";
+const CMD_REMOVE: &str = "remove";
+const OPT_NAMES: &str = "names";
 
 pub fn read_cmdline() -> Result<(), Error> {
-    let matches = Command::new("Pifu - Cross platform package builder")
+    let matches = new_command().get_matches();
+    let manifest = matches.value_of(OPT_MANIFEST).map(Path::new);
+
+    if matches.is_present(OPT_DOWNLOAD) {
+        return download_tools(&matches, manifest);
+    }
+
+    if let Some(tools_matches) = matches.subcommand_matches(CMD_TOOLS) {
+        return manage_tools(tools_matches, manifest);
+    }
+
+    if let Some(schema_matches) = matches.subcommand_matches(CMD_SCHEMA) {
+        return print_schema(schema_matches.value_of(OPT_OUTPUT));
+    }
+
+    if let Some(init_matches) = matches.subcommand_matches(CMD_INIT) {
+        return init_project(init_matches.is_present(OPT_FORCE));
+    }
+
+    let config_file = get_config_file(&matches);
+    log::info!("config file: {:?}", config_file);
+
+    if matches.subcommand_matches(CMD_CHECK).is_some() {
+        return check_config(config_file);
+    }
+
+    if matches.subcommand_matches(CMD_DOCTOR).is_some() {
+        return run_doctor(config_file, manifest);
+    }
+
+    let conf = Config::from_file(config_file)?;
+
+    let mut options = parse_build_options(&matches, &conf)?;
+    options.manifest = manifest.map(Path::to_path_buf);
+    conf.validate(config_file, &options)?;
+    log::debug!("options: {:#?}", options);
+    build::build_with_progress(&conf, &options, |progress| {
+        print_progress(progress, &options);
+    })?;
+    Ok(())
+}
+
+/// Print build steps, error is printed here only if it is ignored.
+fn print_progress(progress: &Progress, options: &build::BuildOptions) {
+    match progress {
+        Progress::Compile { triple, tool } => println!("Compile {} with {}...", triple, tool),
+        Progress::Start { name, arch } => {
+            if options.verbose {
+                // Output of tools follows.
+                println!("Build {} package for {}...", name, arch);
+            } else {
+                print!("Build {} package for {}...", name, arch);
+            }
+        }
+        Progress::Done { .. } => println!(" {}", "Ok".green()),
+        Progress::Failed { error, .. } => {
+            println!(" {}", "Failed".red());
+            if options.ignore_error {
+                println!("{} {}", "Error:".red(), error.report());
+            }
+        }
+    }
+}
+
+fn new_command() -> Command<'static> {
+    let cmd = Command::new("Pifu - Cross platform package builder")
         .version("0.3.3")
         .author("Xu Shaohua <shaohua@biofan.org>")
         .about("General package builder")
@@ -31,6 +117,7 @@ pub fn read_cmdline() -> Result<(), Error> {
                 .long(OPT_CONFIG)
                 .value_name("toml file")
                 .help("Specify a custom toml config file")
+                .global(true)
                 .takes_value(true),
         )
         .arg(
@@ -45,7 +132,7 @@ pub fn read_cmdline() -> Result<(), Error> {
                 .long(OPT_TARGET)
                 .short('t')
                 .multiple_occurrences(true)
-                .help("Build specific target")
+                .help("Build specific target, or plugin with its name")
                 .takes_value(true),
         )
         .arg(
@@ -53,7 +140,7 @@ pub fn read_cmdline() -> Result<(), Error> {
                 .long(OPT_ARCH)
                 .short('a')
                 .multiple_occurrences(true)
-                .help("Build specific architecture")
+                .help("Build specific architecture, all arches in config file are built by default")
                 .takes_value(true),
         )
         .arg(
@@ -62,33 +149,254 @@ pub fn read_cmdline() -> Result<(), Error> {
                 .help("Download required tools from github")
                 .takes_value(false),
         )
+        .arg(
+            Arg::new(OPT_FROM_DIR)
+                .long(OPT_FROM_DIR)
+                .value_name("dir")
+                .requires(OPT_DOWNLOAD)
+                .help("Install tools from local directory instead of downloading them")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new(OPT_MANIFEST)
+                .long(OPT_MANIFEST)
+                .value_name("toml file")
+                .help("Custom download list, overrides urls of builtin tools")
+                .global(true)
+                .takes_value(true),
+        )
         .arg(
             Arg::new(OPT_IGNORE_ERROR)
                 .long(OPT_IGNORE_ERROR)
                 .help("Ignore build errors and continue")
                 .takes_value(false),
         )
-        .get_matches();
+        .arg(
+            Arg::new(OPT_VERBOSE)
+                .long(OPT_VERBOSE)
+                .short('v')
+                .help(
+                    "Print output of external tools, which is always saved to log files in workdir",
+                )
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new(OPT_SKIP_BUILD)
+                .long(OPT_SKIP_BUILD)
+                .help("Do not compile project even if `[build]` section is set")
+                .takes_value(false),
+        );
+    add_subcommands(cmd)
+}
 
-    if matches.is_present(OPT_DOWNLOAD) {
-        return download::download();
+/// Add subcommands to `cmd`.
+fn add_subcommands(cmd: Command<'static>) -> Command<'static> {
+    cmd.subcommand(Command::new(CMD_CHECK).about("Validate config file and report all problems"))
+        .subcommand(
+            Command::new(CMD_DOCTOR).about("Check external tools required by enabled targets"),
+        )
+        .subcommand(
+            Command::new(CMD_SCHEMA)
+                .about("Print JSON schema of config file")
+                .arg(
+                    Arg::new(OPT_OUTPUT)
+                        .short('o')
+                        .long(OPT_OUTPUT)
+                        .value_name("json file")
+                        .help("Write schema to file instead of stdout")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_INIT)
+                .about("Generate pkg/pifu.toml and template files for current project")
+                .arg(
+                    Arg::new(OPT_FORCE)
+                        .long(OPT_FORCE)
+                        .help("Overwrite existing files")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_TOOLS)
+                .about("Manage downloaded tools")
+                .subcommand_required(true)
+                .subcommand(Command::new(CMD_LIST).about("List tools and their install state"))
+                .subcommand(Command::new(CMD_VERIFY).about("Check sha256 of installed tools"))
+                .subcommand(
+                    Command::new(CMD_REMOVE)
+                        .about("Remove installed tools, all of them if no name specified")
+                        .arg(
+                            Arg::new(OPT_NAMES)
+                                .value_name("tool[@version]")
+                                .multiple_values(true)
+                                .takes_value(true),
+                        ),
+                ),
+        )
+}
+
+/// Get path to config file, default is `pkg/pifu.toml` or `pifu.toml`.
+fn get_config_file(matches: &ArgMatches) -> &str {
+    if let Some(config_file) = matches.value_of(OPT_CONFIG) {
+        return config_file;
     }
+    let config_file = "pkg/pifu.toml";
+    if Path::new(config_file).exists() {
+        config_file
+    } else {
+        "pifu.toml"
+    }
+}
 
-    // read config
-    let mut config_file = matches.value_of(OPT_CONFIG).unwrap_or("pkg/pifu.toml");
-    if !Path::new(config_file).exists() {
-        config_file = "pifu.toml";
+fn init_project(force: bool) -> Result<(), Error> {
+    let files = init::init_project(Path::new("."), force)?;
+    for file in &files {
+        println!("{} {}", "Created".green(), file.display());
     }
-    log::info!("config file: {:?}", config_file);
+    println!("Run `pifu check` to validate generated config file.");
+    Ok(())
+}
 
-    let config_content = fs::read_to_string(config_file)
-        .unwrap_or_else(|_| panic!("Failed to read config at {}", config_file));
-    let mut conf: Config = toml::from_str(&config_content).expect("Invalid config");
+fn print_schema(output: Option<&str>) -> Result<(), Error> {
+    let schema = schema::generate_schema()?;
+    if let Some(output) = output {
+        fs::write(output, schema)?;
+    } else {
+        println!("{}", schema);
+    }
+    Ok(())
+}
 
-    conf.metadata.build_id = expand_file_macro_simple(&conf.metadata.build_id)?;
+fn check_config(config_file: &str) -> Result<(), Error> {
+    let (_conf, diagnostics) = check::check_file(config_file)?;
+    let mut num_errors = 0;
+    for diagnostic in &diagnostics {
+        if diagnostic.severity == Severity::Error {
+            num_errors += 1;
+            println!("{}", diagnostic.render(config_file).red());
+        } else {
+            println!("{}", diagnostic.render(config_file).yellow());
+        }
+    }
 
+    if num_errors == 0 {
+        println!("{} {}", config_file, "Ok".green());
+        Ok(())
+    } else {
+        Err(Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("{} error(s) found in {}", num_errors, config_file),
+        ))
+    }
+}
+
+fn run_doctor(config_file: &str, manifest: Option<&Path>) -> Result<(), Error> {
+    let conf = Config::from_file(config_file)?;
+    let distro = doctor::detect_distro();
+    let reports = doctor::check_tools(&conf, distro, manifest)?;
+    if reports.is_empty() {
+        println!("No external tools required by {}", config_file);
+        return Ok(());
+    }
+
+    let mut num_missing = 0;
+    for report in &reports {
+        let status = match report.status {
+            ToolStatus::Ok => "Ok".green(),
+            ToolStatus::Missing => "Missing".red(),
+            ToolStatus::HashMismatch => "Hash mismatch".red(),
+        };
+        println!(
+            "{} [{}] (required by {})",
+            report.name,
+            status,
+            report.required_by.join(", ")
+        );
+        if let Some(path) = report.path.as_ref() {
+            println!("    path: {}", path.display());
+        }
+        if let Some(version) = report.version.as_ref() {
+            println!("    version: {}", version);
+        }
+        if report.status != ToolStatus::Ok {
+            num_missing += 1;
+            println!("    hint: {}", report.hint.yellow());
+        }
+    }
+
+    if num_missing == 0 {
+        Ok(())
+    } else {
+        Err(Error::from_string(
+            ErrorKind::ToolNotFoundError,
+            format!("{} tool(s) missing or invalid", num_missing),
+        ))
+    }
+}
+
+/// Download tools, versions pinned in config file are used if it exists.
+fn download_tools(matches: &ArgMatches, manifest: Option<&Path>) -> Result<(), Error> {
+    let config_file = get_config_file(matches);
+    let versions = if Path::new(config_file).exists() {
+        Config::from_file(config_file)?.tools
+    } else {
+        BTreeMap::new()
+    };
+    let options = DownloadOptions {
+        from_dir: matches.value_of(OPT_FROM_DIR).map(PathBuf::from),
+        manifest: manifest.map(Path::to_path_buf),
+        versions,
+    };
+    download::download(&options)
+}
+
+fn manage_tools(matches: &ArgMatches, manifest: Option<&Path>) -> Result<(), Error> {
+    if let Some(remove_matches) = matches.subcommand_matches(CMD_REMOVE) {
+        let names: Vec<&str> = remove_matches
+            .values_of(OPT_NAMES)
+            .map(Iterator::collect)
+            .unwrap_or_default();
+        let removed = download::remove_tools(&names, manifest)?;
+        for path in &removed {
+            println!("{} {}", "Removed".green(), path.display());
+        }
+        return Ok(());
+    }
+
+    let verify = matches.subcommand_matches(CMD_VERIFY).is_some();
+    let mut num_mismatch = 0;
+    for (task, path, state) in download::list_tools(manifest)? {
+        let state = match state {
+            ToolState::Installed => "Ok".green(),
+            ToolState::Missing => "Missing".yellow(),
+            ToolState::HashMismatch => {
+                num_mismatch += 1;
+                "Hash mismatch".red()
+            }
+        };
+        println!("{} {} ({}) [{}]", task.name, task.version, task.arch, state);
+        if !verify {
+            println!("    path: {}", path.display());
+            println!("    url: {}", task.url);
+        }
+    }
+
+    if verify && num_mismatch > 0 {
+        Err(Error::from_string(
+            ErrorKind::ToolNotFoundError,
+            format!("{} tool(s) do not match sha256", num_mismatch),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn parse_build_options(matches: &ArgMatches, conf: &Config) -> Result<build::BuildOptions, Error> {
     let mut options = build::BuildOptions {
         ignore_error: matches.is_present(OPT_IGNORE_ERROR),
+        skip_build: matches.is_present(OPT_SKIP_BUILD),
+        verbose: matches.is_present(OPT_VERBOSE),
         ..Default::default()
     };
 
@@ -100,6 +408,17 @@ pub fn read_cmdline() -> Result<(), Error> {
                     PlatformTarget::Deb,
                     PlatformTarget::Rpm,
                     PlatformTarget::AppImage,
+                    PlatformTarget::Flatpak,
+                    PlatformTarget::Snap,
+                    PlatformTarget::Apk,
+                    PlatformTarget::Ipk,
+                    PlatformTarget::Tarball,
+                ]);
+            } else if os == "macos" {
+                options.targets.extend([
+                    PlatformTarget::App,
+                    PlatformTarget::Dmg,
+                    PlatformTarget::MacosPkg,
                 ]);
             } else if os == "win" {
                 options.targets.push(PlatformTarget::Nsis);
@@ -107,7 +426,10 @@ pub fn read_cmdline() -> Result<(), Error> {
                 log::error!("Invalid --os {}", &os);
                 return Err(Error::from_string(
                     ErrorKind::CmdlineError,
-                    format!("Invalid --os {}, available values are `linux` or `win`", os),
+                    format!(
+                        "Invalid --os {}, available values are `linux`, `macos` or `win`",
+                        os
+                    ),
                 ));
             }
         }
@@ -118,32 +440,39 @@ pub fn read_cmdline() -> Result<(), Error> {
         for target in target_list {
             if let Ok(target) = PlatformTarget::from_str(target) {
                 options.targets.push(target);
+            } else if conf.plugins.contains_key(target) {
+                options.plugins.push(target.to_string());
             } else {
                 return Err(Error::from_string(
                     ErrorKind::CmdlineError,
-                    format!("Invalid --target {}, available values are `deb`, `rpm`, `app_image` or `nsis`", target),
+                    format!(
+                        "Invalid --target {}, available values are {} or name of plugins",
+                        target,
+                        PlatformTarget::quoted_names()
+                    ),
                 ));
             }
         }
     }
 
     if let Some(arch_list) = matches.values_of(OPT_ARCH) {
-        options.arches.clear();
+        let mut arches = Vec::new();
         for arch in arch_list {
             if let Ok(arch) = Arch::from_str(arch) {
-                options.arches.push(arch);
+                arches.push(arch);
             } else {
                 return Err(Error::from_string(
                     ErrorKind::CmdlineError,
                     format!(
-                        "Invalid --arch {}, available values are `x86_64`, `x86` or `aarch64`",
-                        arch
+                        "Invalid --arch {}, available values are {}",
+                        arch,
+                        Arch::quoted_names()
                     ),
                 ));
             }
         }
+        options.arches = Some(arches);
     }
 
-    log::debug!("options: {:#?}", options);
-    build::build(&conf, &options)
+    Ok(options)
 }
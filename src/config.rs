@@ -2,28 +2,179 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+use colored::Colorize;
 use directories::ProjectDirs;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use crate::apk::ApkConfig;
 use crate::app_image::AppImageConfig;
+use crate::appstream::AppStreamConfig;
 use crate::base::fileset::FileSet;
-use crate::base::{Arch, Metadata, PlatformTarget};
+use crate::base::{expand_file_macro_simple, Arch, Metadata, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::cargo::BuildConfig;
+use crate::check::{check_selected, parse_file, Diagnostic, Severity};
 use crate::deb::DebConfig;
+use crate::desktop::DesktopConfig;
 use crate::error::{Error, ErrorKind};
+use crate::flatpak::FlatpakConfig;
+use crate::ipk::IpkConfig;
+use crate::macos::{AppConfig, DmgConfig};
+use crate::macos_pkg::MacosPkgConfig;
 use crate::nsis::NsisConfig;
+use crate::plugin::PluginConfig;
 use crate::rpm::RpmConfig;
+use crate::sign::SignConfig;
+use crate::snap::SnapConfig;
+use crate::tarball::TarballConfig;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub metadata: Metadata,
 
+    /// Compile rust project before packaging.
+    pub build: Option<BuildConfig>,
+
     pub windows: Option<WindowsConfig>,
 
     pub linux: Option<LinuxConfig>,
+
+    pub macos: Option<MacOsConfig>,
+
+    /// External packagers, like `[plugins.foo]` runs `pifu-foo` executable.
+    #[serde(default)]
+    pub plugins: BTreeMap<String, PluginConfig>,
+
+    /// Pinned versions of downloaded tools, like `appimagetool = "13"`.
+    #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+
+    /// Signing key, used by targets with signing enabled.
+    pub sign: Option<SignConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Config {
+    /// Create a new config with only `metadata` section.
+    #[must_use]
+    pub const fn new(metadata: Metadata) -> Self {
+        Self {
+            metadata,
+            build: None,
+            windows: None,
+            linux: None,
+            macos: None,
+            plugins: BTreeMap::new(),
+            tools: BTreeMap::new(),
+            sign: None,
+        }
+    }
+
+    /// Read config file, and expand macros in `build_id`.
+    ///
+    /// Syntax and type errors are rejected, call `validate()` to check
+    /// semantic problems before build.
+    pub fn from_file(path: &str) -> Result<Self, Error> {
+        let mut conf = read_config(path)?;
+        conf.metadata.build_id = expand_file_macro_simple(&conf.metadata.build_id)?;
+        Ok(conf)
+    }
+
+    /// Check semantic problems of targets selected in `options`, like missing files.
+    ///
+    /// `path` is the config file this config is read from, used to locate problems.
+    pub fn validate(&self, path: &str, options: &BuildOptions) -> Result<(), Error> {
+        let diagnostics = check_selected(path, self, options)?;
+        report_diagnostics(path, &diagnostics)
+    }
+
+    #[must_use]
+    pub fn with_build(mut self, build: BuildConfig) -> Self {
+        self.build = Some(build);
+        self
+    }
+
+    #[must_use]
+    pub fn with_linux(mut self, linux: LinuxConfig) -> Self {
+        self.linux = Some(linux);
+        self
+    }
+
+    #[must_use]
+    pub fn with_macos(mut self, macos: MacOsConfig) -> Self {
+        self.macos = Some(macos);
+        self
+    }
+
+    #[must_use]
+    pub fn with_windows(mut self, windows: WindowsConfig) -> Self {
+        self.windows = Some(windows);
+        self
+    }
+
+    #[must_use]
+    pub fn with_plugin(mut self, name: &str, plugin: PluginConfig) -> Self {
+        self.plugins.insert(name.to_string(), plugin);
+        self
+    }
+
+    /// Pin version of downloaded tool `name`.
+    #[must_use]
+    pub fn with_tool(mut self, name: &str, version: &str) -> Self {
+        self.tools.insert(name.to_string(), version.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_sign(mut self, sign: SignConfig) -> Self {
+        self.sign = Some(sign);
+        self
+    }
+
+    /// Get `sign` section, returns error if it is not set.
+    pub fn sign_conf(&self) -> Result<&SignConfig, Error> {
+        self.sign
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidConfError, "`sign` config not set!"))
+    }
+
+    /// Get `linux` section, returns error if it is not set.
+    pub fn linux_conf(&self) -> Result<&LinuxConfig, Error> {
+        self.linux
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidConfError, "`linux` config not set!"))
+    }
+
+    /// Get `macos` section, returns error if it is not set.
+    pub fn macos_conf(&self) -> Result<&MacOsConfig, Error> {
+        self.macos
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidConfError, "`macos` config not set!"))
+    }
+
+    /// Get `windows` section, returns error if it is not set.
+    pub fn windows_conf(&self) -> Result<&WindowsConfig, Error> {
+        self.windows
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidConfError, "`windows` config not set!"))
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    /// Parse config from toml string, without validation.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct LinuxConfig {
     #[serde(default = "default_arch")]
     pub arch: Vec<Arch>,
@@ -33,7 +184,7 @@ pub struct LinuxConfig {
 
     pub files: Option<Vec<FileSet>>,
 
-    /// Specific config for AppImage format.
+    /// Specific config for `AppImage` format.
     #[serde(default = "AppImageConfig::default")]
     pub app_image: AppImageConfig,
 
@@ -44,6 +195,52 @@ pub struct LinuxConfig {
     /// Specific config for Rpm format.
     #[serde(default = "RpmConfig::default")]
     pub rpm: RpmConfig,
+
+    /// Specific config for flatpak format.
+    #[serde(default = "FlatpakConfig::default")]
+    pub flatpak: FlatpakConfig,
+
+    /// Specific config for snap format.
+    #[serde(default = "SnapConfig::default")]
+    pub snap: SnapConfig,
+
+    /// Specific config for Alpine apk format.
+    #[serde(default = "ApkConfig::default")]
+    pub apk: ApkConfig,
+
+    /// Specific config for `OpenWrt` ipk format.
+    #[serde(default = "IpkConfig::default")]
+    pub ipk: IpkConfig,
+
+    /// Specific config for portable tarball.
+    #[serde(default = "TarballConfig::default")]
+    pub tarball: TarballConfig,
+
+    /// Generate desktop entry and icons.
+    pub desktop: Option<DesktopConfig>,
+
+    /// Generate `AppStream` metainfo.
+    pub appstream: Option<AppStreamConfig>,
+}
+
+impl Default for LinuxConfig {
+    fn default() -> Self {
+        Self {
+            arch: default_arch(),
+            targets: default_linux_targets(),
+            files: None,
+            app_image: AppImageConfig::default(),
+            deb: DebConfig::default(),
+            rpm: RpmConfig::default(),
+            flatpak: FlatpakConfig::default(),
+            snap: SnapConfig::default(),
+            apk: ApkConfig::default(),
+            ipk: IpkConfig::default(),
+            tarball: TarballConfig::default(),
+            desktop: None,
+            appstream: None,
+        }
+    }
 }
 
 fn default_arch() -> Vec<Arch> {
@@ -54,7 +251,8 @@ fn default_linux_targets() -> Vec<PlatformTarget> {
     vec![PlatformTarget::Deb, PlatformTarget::AppImage]
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct WindowsConfig {
     #[serde(default = "default_arch")]
     pub arch: Vec<Arch>,
@@ -72,18 +270,127 @@ pub struct WindowsConfig {
     pub nsis: Option<NsisConfig>,
 }
 
+impl WindowsConfig {
+    #[must_use]
+    pub fn new(exe_file: &str) -> Self {
+        Self {
+            arch: default_arch(),
+            targets: default_windows_targets(),
+            exe_file: exe_file.to_string(),
+            files: None,
+            nsis: None,
+        }
+    }
+}
+
 fn default_windows_targets() -> Vec<PlatformTarget> {
     vec![PlatformTarget::Nsis]
 }
 
-pub fn get_project_dir() -> Result<ProjectDirs, Error> {
-    match ProjectDirs::from("org", "biofan", "pifu") {
-        Some(dir) => Ok(dir),
-        None => Err(Error::new(ErrorKind::HomeDirError, "Invalid $HOME")),
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MacOsConfig {
+    /// Only `x86_64` and `aarch64` are supported.
+    #[serde(default = "default_arch")]
+    pub arch: Vec<Arch>,
+
+    #[serde(default = "default_macos_targets")]
+    pub targets: Vec<PlatformTarget>,
+
+    /// String - Executable in `Contents/MacOS`, default is `metadata.name`.
+    pub exe_file: Option<String>,
+
+    /// Files copied to `Contents` of application bundle, like
+    /// `{from = "${target_dir}/hello", to = "MacOS/hello"}`.
+    pub files: Option<Vec<FileSet>>,
+
+    /// String - PNG icon, which is converted to `Contents/Resources/<name>.icns`.
+    pub icon: Option<String>,
+
+    /// Specific config for application bundle.
+    #[serde(default = "AppConfig::default")]
+    pub app: AppConfig,
+
+    /// Specific config for dmg format.
+    #[serde(default = "DmgConfig::default")]
+    pub dmg: DmgConfig,
+
+    /// Specific config for flat installer package.
+    #[serde(default = "MacosPkgConfig::default")]
+    pub macos_pkg: MacosPkgConfig,
+}
+
+impl MacOsConfig {
+    /// Get name of executable in `Contents/MacOS`.
+    #[must_use]
+    pub fn exe_file<'a>(&'a self, conf: &'a Config) -> &'a str {
+        self.exe_file.as_deref().unwrap_or(&conf.metadata.name)
+    }
+}
+
+impl Default for MacOsConfig {
+    fn default() -> Self {
+        Self {
+            arch: default_arch(),
+            targets: default_macos_targets(),
+            exe_file: None,
+            files: None,
+            icon: None,
+            app: AppConfig::default(),
+            dmg: DmgConfig::default(),
+            macos_pkg: MacosPkgConfig::default(),
+        }
+    }
+}
+
+fn default_macos_targets() -> Vec<PlatformTarget> {
+    vec![PlatformTarget::App, PlatformTarget::Dmg]
+}
+
+/// Read config file, with syntax and type errors rejected.
+///
+/// Warnings are printed, and all errors are returned at once.
+pub fn read_config(path: &str) -> Result<Config, Error> {
+    let (conf, diagnostics) = parse_file(path)?;
+    report_diagnostics(path, &diagnostics)?;
+    conf.ok_or_else(|| Error::new(ErrorKind::InvalidConfError, "Invalid config file"))
+}
+
+/// Print warnings, and returns all errors at once.
+fn report_diagnostics(path: &str, diagnostics: &[Diagnostic]) -> Result<(), Error> {
+    let mut errors = Vec::new();
+    for diagnostic in diagnostics {
+        if diagnostic.severity == Severity::Error {
+            errors.push(diagnostic.render(path));
+        } else {
+            eprintln!("{} {}", "Warning:".yellow(), diagnostic.render(path));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Invalid config file:\n{}", errors.join("\n")),
+        ))
     }
 }
 
+pub fn get_project_dir() -> Result<ProjectDirs, Error> {
+    ProjectDirs::from("org", "biofan", "pifu")
+        .ok_or_else(|| Error::new(ErrorKind::HomeDirError, "Invalid $HOME"))
+}
+
+/// Environment variable to override directory of downloaded tools.
+pub const TOOLS_DIR_ENV: &str = "PIFU_TOOLS_DIR";
+
+/// Get directory of downloaded tools, `$PIFU_TOOLS_DIR` if set, or `bin`
+/// folder in config directory.
 pub fn get_binary_dir() -> Result<PathBuf, Error> {
+    if let Some(dir) = std::env::var_os(TOOLS_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
     let project_dir = get_project_dir()?;
     let conf_dir = project_dir.config_dir();
     Ok(conf_dir.join("bin"))
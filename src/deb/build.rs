@@ -2,18 +2,46 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::appstream::install_metainfo;
 use crate::base::archive;
 use crate::base::compress;
 use crate::base::fileset;
 use crate::base::utils;
-use crate::base::Arch;
+use crate::base::{Arch, PlatformTarget};
+use crate::build::BuildOptions;
 use crate::config::{Config, LinuxConfig};
 use crate::deb::control;
+use crate::desktop;
 use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of debian `.deb` format.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DebPackager;
+
+impl Packager for DebPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Deb
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        _options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        build_deb(conf, linux_conf, arch)
+    }
+}
 
-pub fn build_deb(conf: &Config, linux_conf: &LinuxConfig, arch: Arch) -> Result<(), Error> {
+pub fn build_deb(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+) -> Result<Vec<PathBuf>, Error> {
     let deb_conf = &linux_conf.deb;
 
     let files = if let Some(files) = deb_conf.files.as_ref() {
@@ -34,7 +62,20 @@ pub fn build_deb(conf: &Config, linux_conf: &LinuxConfig, arch: Arch) -> Result<
 
     utils::rmdir(&deb_dir)?;
 
-    fileset::copy_filesets(files, &conf.metadata.src_dir, &data_dir)?;
+    fileset::copy_filesets(files, conf, arch, PlatformTarget::Deb, &data_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        desktop::install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::Deb,
+            &data_dir,
+            false,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &data_dir)?;
+    }
 
     let data_tar_file = deb_dir.join("data.tar");
     archive::create_tar_chown(&data_dir, &data_tar_file)?;
@@ -47,7 +88,13 @@ pub fn build_deb(conf: &Config, linux_conf: &LinuxConfig, arch: Arch) -> Result<
 
     let file_size = utils::get_folder_size(&data_dir)?;
     let control_file = control_dir.join("control");
-    control::generate_control(conf, arch, file_size, &control_file)?;
+    control::generate_control(
+        conf,
+        deb_conf,
+        control::arch_name(arch),
+        file_size,
+        &control_file,
+    )?;
 
     let control_tar_file = deb_dir.join("control.tar");
     archive::create_tar_without_rootdir(&control_dir, &control_tar_file)?;
@@ -68,5 +115,5 @@ pub fn build_deb(conf: &Config, linux_conf: &LinuxConfig, arch: Arch) -> Result<
     let xz_files = vec![&deb_binary_file, &control_xz_file, &data_xz_file];
     archive::create_ar_files(&xz_files, &deb_file)?;
 
-    Ok(())
+    Ok(vec![deb_file])
 }
@@ -2,11 +2,13 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::base::fileset::FileSet;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct DebConfig {
     #[serde(default = "default_priority")]
     pub priority: String,
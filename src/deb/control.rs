@@ -7,13 +7,16 @@ use std::io::{self, Write};
 use std::path::Path;
 use walkdir::WalkDir;
 
+use super::config::DebConfig;
 use crate::base::Arch;
 use crate::config::Config;
 use crate::Error;
 
+/// Write control file, which is shared by deb and ipk formats.
 pub fn generate_control(
     conf: &Config,
-    arch: Arch,
+    deb: &DebConfig,
+    arch_name: &str,
     size: u64,
     dest_file: &Path,
 ) -> Result<(), Error> {
@@ -25,10 +28,8 @@ pub fn generate_control(
     let metadata = &conf.metadata;
     writeln!(&mut fd, "Package: {}", metadata.name)?;
     writeln!(&mut fd, "Version: {}", metadata.version)?;
-    writeln!(&mut fd, "Architecture: {}", arch_name(arch))?;
+    writeln!(&mut fd, "Architecture: {}", arch_name)?;
 
-    let linux = conf.linux.as_ref().expect("Linux conf is not set");
-    let deb = &linux.deb;
     if let Some(section) = deb.section.as_ref() {
         writeln!(&mut fd, "Section: {}", section)?;
     }
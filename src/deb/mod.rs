@@ -6,5 +6,6 @@ mod build;
 mod config;
 mod control;
 
-pub use build::build_deb;
+pub use build::DebPackager;
 pub use config::DebConfig;
+pub use control::generate_control;
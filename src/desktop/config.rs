@@ -0,0 +1,37 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Generate `.desktop` entry and hicolor icons for linux targets.
+///
+/// `Name` and `Comment` are read from `product_name` and `description` of
+/// metadata, file names are based on `app_id`.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DesktopConfig {
+    /// String - Command line to launch the application, default is package name.
+    pub exec: Option<String>,
+
+    /// String - Source icon, a square PNG file (at least 256x256 is recommended)
+    /// or SVG file, relative to `src_dir`. PNG is resized to hicolor sizes.
+    pub icon: Option<String>,
+
+    /// Array of String - Freedesktop categories, like `["Development", "IDE"]`.
+    #[serde(default)]
+    pub categories: Vec<String>,
+
+    /// Array of String - Additional words to search the application.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// Array of String - MIME types the application can open, like `text/plain`.
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+
+    /// Boolean - Whether the application runs in a terminal.
+    #[serde(default)]
+    pub terminal: bool,
+}
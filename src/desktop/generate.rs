@@ -0,0 +1,304 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use image::imageops::FilterType;
+use image::GenericImageView;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config::DesktopConfig;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::check::{reject_errors, Issue};
+use crate::config::Config;
+use crate::error::{Error, ErrorKind};
+
+/// Sizes of PNG icons in hicolor theme.
+pub const ICON_SIZES: &[u32] = &[16, 24, 32, 48, 64, 128, 256, 512];
+
+/// Size of icon placed at root of `AppDir`.
+const APP_DIR_ICON_SIZE: u32 = 256;
+
+/// Registered main categories, one of them is required.
+const MAIN_CATEGORIES: &[&str] = &[
+    "AudioVideo",
+    "Audio",
+    "Video",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+/// Validate desktop config against desktop entry specification.
+#[must_use]
+pub fn validate_desktop(desktop_conf: &DesktopConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if desktop_conf
+        .exec
+        .as_ref()
+        .is_some_and(|exec| exec.trim().is_empty())
+    {
+        issues.push(Issue::error("exec", "is empty".to_string()));
+    }
+
+    if let Some(icon) = desktop_conf.icon.as_ref() {
+        if icon_kind(icon).is_none() {
+            issues.push(Issue::error(
+                "icon",
+                format!("{:?} shall be a .png or .svg file", icon),
+            ));
+        }
+    }
+
+    let lists = [
+        ("categories", &desktop_conf.categories),
+        ("keywords", &desktop_conf.keywords),
+        ("mime_types", &desktop_conf.mime_types),
+    ];
+    for (key, values) in lists {
+        for value in values {
+            if value.is_empty() || value.contains([';', '\n']) {
+                issues.push(Issue::error(
+                    key,
+                    format!("contains invalid item {:?}", value),
+                ));
+            }
+        }
+    }
+
+    if !desktop_conf
+        .categories
+        .iter()
+        .any(|category| MAIN_CATEGORIES.contains(&category.as_str()))
+    {
+        issues.push(Issue::warning(
+            "categories",
+            format!(
+                "shall contain one of main categories: {}",
+                MAIN_CATEGORIES.join(", ")
+            ),
+        ));
+    }
+
+    for mime_type in &desktop_conf.mime_types {
+        let is_valid = mime_type
+            .split_once('/')
+            .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty());
+        if !is_valid {
+            issues.push(Issue::error(
+                "mime_types",
+                format!("{:?} is not in `type/subtype` form", mime_type),
+            ));
+        }
+    }
+    issues
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconKind {
+    Png,
+    Svg,
+}
+
+fn icon_kind(icon: &str) -> Option<IconKind> {
+    let ext = Path::new(icon).extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some(IconKind::Png),
+        "svg" => Some(IconKind::Svg),
+        _ => None,
+    }
+}
+
+/// Escape string value of desktop entry.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
+/// Join list values of desktop entry, each one ends with `;`.
+fn join_list(values: &[String]) -> String {
+    values.iter().map(|value| escape(value) + ";").collect()
+}
+
+/// Generate content of desktop entry.
+pub fn desktop_entry(
+    conf: &Config,
+    desktop_conf: &DesktopConfig,
+    arch: Arch,
+    target: PlatformTarget,
+) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let exec = match desktop_conf.exec.as_ref() {
+        Some(exec) => expand_file_macro(exec, conf, arch, target)?,
+        None => metadata.name.clone(),
+    };
+
+    let mut entry = String::new();
+    writeln!(entry, "[Desktop Entry]")?;
+    writeln!(entry, "Type=Application")?;
+    writeln!(entry, "Version=1.5")?;
+    writeln!(entry, "Name={}", escape(&metadata.product_name))?;
+    if !metadata.description.is_empty() {
+        writeln!(entry, "Comment={}", escape(&metadata.description))?;
+    }
+    writeln!(entry, "Exec={}", escape(&exec))?;
+    if desktop_conf.icon.is_some() {
+        writeln!(entry, "Icon={}", escape(&metadata.app_id))?;
+    }
+    writeln!(entry, "Terminal={}", desktop_conf.terminal)?;
+    if !desktop_conf.categories.is_empty() {
+        writeln!(entry, "Categories={}", join_list(&desktop_conf.categories))?;
+    }
+    if !desktop_conf.keywords.is_empty() {
+        writeln!(entry, "Keywords={}", join_list(&desktop_conf.keywords))?;
+    }
+    if !desktop_conf.mime_types.is_empty() {
+        writeln!(entry, "MimeType={}", join_list(&desktop_conf.mime_types))?;
+    }
+    Ok(entry)
+}
+
+/// Write desktop entry and icons to `usr/share` of `dest_dir`.
+///
+/// If `app_dir` is true, desktop entry and icon are also copied to `dest_dir`,
+/// as required by `AppImage`.
+pub fn install_desktop(
+    conf: &Config,
+    desktop_conf: &DesktopConfig,
+    arch: Arch,
+    target: PlatformTarget,
+    dest_dir: &Path,
+    app_dir: bool,
+) -> Result<(), Error> {
+    reject_errors("linux.desktop", &validate_desktop(desktop_conf))?;
+
+    let app_id = &conf.metadata.app_id;
+    let entry = desktop_entry(conf, desktop_conf, arch, target)?;
+    let applications_dir = dest_dir.join("usr/share/applications");
+    fs::create_dir_all(&applications_dir)?;
+    let desktop_file = applications_dir.join(format!("{}.desktop", app_id));
+    fs::write(&desktop_file, &entry)?;
+    if app_dir {
+        fs::write(dest_dir.join(format!("{}.desktop", app_id)), &entry)?;
+    }
+
+    if let Some(icon) = desktop_conf.icon.as_ref() {
+        let icon = expand_file_macro(icon, conf, arch, target)?;
+        let src_file = Path::new(&conf.metadata.src_dir).join(&icon);
+        let icons_dir = dest_dir.join("usr/share/icons/hicolor");
+        let root_icon = if icon_kind(&icon) == Some(IconKind::Svg) {
+            let svg_dir = icons_dir.join("scalable/apps");
+            fs::create_dir_all(&svg_dir)?;
+            let svg_file = svg_dir.join(format!("{}.svg", app_id));
+            copy_icon(&src_file, &svg_file)?;
+            svg_file
+        } else {
+            resize_icon(&src_file, &icons_dir, app_id)?
+        };
+        if app_dir {
+            let ext = root_icon
+                .extension()
+                .map_or_else(String::new, |ext| ext.to_string_lossy().to_string());
+            copy_icon(&root_icon, &dest_dir.join(format!("{}.{}", app_id, ext)))?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_icon(src: &Path, dest: &Path) -> Result<(), Error> {
+    fs::copy(src, dest).map(drop).map_err(|err| {
+        Error::new(ErrorKind::IoError, "Failed to copy icon")
+            .with_source(err)
+            .with_path(src)
+    })
+}
+
+/// Resize PNG icon to hicolor sizes which are not larger than itself.
+///
+/// Returns the icon to be placed at root of `AppDir`.
+fn resize_icon(src_file: &Path, icons_dir: &Path, app_id: &str) -> Result<PathBuf, Error> {
+    let image = image::open(src_file).map_err(|err| Error::from(err).with_path(src_file))?;
+    let (width, height) = image.dimensions();
+    if width != height {
+        log::warn!(
+            "Icon {:?} is not square: {}x{}, it will be stretched",
+            src_file,
+            width,
+            height
+        );
+    }
+
+    let mut sizes: Vec<u32> = ICON_SIZES
+        .iter()
+        .copied()
+        .filter(|size| *size <= width)
+        .collect();
+    if sizes.is_empty() {
+        sizes.push(width);
+    }
+
+    let mut root_icon = None;
+    for size in sizes {
+        let size_dir = icons_dir.join(format!("{}x{}/apps", size, size));
+        fs::create_dir_all(&size_dir)?;
+        let png_file = size_dir.join(format!("{}.png", app_id));
+        if size == width && width == height {
+            copy_icon(src_file, &png_file)?;
+        } else {
+            image
+                .resize_exact(size, size, FilterType::Lanczos3)
+                .save(&png_file)
+                .map_err(|err| Error::from(err).with_path(&png_file))?;
+        }
+        if size <= APP_DIR_ICON_SIZE || root_icon.is_none() {
+            root_icon = Some(png_file);
+        }
+    }
+    // `sizes` is never empty.
+    Ok(root_icon.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_desktop_entry() {
+        let mut metadata = Metadata::new("hello", "1.0.0");
+        metadata.product_name = "Hello".to_string();
+        metadata.description = "pifu example".to_string();
+        let conf = Config::new(metadata);
+        let desktop_conf = DesktopConfig {
+            icon: Some("examples/hello.png".to_string()),
+            categories: vec!["System".to_string(), "Monitor".to_string()],
+            terminal: true,
+            ..DesktopConfig::default()
+        };
+        assert!(validate_desktop(&desktop_conf).is_empty());
+        let entry = desktop_entry(&conf, &desktop_conf, Arch::X86_64, PlatformTarget::Deb).unwrap();
+        assert!(entry.contains("Exec=hello\n"));
+        assert!(entry.contains("Icon=org.example.hello\n"));
+        assert!(entry.contains("Categories=System;Monitor;\n"));
+        assert!(entry.contains("Terminal=true\n"));
+
+        let desktop_conf = DesktopConfig {
+            icon: Some("hello.ico".to_string()),
+            mime_types: vec!["text".to_string()],
+            ..DesktopConfig::default()
+        };
+        let issues = validate_desktop(&desktop_conf);
+        assert_eq!(issues.len(), 3);
+    }
+}
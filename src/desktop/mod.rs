@@ -0,0 +1,10 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod config;
+mod generate;
+
+pub use config::DesktopConfig;
+pub use generate::install_desktop;
+pub use generate::validate_desktop;
@@ -0,0 +1,503 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::base::hash::sha256sum;
+use crate::base::{NamedEnum, PlatformTarget};
+use crate::cargo::BuildTool;
+use crate::config::{get_binary_dir, Config, LinuxConfig};
+use crate::download::read_task_list;
+use crate::error::Error;
+
+/// Linux distribution family, used to print install hints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distro {
+    Debian,
+    Fedora,
+    Arch,
+    OpenSuse,
+    Alpine,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolStatus {
+    Ok,
+    Missing,
+
+    /// Downloaded tool does not match sha256 in download list.
+    HashMismatch,
+}
+
+/// Result of checking one tool.
+#[derive(Debug)]
+pub struct ToolReport {
+    pub name: String,
+
+    /// Targets or features which require this tool.
+    pub required_by: Vec<String>,
+
+    pub path: Option<PathBuf>,
+
+    /// First line of version output, or sha256 of downloaded tools.
+    pub version: Option<String>,
+
+    pub status: ToolStatus,
+
+    /// How to install this tool.
+    pub hint: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ToolKind {
+    /// Command searched in $PATH, with argument to print its version.
+    Command(&'static str),
+
+    /// File downloaded with `pifu --download`, with its install path and sha256,
+    /// `None` if not available for this arch.
+    Downloaded(Option<(PathBuf, String)>),
+
+    /// External packager.
+    Plugin,
+}
+
+#[derive(Debug)]
+struct Requirement {
+    name: String,
+    kind: ToolKind,
+    required_by: Vec<String>,
+}
+
+fn require(requirements: &mut Vec<Requirement>, name: &str, kind: ToolKind, required_by: &str) {
+    if let Some(req) = requirements.iter_mut().find(|req| req.name == name) {
+        if !req.required_by.iter().any(|r| r == required_by) {
+            req.required_by.push(required_by.to_string());
+        }
+    } else {
+        requirements.push(Requirement {
+            name: name.to_string(),
+            kind,
+            required_by: vec![required_by.to_string()],
+        });
+    }
+}
+
+/// Collect tools required by targets enabled in config.
+fn collect_requirements(conf: &Config, manifest: Option<&Path>) -> Result<Vec<Requirement>, Error> {
+    let mut requirements = Vec::new();
+
+    if let Some(build_conf) = conf.build.as_ref() {
+        let name = match build_conf.tool {
+            BuildTool::Cargo => "cargo",
+            BuildTool::Cross => "cross",
+            BuildTool::Zigbuild => "cargo-zigbuild",
+        };
+        require(
+            &mut requirements,
+            name,
+            ToolKind::Command("--version"),
+            "build",
+        );
+    }
+
+    if serde_json::to_string(conf)?.contains("${git}") {
+        require(
+            &mut requirements,
+            "git",
+            ToolKind::Command("--version"),
+            "${git} macro",
+        );
+    }
+
+    if let Some(linux_conf) = conf.linux.as_ref() {
+        collect_linux_requirements(conf, linux_conf, manifest, &mut requirements)?;
+    }
+
+    if let Some(macos_conf) = conf.macos.as_ref() {
+        if macos_conf.targets.contains(&PlatformTarget::Dmg) {
+            require(
+                &mut requirements,
+                "genisoimage",
+                ToolKind::Command("--version"),
+                "dmg",
+            );
+        }
+    }
+
+    if let Some(windows_conf) = conf.windows.as_ref() {
+        if windows_conf.targets.contains(&PlatformTarget::Nsis) {
+            require(
+                &mut requirements,
+                "makensis",
+                ToolKind::Command("-VERSION"),
+                "nsis",
+            );
+        }
+    }
+
+    for (name, plugin_conf) in &conf.plugins {
+        require(
+            &mut requirements,
+            &plugin_conf.command(name),
+            ToolKind::Plugin,
+            &format!("plugin {}", name),
+        );
+    }
+
+    Ok(requirements)
+}
+
+/// Collect tools required by linux targets.
+fn collect_linux_requirements(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    manifest: Option<&Path>,
+    requirements: &mut Vec<Requirement>,
+) -> Result<(), Error> {
+    if linux_conf.targets.contains(&PlatformTarget::Rpm) {
+        require(
+            requirements,
+            "rpmbuild",
+            ToolKind::Command("--version"),
+            "rpm",
+        );
+    }
+    if linux_conf.targets.contains(&PlatformTarget::AppImage) {
+        if linux_conf.app_image.embed_libs {
+            require(
+                requirements,
+                "ldd",
+                ToolKind::Command("--version"),
+                "app_image",
+            );
+        }
+        for framework in &linux_conf.app_image.bundle_frameworks {
+            if !linux_conf
+                .app_image
+                .framework_paths
+                .contains_key(framework.name())
+            {
+                require(
+                    requirements,
+                    framework.detect_tool(),
+                    ToolKind::Command("--version"),
+                    &format!("app_image {}", framework.name()),
+                );
+            }
+        }
+        if linux_conf.app_image.sign {
+            let gpg = conf.sign.as_ref().map_or("gpg", |sign| sign.gpg.as_str());
+            require(
+                requirements,
+                gpg,
+                ToolKind::Command("--version"),
+                "app_image",
+            );
+        }
+        let task_list = read_task_list(manifest)?;
+        let binary_dir = get_binary_dir()?;
+        let version = conf.tools.get("appimagetool").map(String::as_str);
+        for arch in &linux_conf.arch {
+            let task = task_list
+                .find("appimagetool", version, *arch)
+                .map(|task| (task.install_path(&binary_dir), task.sha256.clone()));
+            require(
+                requirements,
+                &format!("appimagetool-{}.AppImage", arch),
+                ToolKind::Downloaded(task),
+                "app_image",
+            );
+        }
+    }
+    if linux_conf.targets.contains(&PlatformTarget::Snap) {
+        require(
+            requirements,
+            "mksquashfs",
+            ToolKind::Command("-version"),
+            "snap",
+        );
+    }
+    if linux_conf.targets.contains(&PlatformTarget::Tarball)
+        && !linux_conf.tarball.exe_files.is_empty()
+    {
+        require(
+            requirements,
+            "ldd",
+            ToolKind::Command("--version"),
+            "tarball",
+        );
+    }
+    if linux_conf.targets.contains(&PlatformTarget::Apk) && linux_conf.apk.sign {
+        let openssl = conf
+            .sign
+            .as_ref()
+            .map_or("openssl", |sign| sign.openssl.as_str());
+        require(requirements, openssl, ToolKind::Command("version"), "apk");
+    }
+    if linux_conf.targets.contains(&PlatformTarget::Flatpak) && linux_conf.flatpak.bundle {
+        for name in ["flatpak-builder", "flatpak"] {
+            require(
+                requirements,
+                name,
+                ToolKind::Command("--version"),
+                "flatpak",
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Check presence and version of tools required by `conf`.
+///
+/// `manifest` is the custom download list used by `pifu --download`.
+pub fn check_tools(
+    conf: &Config,
+    distro: Distro,
+    manifest: Option<&Path>,
+) -> Result<Vec<ToolReport>, Error> {
+    let mut reports = Vec::new();
+    for req in collect_requirements(conf, manifest)? {
+        let mut report = ToolReport {
+            hint: install_hint(&req.name, distro),
+            name: req.name,
+            required_by: req.required_by,
+            path: None,
+            version: None,
+            status: ToolStatus::Missing,
+        };
+        match req.kind {
+            ToolKind::Command(version_arg) => {
+                report.path = find_command(&report.name);
+                if let Some(path) = report.path.as_ref() {
+                    report.version = get_version(path, version_arg);
+                    report.status = ToolStatus::Ok;
+                }
+            }
+            ToolKind::Plugin => {
+                report.path = find_command(&report.name);
+                if report.path.is_some() {
+                    report.status = ToolStatus::Ok;
+                }
+            }
+            ToolKind::Downloaded(None) => (),
+            ToolKind::Downloaded(Some((path, sha256))) => {
+                if path.exists() {
+                    let file_hash = sha256sum(&path)?;
+                    report.status = if file_hash == sha256 {
+                        ToolStatus::Ok
+                    } else {
+                        ToolStatus::HashMismatch
+                    };
+                    report.version = Some(format!("sha256 {}", file_hash));
+                    report.path = Some(path);
+                }
+            }
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// Search executable `name` in $PATH, or check it directly if it contains a path separator.
+fn find_command(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Get first non-empty line of version output.
+fn get_version(path: &Path, version_arg: &str) -> Option<String> {
+    let output = Command::new(path).arg(version_arg).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Detect distribution of current system from `/etc/os-release`.
+#[must_use]
+pub fn detect_distro() -> Distro {
+    fs::read_to_string("/etc/os-release")
+        .map_or(Distro::Unknown, |content| parse_os_release(&content))
+}
+
+fn parse_os_release(content: &str) -> Distro {
+    let mut ids = Vec::new();
+    for line in content.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            if key == "ID" || key == "ID_LIKE" {
+                ids.extend(
+                    value
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .map(ToString::to_string),
+                );
+            }
+        }
+    }
+    for id in &ids {
+        let distro = match id.as_str() {
+            "debian" | "ubuntu" => Distro::Debian,
+            "fedora" | "rhel" | "centos" => Distro::Fedora,
+            "arch" => Distro::Arch,
+            "opensuse" | "suse" | "opensuse-leap" | "opensuse-tumbleweed" => Distro::OpenSuse,
+            "alpine" => Distro::Alpine,
+            _ => continue,
+        };
+        return distro;
+    }
+    Distro::Unknown
+}
+
+/// Package names of tools per distribution: debian, fedora, arch, opensuse, alpine.
+const PACKAGES: &[(&str, [&str; 5])] = &[
+    (
+        "rpmbuild",
+        ["rpm", "rpm-build", "rpm-tools", "rpm-build", "rpm"],
+    ),
+    (
+        "makensis",
+        ["nsis", "mingw32-nsis", "nsis", "mingw32-cross-nsis", "nsis"],
+    ),
+    (
+        "ldd",
+        ["libc-bin", "glibc-common", "glibc", "glibc", "musl-utils"],
+    ),
+    ("git", ["git", "git", "git", "git", "git"]),
+    (
+        "genisoimage",
+        [
+            "genisoimage",
+            "genisoimage",
+            "cdrkit",
+            "genisoimage",
+            "cdrkit",
+        ],
+    ),
+    ("gpg", ["gnupg", "gnupg2", "gnupg", "gpg2", "gnupg"]),
+    (
+        "pkg-config",
+        [
+            "pkg-config",
+            "pkgconf-pkg-config",
+            "pkgconf",
+            "pkg-config",
+            "pkgconf",
+        ],
+    ),
+    (
+        "qmake",
+        [
+            "qt5-qmake",
+            "qt5-qtbase-devel",
+            "qt5-base",
+            "libqt5-qtbase-common-devel",
+            "qt5-qtbase-dev",
+        ],
+    ),
+    (
+        "qmake6",
+        [
+            "qmake6",
+            "qt6-qtbase-devel",
+            "qt6-base",
+            "qt6-base-common-devel",
+            "qt6-qtbase-dev",
+        ],
+    ),
+    (
+        "flatpak-builder",
+        [
+            "flatpak-builder",
+            "flatpak-builder",
+            "flatpak-builder",
+            "flatpak-builder",
+            "flatpak-builder",
+        ],
+    ),
+    (
+        "flatpak",
+        ["flatpak", "flatpak", "flatpak", "flatpak", "flatpak"],
+    ),
+    (
+        "openssl",
+        ["openssl", "openssl", "openssl", "openssl", "openssl"],
+    ),
+    (
+        "mksquashfs",
+        [
+            "squashfs-tools",
+            "squashfs-tools",
+            "squashfs-tools",
+            "squashfs",
+            "squashfs-tools",
+        ],
+    ),
+];
+
+/// Get install command of tool on `distro`.
+fn install_hint(name: &str, distro: Distro) -> String {
+    if name.starts_with("appimagetool-") {
+        return "run `pifu --download`".to_string();
+    }
+    match name {
+        "cargo" => return "install rust toolchain from https://rustup.rs".to_string(),
+        "cross" => return "run `cargo install cross`".to_string(),
+        "cargo-zigbuild" => return "run `cargo install cargo-zigbuild`".to_string(),
+        _ => (),
+    }
+    let Some((_, packages)) = PACKAGES.iter().find(|(tool, _)| *tool == name) else {
+        return format!("install `{}` to $PATH or set `command` of plugin", name);
+    };
+
+    let (index, command) = match distro {
+        Distro::Debian => (0, "sudo apt install"),
+        Distro::Fedora => (1, "sudo dnf install"),
+        Distro::Arch => (2, "sudo pacman -S"),
+        Distro::OpenSuse => (3, "sudo zypper install"),
+        Distro::Alpine => (4, "sudo apk add"),
+        Distro::Unknown => {
+            return format!("install the package providing `{}`", name);
+        }
+    };
+    format!("{} {}", command, packages[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_os_release() {
+        let content = "NAME=\"Linux Mint\"\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\n";
+        assert_eq!(parse_os_release(content), Distro::Debian);
+        assert_eq!(parse_os_release("ID=fedora\n"), Distro::Fedora);
+        assert_eq!(parse_os_release("ID=gentoo\n"), Distro::Unknown);
+    }
+
+    #[test]
+    fn test_install_hint() {
+        assert_eq!(
+            install_hint("rpmbuild", Distro::Fedora),
+            "sudo dnf install rpm-build"
+        );
+        assert_eq!(
+            install_hint("appimagetool-x86_64.AppImage", Distro::Debian),
+            "run `pifu --download`"
+        );
+    }
+}
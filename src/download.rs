@@ -2,94 +2,410 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+//! Download manager of external tools.
+//!
+//! Tools are installed to `<binary_dir>/<tool>/<version>/<filename>`, so that
+//! multiple versions of the same tool can live side by side. Version of a tool
+//! can be pinned in `[tools]` section of config file, newest version in
+//! download list is used by default.
+//!
+//! Older releases installed tools to `<binary_dir>/<filename>`, these files
+//! are moved to the versioned directory if their hash matches.
+
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::path::Path;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
 use crate::base::config::Arch;
 use crate::base::hash::sha256sum;
-use crate::config::get_binary_dir;
-use crate::Error;
+use crate::config::{get_binary_dir, Config};
+use crate::error::{Error, ErrorKind};
+
+/// Number of attempts to download a file.
+const DOWNLOAD_RETRIES: usize = 3;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TaskList {
+    #[serde(default)]
+    pub tool: Vec<Task>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Task {
+    /// Tool name, like `appimagetool`.
+    pub name: String,
+
+    /// Release version, like `13`.
+    pub version: String,
+
+    pub arch: Arch,
+    pub url: String,
+    pub filename: String,
+    pub sha256: String,
+}
+
+impl Task {
+    /// Get path to installed file.
+    #[must_use]
+    pub fn install_path(&self, binary_dir: &Path) -> PathBuf {
+        binary_dir
+            .join(&self.name)
+            .join(&self.version)
+            .join(&self.filename)
+    }
+
+    /// Get path to file installed by older releases, which use flat layout.
+    #[must_use]
+    pub fn legacy_path(&self, binary_dir: &Path) -> PathBuf {
+        binary_dir.join(&self.filename)
+    }
+}
+
+/// Where to get tools from.
+#[derive(Debug, Default)]
+pub struct DownloadOptions {
+    /// Copy tools from this directory instead of downloading them.
+    pub from_dir: Option<PathBuf>,
+
+    /// Manifest file in the same format as builtin download list,
+    /// its entries override builtin ones with the same tool, version and arch.
+    pub manifest: Option<PathBuf>,
 
-#[derive(Debug, Deserialize, Serialize)]
-struct TaskList {
-    appimagetool: Vec<Task>,
+    /// Pinned tool versions, as in `[tools]` section of config file.
+    /// Default versions are installed for other tools.
+    pub versions: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Task {
+/// State of a tool in binary directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolState {
+    Installed,
+    Missing,
+    HashMismatch,
+}
+
+impl TaskList {
+    /// Merge tasks in `other` into self, tasks with same tool, version and arch
+    /// are replaced.
+    fn merge(&mut self, other: Self) {
+        for task in other.tool {
+            if let Some(old_task) = self.tool.iter_mut().find(|old_task| {
+                old_task.name == task.name
+                    && old_task.version == task.version
+                    && old_task.arch == task.arch
+            }) {
+                *old_task = task;
+            } else {
+                self.tool.push(task);
+            }
+        }
+    }
+
+    /// Get newest version of tool `name`.
+    #[must_use]
+    pub fn default_version(&self, name: &str) -> Option<&str> {
+        self.tool
+            .iter()
+            .filter(|task| task.name == name)
+            .map(|task| task.version.as_str())
+            .max_by(|a, b| compare_versions(a, b))
+    }
+
+    /// Find tool `name` for `arch`, default version is used if `version` is None.
+    #[must_use]
+    pub fn find(&self, name: &str, version: Option<&str>, arch: Arch) -> Option<&Task> {
+        let version = version.or_else(|| self.default_version(name))?;
+        self.tool
+            .iter()
+            .find(|task| task.name == name && task.version == version && task.arch == arch)
+    }
+
+    /// Get tasks of selected version of each tool.
+    fn selected(&self, versions: &BTreeMap<String, String>) -> Vec<&Task> {
+        self.tool
+            .iter()
+            .filter(|task| {
+                versions.get(&task.name).map_or_else(
+                    || self.default_version(&task.name) == Some(task.version.as_str()),
+                    |version| version == &task.version,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Compare version strings part by part, numeric parts are compared by value.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |s: &'_ str| -> Vec<String> {
+        s.split(|c: char| !c.is_ascii_alphanumeric())
+            .map(ToString::to_string)
+            .collect()
+    };
+    let (a_parts, b_parts) = (split(a), split(b));
+    for (a_part, b_part) in a_parts.iter().zip(b_parts.iter()) {
+        let ordering = match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+            (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+            _ => a_part.cmp(b_part),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_parts.len().cmp(&b_parts.len())
+}
+
+/// Parse builtin download list, and merge with user `manifest` if set.
+pub fn read_task_list(manifest: Option<&Path>) -> Result<TaskList, Error> {
+    let task_list_str = include_str!("download-list.toml");
+    let mut task_list: TaskList = toml::from_str(task_list_str)?;
+    if let Some(manifest) = manifest {
+        let content = fs::read_to_string(manifest).map_err(|err| {
+            Error::new(ErrorKind::IoError, "Failed to read tools manifest")
+                .with_source(err)
+                .with_path(manifest)
+        })?;
+        let user_list: TaskList = toml::from_str(&content)
+            .map_err(|err| Error::from_source(ErrorKind::TomlError, err).with_path(manifest))?;
+        task_list.merge(user_list);
+    }
+    Ok(task_list)
+}
+
+/// Get path to tool `name` for `arch`, with version pinned in `[tools]`
+/// section of `conf`, or default version.
+///
+/// The file may not be installed yet.
+pub fn tool_path(
+    conf: &Config,
+    name: &str,
     arch: Arch,
-    url: String,
-    filename: String,
-    sha256: String,
+    manifest: Option<&Path>,
+) -> Result<PathBuf, Error> {
+    let task_list = read_task_list(manifest)?;
+    let version = conf.tools.get(name).map(String::as_str);
+    let task = task_list.find(name, version, arch).ok_or_else(|| {
+        Error::from_string(
+            ErrorKind::ToolNotFoundError,
+            format!(
+                "`{}` version {} for {} not found in download list",
+                name,
+                version.unwrap_or("(default)"),
+                arch
+            ),
+        )
+    })?;
+    let binary_dir = get_binary_dir()?;
+    migrate_legacy_file(task, &binary_dir)?;
+    Ok(task.install_path(&binary_dir))
+}
+
+/// Move tool installed in flat layout to its versioned directory, if it is
+/// not installed yet and its hash matches `task`.
+///
+/// Returns true if file is moved.
+fn migrate_legacy_file(task: &Task, binary_dir: &Path) -> Result<bool, Error> {
+    let legacy_path = task.legacy_path(binary_dir);
+    let filepath = task.install_path(binary_dir);
+    if filepath.exists() || !legacy_path.is_file() {
+        return Ok(false);
+    }
+    if tool_state(task, &legacy_path) != ToolState::Installed {
+        return Ok(false);
+    }
+    if let Some(parent) = filepath.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&legacy_path, &filepath).map_err(|err| Error::from(err).with_path(&legacy_path))?;
+    log::info!("Moved {:?} to {:?}", legacy_path, filepath);
+    Ok(true)
+}
+
+/// Check whether `filepath` exists and matches hash of `task`.
+fn tool_state(task: &Task, filepath: &Path) -> ToolState {
+    if !filepath.exists() {
+        return ToolState::Missing;
+    }
+    match sha256sum(filepath) {
+        Ok(file_hash) if file_hash == task.sha256 => ToolState::Installed,
+        Ok(file_hash) => {
+            log::error!(
+                "Hash mismatch, expected {:?}, got {:?}",
+                task.sha256,
+                &file_hash
+            );
+            ToolState::HashMismatch
+        }
+        Err(err) => {
+            log::error!("Failed to read {:?}, err: {}", filepath, err);
+            ToolState::HashMismatch
+        }
+    }
 }
 
-pub fn download() -> Result<(), Error> {
+pub fn download(options: &DownloadOptions) -> Result<(), Error> {
     // 0. get local binary directory.
     let binary_dir = get_binary_dir()?;
     log::info!("binary dir: {:?}", binary_dir);
-    fs::create_dir_all(&binary_dir)?;
 
     // 1. read and parse file list
-    let task_list_str = include_str!("download-list.toml");
-    let task_list: TaskList = toml::from_str(task_list_str)?;
+    let task_list = read_task_list(options.manifest.as_deref())?;
+    for (name, version) in &options.versions {
+        if !task_list
+            .tool
+            .iter()
+            .any(|task| &task.name == name && &task.version == version)
+        {
+            return Err(Error::from_string(
+                ErrorKind::ToolNotFoundError,
+                format!("`{}` version {} not found in download list", name, version),
+            ));
+        }
+    }
 
-    for task in &task_list.appimagetool {
+    let mut failed = Vec::new();
+    for task in task_list.selected(&options.versions) {
         // 2. check file exists and file hash matches
-        let filepath = Path::new(&binary_dir).join(&task.filename);
-        if filepath.exists() {
-            if let Ok(file_hash) = sha256sum(&filepath) {
-                if file_hash == task.sha256 {
-                    log::info!("Skip exists file: {:?}", &filepath);
-                    continue;
-                }
-
-                log::error!(
-                    "Hash mismatch, expected {:?}, got {:?}",
-                    task.sha256,
-                    &file_hash
-                );
-            }
+        migrate_legacy_file(task, &binary_dir)?;
+        let filepath = task.install_path(&binary_dir);
+        if tool_state(task, &filepath) == ToolState::Installed {
+            log::info!("Skip exists file: {:?}", &filepath);
+            continue;
+        }
+        if let Some(parent) = filepath.parent() {
+            fs::create_dir_all(parent)?;
         }
 
-        for _retry in 0..3 {
-            // 3. download file one by one with reqwest crate
-            if let Err(err) = download_file(&task.url, &filepath) {
-                log::error!("Failed to download {:?}, got error: {:?}", &task.url, err);
-                continue;
-            }
+        let installed = options.from_dir.as_ref().map_or_else(
+            || install_from_url(task, &filepath),
+            |from_dir| install_from_dir(task, from_dir, &filepath),
+        );
+        if installed && cfg!(unix) {
+            add_executable_permission(&filepath)?;
+        } else if !installed {
+            failed.push(format!("{} {} ({})", task.name, task.version, task.arch));
+        }
+    }
 
-            // 4. check downloaded file hash
-            match sha256sum(&filepath) {
-                Ok(file_hash) => {
-                    if file_hash == task.sha256 {
-                        if cfg!(unix) {
-                            add_executable_permission(&filepath)?;
-                        }
-                        break;
-                    }
-
-                    log::error!(
-                        "Hash mismatch, expected {:?}, got {:?}",
-                        task.sha256,
-                        &file_hash
-                    );
-                }
-                Err(err) => log::error!("err: {:?}", err),
-            }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::from_string(
+            ErrorKind::ToolNotFoundError,
+            format!("Failed to install {}", failed.join(", ")),
+        ))
+    }
+}
+
+/// Copy tool from local directory, returns true if succeeded.
+///
+/// `from_dir` may be a copy of binary directory, or contain files directly.
+fn install_from_dir(task: &Task, from_dir: &Path, filepath: &Path) -> bool {
+    let mut src_file = task.install_path(from_dir);
+    if !src_file.exists() {
+        src_file = from_dir.join(&task.filename);
+    }
+    if !src_file.exists() {
+        log::warn!("{:?} not found in {:?}", &task.filename, from_dir);
+        return false;
+    }
+    if let Err(err) = fs::copy(&src_file, filepath) {
+        log::error!("Failed to copy {:?}, got error: {}", &src_file, err);
+        return false;
+    }
+    tool_state(task, filepath) == ToolState::Installed
+}
+
+/// Download tool from url of task, returns true if succeeded.
+///
+/// File is downloaded to `<filepath>.part` first, and download is resumed
+/// from it on retry.
+fn install_from_url(task: &Task, filepath: &Path) -> bool {
+    let mut part_file = filepath.as_os_str().to_os_string();
+    part_file.push(".part");
+    let part_file = PathBuf::from(part_file);
+
+    for _retry in 0..DOWNLOAD_RETRIES {
+        // 3. download file one by one with reqwest crate
+        if let Err(err) = download_file(&task.url, &part_file) {
+            log::error!("Failed to download {:?}, got error: {}", &task.url, err);
+            continue;
+        }
+
+        // 4. check downloaded file hash
+        if tool_state(task, &part_file) == ToolState::Installed {
+            return fs::rename(&part_file, filepath).is_ok();
         }
+        // Corrupted file cannot be resumed.
+        let _ = fs::remove_file(&part_file);
+    }
+    false
+}
+
+fn download_file(url: &str, filepath: &Path) -> Result<(), Error> {
+    log::info!("Downloading {} to {:?}", url, filepath);
+    if let Some(path) = url.strip_prefix("file://") {
+        fs::copy(path, filepath)?;
+        return Ok(());
     }
 
+    let offset = fs::metadata(filepath).map_or(0, |meta| meta.len());
+    let client = reqwest::blocking::Client::new();
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let response = request.send()?;
+    let status = response.status();
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // Already downloaded.
+        return Ok(());
+    }
+    let mut response = response.error_for_status()?;
+
+    let (mut fd, mut downloaded) = if status == reqwest::StatusCode::PARTIAL_CONTENT {
+        log::info!("Resume download from {} bytes", offset);
+        (OpenOptions::new().append(true).open(filepath)?, offset)
+    } else {
+        (File::create(filepath)?, 0)
+    };
+    let total = response.content_length().map(|len| len + downloaded);
+    let name = filepath
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let len = response.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        fd.write_all(&buf[..len])?;
+        downloaded += len as u64;
+        print_progress(&name, downloaded, total);
+    }
+    eprintln!();
     Ok(())
 }
 
-fn download_file<P: AsRef<Path>>(url: &str, filepath: P) -> Result<(), Error> {
-    log::info!("Downloading {} to {:?}", url, filepath.as_ref());
-    let mut response = reqwest::blocking::get(url)?;
-    let mut fd = File::create(filepath)?;
-    std::io::copy(&mut response, &mut fd)
-        .map(drop)
-        .map_err(Into::into)
+/// Print download progress to stderr, in the same line.
+fn print_progress(name: &str, downloaded: u64, total: Option<u64>) {
+    const MIB: u64 = 1024 * 1024;
+    let mut stderr = io::stderr();
+    let _ = match total {
+        Some(total) if total > 0 => write!(
+            stderr,
+            "\r{}: {}% ({}/{} MiB)",
+            name,
+            downloaded * 100 / total,
+            downloaded / MIB,
+            total / MIB
+        ),
+        _ => write!(stderr, "\r{}: {} MiB", name, downloaded / MIB),
+    };
+    let _ = stderr.flush();
 }
 
 fn add_executable_permission<P: AsRef<Path>>(filepath: P) -> Result<(), Error> {
@@ -98,3 +414,187 @@ fn add_executable_permission<P: AsRef<Path>>(filepath: P) -> Result<(), Error> {
     perms.set_mode(0o755);
     fs::set_permissions(&filepath, perms).map_err(Into::into)
 }
+
+/// Get state of all tools in download list.
+pub fn list_tools(manifest: Option<&Path>) -> Result<Vec<(Task, PathBuf, ToolState)>, Error> {
+    let binary_dir = get_binary_dir()?;
+    let task_list = read_task_list(manifest)?;
+    Ok(task_list
+        .tool
+        .into_iter()
+        .map(|task| {
+            let filepath = task.install_path(&binary_dir);
+            let state = tool_state(&task, &filepath);
+            (task, filepath, state)
+        })
+        .collect())
+}
+
+/// Remove tools from binary directory, `names` are tool names like `appimagetool`
+/// or with version like `appimagetool@13`. All tools in download list are removed
+/// if `names` is empty.
+///
+/// Returns paths of removed files.
+pub fn remove_tools(names: &[&str], manifest: Option<&Path>) -> Result<Vec<PathBuf>, Error> {
+    let binary_dir = get_binary_dir()?;
+    let task_list = read_task_list(manifest)?;
+    remove_task_files(&task_list, names, &binary_dir)
+}
+
+fn remove_task_files(
+    task_list: &TaskList,
+    names: &[&str],
+    binary_dir: &Path,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut removed = Vec::new();
+    for task in &task_list.tool {
+        let matched = names.is_empty()
+            || names.iter().any(|name| match name.split_once('@') {
+                Some((name, version)) => task.name == name && task.version == version,
+                None => &task.name == name,
+            });
+        if !matched {
+            continue;
+        }
+        let filepath = task.install_path(binary_dir);
+        if filepath.exists() {
+            fs::remove_file(&filepath)?;
+            if let Some(version_dir) = filepath.parent() {
+                remove_empty_dirs(version_dir, binary_dir)?;
+            }
+            removed.push(filepath);
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove `dir` and its parents if they are empty, until `root_dir`.
+fn remove_empty_dirs(dir: &Path, root_dir: &Path) -> Result<(), Error> {
+    let mut dir = dir;
+    while dir != root_dir && dir.starts_with(root_dir) {
+        if fs::read_dir(dir)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(dir)?;
+        match dir.parent() {
+            Some(parent) => dir = parent,
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_task_list() {
+        let mut task_list = read_task_list(None).unwrap();
+        let num_tasks = task_list.tool.len();
+        let user_list: TaskList = toml::from_str(
+            r#"
+[[tool]]
+name = "appimagetool"
+version = "13"
+arch = "x86_64"
+filename = "appimagetool-x86_64.AppImage"
+sha256 = "abc"
+url = "file:///opt/mirror/appimagetool-x86_64.AppImage"
+
+[[tool]]
+name = "appimagetool"
+version = "13.1"
+arch = "x86_64"
+filename = "appimagetool-x86_64.AppImage"
+sha256 = "def"
+url = "file:///opt/mirror/13.1/appimagetool-x86_64.AppImage"
+"#,
+        )
+        .unwrap();
+        task_list.merge(user_list);
+        assert_eq!(task_list.tool.len(), num_tasks + 1);
+        assert_eq!(task_list.default_version("appimagetool"), Some("13.1"));
+        let task = task_list
+            .find("appimagetool", Some("13"), Arch::X86_64)
+            .unwrap();
+        assert!(task.url.starts_with("file://"));
+        assert!(task_list
+            .find("appimagetool", None, Arch::AArch64)
+            .is_none());
+    }
+
+    #[test]
+    fn test_migrate_legacy_file() {
+        let binary_dir = tempfile::tempdir().unwrap();
+        let binary_dir = binary_dir.path();
+        let mut task = read_task_list(None)
+            .unwrap()
+            .find("appimagetool", None, Arch::X86_64)
+            .unwrap()
+            .clone();
+        let legacy_path = task.legacy_path(binary_dir);
+        fs::write(&legacy_path, "appimagetool").unwrap();
+
+        // Hash mismatch, file is kept.
+        assert!(!migrate_legacy_file(&task, binary_dir).unwrap());
+        assert!(legacy_path.exists());
+
+        task.sha256 = sha256sum(&legacy_path).unwrap();
+        assert!(migrate_legacy_file(&task, binary_dir).unwrap());
+        assert!(!legacy_path.exists());
+        assert_eq!(
+            fs::read_to_string(task.install_path(binary_dir)).unwrap(),
+            "appimagetool"
+        );
+    }
+
+    #[test]
+    fn test_remove_task_files() {
+        let binary_dir = tempfile::tempdir().unwrap();
+        let binary_dir = binary_dir.path();
+        let task_list: TaskList = toml::from_str(
+            r#"
+[[tool]]
+name = "nsis"
+version = "3.08"
+arch = "x86_64"
+filename = "makensis"
+sha256 = "abc"
+url = "file:///opt/mirror/makensis"
+
+[[tool]]
+name = "nsis"
+version = "3.09"
+arch = "x86_64"
+filename = "makensis"
+sha256 = "def"
+url = "file:///opt/mirror/makensis"
+"#,
+        )
+        .unwrap();
+        for task in &task_list.tool {
+            let filepath = task.install_path(binary_dir);
+            fs::create_dir_all(filepath.parent().unwrap()).unwrap();
+            fs::write(&filepath, "makensis").unwrap();
+        }
+
+        let removed = remove_task_files(&task_list, &["nsis@3.08"], binary_dir).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!binary_dir.join("nsis/3.08").exists());
+        assert!(binary_dir.join("nsis/3.09/makensis").exists());
+
+        let removed = remove_task_files(&task_list, &["nsis"], binary_dir).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!binary_dir.join("nsis").exists());
+        assert!(binary_dir.exists());
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("13", "9"), Ordering::Greater);
+        assert_eq!(compare_versions("2.5", "2.10"), Ordering::Less);
+        assert_eq!(compare_versions("3.09", "3.9"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0", "1.0.1"), Ordering::Less);
+    }
+}
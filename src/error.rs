@@ -2,13 +2,17 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
+use std::error;
+use std::fmt::{self, Write};
 use std::io;
-use std::path;
+use std::path::{self, Path, PathBuf};
 use std::string;
 use std::time;
 
+use crate::base::Arch;
+
 /// Prepresent the type of errors.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorKind {
     IoError,
 
@@ -30,6 +34,9 @@ pub enum ErrorKind {
 
     RpmCompilerError,
 
+    /// `cargo build` or `cross build` failed.
+    CargoBuildError,
+
     /// Failed to get git commit hash.
     /// `git` command not found or this is not a git repo.
     GitHashError,
@@ -56,26 +63,156 @@ pub enum ErrorKind {
     HttpError,
 
     CmdlineError,
+
+    /// File already exists and shall not be overwritten.
+    FileExistsError,
+
+    FmtError,
+    PluginError,
+
+    /// Tools required by enabled targets are missing.
+    ToolNotFoundError,
+
+    /// Failed to read or resize icon.
+    ImageError,
+
+    /// `gpg` or other signing tool failed.
+    SignError,
+
+    /// `flatpak-builder` or `flatpak build-bundle` failed.
+    FlatpakBuilderError,
+
+    /// `mksquashfs` failed to pack snap.
+    MksquashfsError,
+
+    /// `genisoimage` failed to create dmg.
+    GenisoimageError,
+}
+
+impl ErrorKind {
+    /// Exit code of command line tool when error of this kind occurs.
+    ///
+    /// - 2: invalid command line arguments
+    /// - 3: invalid config file
+    /// - 4: failed to compile rust project
+    /// - 5: external packaging tool is missing or failed
+    /// - 6: network error
+    /// - 1: other errors
+    #[must_use]
+    pub const fn exit_code(self) -> u8 {
+        match self {
+            Self::CmdlineError => 2,
+            Self::InvalidConfError
+            | Self::TomlError
+            | Self::FilesNotSet
+            | Self::EnvironmentNotSetError
+            | Self::FileExistsError => 3,
+            Self::CargoBuildError => 4,
+            Self::AppImageCompilerError
+            | Self::NsisCompilerError
+            | Self::RpmCompilerError
+            | Self::PluginError
+            | Self::SignError
+            | Self::FlatpakBuilderError
+            | Self::MksquashfsError
+            | Self::GenisoimageError
+            | Self::ToolNotFoundError => 5,
+            Self::HttpError => 6,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::IoError => "I/O error",
+            Self::FilesNotSet => "`files` not set",
+            Self::Lz2EncodeError => "xz encoder error",
+            Self::WalkDirError => "failed to walk directory",
+            Self::StripPrefixError => "invalid path prefix",
+            Self::SystemTimeError => "invalid system time",
+            Self::InvalidConfError => "invalid config",
+            Self::AppImageCompilerError => "appimagetool error",
+            Self::NsisCompilerError => "makensis error",
+            Self::RpmCompilerError => "rpmbuild error",
+            Self::CargoBuildError => "failed to compile project",
+            Self::GitHashError => "failed to get git commit hash",
+            Self::EnvironmentNotSetError => "environment variable not set",
+            Self::Utf8Error => "invalid utf-8 string",
+            Self::JsonError => "json error",
+            Self::TomlError => "toml error",
+            Self::RegexError => "invalid regex",
+            Self::InvalidDirname => "invalid directory name",
+            Self::GlobPatternError => "invalid glob pattern",
+            Self::GlobError => "glob error",
+            Self::HomeDirError => "invalid home directory",
+            Self::HttpError => "http error",
+            Self::CmdlineError => "invalid command line argument",
+            Self::FileExistsError => "file already exists",
+            Self::FmtError => "format error",
+            Self::PluginError => "plugin error",
+            Self::ToolNotFoundError => "required tools not found",
+            Self::ImageError => "image error",
+            Self::SignError => "signing error",
+            Self::FlatpakBuilderError => "flatpak-builder error",
+            Self::MksquashfsError => "mksquashfs error",
+            Self::GenisoimageError => "genisoimage error",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Where and while doing what the error occurs.
+#[derive(Debug, Default, Clone)]
+pub struct ErrorContext {
+    /// Name of package target or plugin, like `deb`.
+    pub target: Option<String>,
+
+    pub arch: Option<Arch>,
+
+    /// File being processed.
+    pub path: Option<PathBuf>,
+
+    /// External command line.
+    pub command: Option<String>,
+
+    /// Captured stderr output of external command.
+    pub stderr: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
     message: String,
+    // Boxed to keep `Result<T, Error>` small.
+    context: Box<ErrorContext>,
+    source: Option<Box<dyn error::Error + Send + Sync + 'static>>,
 }
 
 impl Error {
     #[must_use]
     pub fn new(kind: ErrorKind, message: &str) -> Self {
+        Self::from_string(kind, message.to_owned())
+    }
+
+    #[must_use]
+    pub fn from_string(kind: ErrorKind, message: String) -> Self {
         Self {
             kind,
-            message: message.to_owned(),
+            message,
+            context: Box::default(),
+            source: None,
         }
     }
 
+    /// Wrap underlying error, message is description of `kind`.
     #[must_use]
-    pub const fn from_string(kind: ErrorKind, message: String) -> Self {
-        Self { kind, message }
+    pub fn from_source<E>(kind: ErrorKind, source: E) -> Self
+    where
+        E: error::Error + Send + Sync + 'static,
+    {
+        Self::from_string(kind, String::new()).with_source(source)
     }
 
     #[must_use]
@@ -87,76 +224,218 @@ impl Error {
     pub fn message(&self) -> &str {
         &self.message
     }
+
+    #[must_use]
+    pub const fn context(&self) -> &ErrorContext {
+        &self.context
+    }
+
+    #[must_use]
+    pub fn with_source<E>(mut self, source: E) -> Self
+    where
+        E: error::Error + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Set target name if it is not set yet.
+    #[must_use]
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.context
+            .target
+            .get_or_insert_with(|| target.to_string());
+        self
+    }
+
+    /// Set arch if it is not set yet.
+    #[must_use]
+    pub fn with_arch(mut self, arch: Arch) -> Self {
+        self.context.arch.get_or_insert(arch);
+        self
+    }
+
+    #[must_use]
+    pub fn with_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.context.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    #[must_use]
+    pub fn with_command(mut self, command: &str) -> Self {
+        self.context.command = Some(command.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn with_stderr(mut self, stderr: &str) -> Self {
+        self.context.stderr = Some(stderr.to_string());
+        self
+    }
+
+    /// Render error message with its context and chain of source errors.
+    #[must_use]
+    pub fn report(&self) -> String {
+        let mut out = self.to_string();
+        let context = &self.context;
+        // Writing to string never fails.
+        if let Some(target) = context.target.as_ref() {
+            let _ = write!(out, "\n  target: {}", target);
+        }
+        if let Some(arch) = context.arch {
+            let _ = write!(out, "\n  arch: {}", arch);
+        }
+        if let Some(path) = context.path.as_ref() {
+            let _ = write!(out, "\n  path: {}", path.display());
+        }
+        if let Some(command) = context.command.as_ref() {
+            let _ = write!(out, "\n  command: {}", command);
+        }
+
+        let mut source = error::Error::source(self);
+        if source.is_some() {
+            out.push_str("\nCaused by:");
+        }
+        while let Some(err) = source {
+            let _ = write!(out, "\n  {}", err);
+            source = err.source();
+        }
+
+        if let Some(stderr) = context.stderr.as_ref() {
+            out.push_str("\nstderr:");
+            for line in stderr.lines() {
+                let _ = write!(out, "\n  | {}", line);
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.message.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|err| err.as_ref() as &(dyn error::Error + 'static))
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(err: fmt::Error) -> Self {
+        Self::from_source(ErrorKind::FmtError, err)
+    }
 }
 
 impl From<glob::GlobError> for Error {
     fn from(err: glob::GlobError) -> Self {
-        Self::from_string(ErrorKind::GlobError, format!("{}", err))
+        Self::from_source(ErrorKind::GlobError, err)
     }
 }
 
 impl From<glob::PatternError> for Error {
     fn from(err: glob::PatternError) -> Self {
-        Self::from_string(ErrorKind::GlobPatternError, format!("{}", err))
+        Self::from_source(ErrorKind::GlobPatternError, err)
     }
 }
 
 impl From<regex::Error> for Error {
     fn from(err: regex::Error) -> Self {
-        Self::from_string(ErrorKind::RegexError, format!("{}", err))
+        Self::from_source(ErrorKind::RegexError, err)
     }
 }
 
 impl From<serde_json::Error> for Error {
     fn from(err: serde_json::Error) -> Self {
-        Self::from_string(ErrorKind::JsonError, format!("{}", err))
+        Self::from_source(ErrorKind::JsonError, err)
     }
 }
 
 impl From<time::SystemTimeError> for Error {
     fn from(err: time::SystemTimeError) -> Self {
-        Self::from_string(ErrorKind::SystemTimeError, format!("{}", err))
+        Self::from_source(ErrorKind::SystemTimeError, err)
     }
 }
 
 impl From<xz2::stream::Error> for Error {
     fn from(err: xz2::stream::Error) -> Self {
-        Self::from_string(ErrorKind::Lz2EncodeError, format!("{}", err))
+        Self::from_source(ErrorKind::Lz2EncodeError, err)
     }
 }
 
 impl From<walkdir::Error> for Error {
     fn from(err: walkdir::Error) -> Self {
-        Self::from_string(ErrorKind::WalkDirError, format!("{}", err))
+        Self::from_source(ErrorKind::WalkDirError, err)
     }
 }
 
 impl From<path::StripPrefixError> for Error {
     fn from(err: path::StripPrefixError) -> Self {
-        Self::from_string(ErrorKind::StripPrefixError, format!("{}", err))
+        Self::from_source(ErrorKind::StripPrefixError, err)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Self::from_source(ErrorKind::ImageError, err)
     }
 }
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        Self::from_string(ErrorKind::IoError, format!("{}", err))
+        Self::from_source(ErrorKind::IoError, err)
     }
 }
 
 impl From<string::FromUtf8Error> for Error {
     fn from(err: string::FromUtf8Error) -> Self {
-        Self::from_string(ErrorKind::Utf8Error, format!("{}", err))
+        Self::from_source(ErrorKind::Utf8Error, err)
     }
 }
 
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
-        Self::from_string(ErrorKind::HttpError, format!("{}", err))
+        Self::from_source(ErrorKind::HttpError, err)
     }
 }
 
 impl From<toml::de::Error> for Error {
     fn from(err: toml::de::Error) -> Self {
-        Self::from_string(ErrorKind::TomlError, format!("{}", err))
+        Self::from_source(ErrorKind::TomlError, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let io_err = io::Error::new(io::ErrorKind::NotFound, "No such file");
+        let err = Error::new(ErrorKind::RpmCompilerError, "Failed to run `rpmbuild`")
+            .with_source(io_err)
+            .with_command("rpmbuild -bb app.spec")
+            .with_target("rpm")
+            .with_arch(Arch::X86_64)
+            .with_target("deb");
+        assert_eq!(err.to_string(), "Failed to run `rpmbuild`");
+        assert_eq!(err.kind().exit_code(), 5);
+        assert_eq!(
+            err.report(),
+            "Failed to run `rpmbuild`\n  target: rpm\n  arch: x86_64\n  \
+             command: rpmbuild -bb app.spec\nCaused by:\n  No such file"
+        );
+
+        let err = Error::from(io::Error::other("oops"));
+        assert_eq!(err.to_string(), "I/O error");
+        assert_eq!(err.report(), "I/O error\nCaused by:\n  oops");
     }
 }
@@ -0,0 +1,185 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::manifest::{
+    flatpak_arch, generate_manifest, validate_app_id, validate_flatpak, FILES_DIR,
+};
+use crate::appstream::install_metainfo;
+use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::check::reject_errors;
+use crate::config::{Config, LinuxConfig};
+use crate::desktop::install_desktop;
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of flatpak format.
+///
+/// `flatpak-builder` and `flatpak` commands are required to build bundle.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FlatpakPackager;
+
+impl Packager for FlatpakPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Flatpak
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_flatpak(conf, linux_conf, arch, &runner)
+    }
+}
+
+/// Generate manifest, and build `.flatpak` bundle if `bundle` is enabled.
+///
+/// Returns path to manifest file and the bundle.
+pub fn build_flatpak(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let flatpak_conf = &linux_conf.flatpak;
+    reject_errors("linux.flatpak", &validate_flatpak(flatpak_conf))?;
+    validate_app_id(&conf.metadata.app_id).map_err(|message| {
+        Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Invalid config: `metadata.app_id` {}", message),
+        )
+    })?;
+
+    let files = if let Some(files) = flatpak_conf.files.as_ref() {
+        files
+    } else if let Some(files) = linux_conf.files.as_ref() {
+        files
+    } else {
+        return Err(Error::new(
+            ErrorKind::FilesNotSet,
+            "`files` property not set for flatpak format",
+        ));
+    };
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    let flatpak_dir = workdir.join("flatpak").join(arch.to_string());
+    utils::rmdir(&flatpak_dir)?;
+
+    let files_dir = flatpak_dir.join(FILES_DIR);
+    fs::create_dir_all(&files_dir)?;
+    copy_filesets(files, conf, arch, PlatformTarget::Flatpak, &files_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::Flatpak,
+            &files_dir,
+            false,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &files_dir)?;
+    }
+
+    let manifest = generate_manifest(conf, flatpak_conf, install_commands(&files_dir)?)?;
+    let manifest_file = flatpak_dir.join(format!("{}.json", conf.metadata.app_id));
+    fs::write(&manifest_file, manifest)?;
+    let mut artifacts = vec![manifest_file.clone()];
+
+    if flatpak_conf.bundle {
+        let artifact_name = expand_file_macro(
+            &flatpak_conf.artifact_name,
+            conf,
+            arch,
+            PlatformTarget::Flatpak,
+        )?;
+        let artifact = workdir.join(artifact_name);
+        build_bundle(conf, &flatpak_dir, &manifest_file, &artifact, arch, runner)?;
+        artifacts.push(artifact);
+    }
+    Ok(artifacts)
+}
+
+/// Commands to copy pre-built files to `/app`.
+///
+/// Files in `usr` are copied to `/app` directly, as `/usr` is taken by runtime.
+fn install_commands(files_dir: &Path) -> Result<Vec<String>, Error> {
+    let mut names = fs::read_dir(files_dir)?
+        .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    let commands = names
+        .iter()
+        .map(|name| {
+            if name == "usr" {
+                "cp -a usr/. /app/".to_string()
+            } else {
+                format!("cp -a '{}' /app/", name.replace('\'', r"'\''"))
+            }
+        })
+        .collect();
+    Ok(commands)
+}
+
+fn build_bundle(
+    conf: &Config,
+    flatpak_dir: &Path,
+    manifest_file: &Path,
+    artifact: &Path,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<(), Error> {
+    let arch_arg = format!("--arch={}", flatpak_arch(arch));
+    let repo_dir = flatpak_dir.join("repo");
+
+    let mut cmd = Command::new("flatpak-builder");
+    cmd.arg("--force-clean")
+        .arg(&arch_arg)
+        .arg(format!(
+            "--state-dir={}",
+            flatpak_dir.join(".flatpak-builder").display()
+        ))
+        .arg(format!("--repo={}", repo_dir.display()))
+        .arg(flatpak_dir.join("build"))
+        .arg(manifest_file);
+    runner.run(&mut cmd, ErrorKind::FlatpakBuilderError)?;
+
+    let mut cmd = Command::new("flatpak");
+    cmd.arg("build-bundle")
+        .arg(&arch_arg)
+        .arg(&repo_dir)
+        .arg(artifact)
+        .arg(&conf.metadata.app_id);
+    runner.run(&mut cmd, ErrorKind::FlatpakBuilderError)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_commands() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        fs::create_dir_all(dir.join("usr/bin")).unwrap();
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        assert_eq!(
+            install_commands(dir).unwrap(),
+            vec!["cp -a 'lib' /app/", "cp -a usr/. /app/"]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::base::fileset::FileSet;
+use crate::base::utils::default_false;
+use crate::config::Config;
+
+/// Generate flatpak-builder manifest, `id` is read from `metadata.app_id`.
+///
+/// Files are installed to `/app`, with leading `usr/` stripped.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlatpakConfig {
+    /// File list.
+    pub files: Option<Vec<FileSet>>,
+
+    /// String - Runtime id, default is `org.freedesktop.Platform`.
+    #[serde(default = "default_runtime")]
+    pub runtime: String,
+
+    /// String - Version of runtime and sdk, default is `24.08`.
+    #[serde(default = "default_runtime_version")]
+    pub runtime_version: String,
+
+    /// String - Sdk id, default is `org.freedesktop.Sdk`.
+    #[serde(default = "default_sdk")]
+    pub sdk: String,
+
+    /// String - Executable in `/app/bin` to run, default is package name.
+    pub command: Option<String>,
+
+    /// Array of String - Sandbox permissions, like `["--share=ipc", "--socket=wayland"]`.
+    #[serde(default)]
+    pub finish_args: Vec<String>,
+
+    /// Boolean - whether to build `.flatpak` bundle with `flatpak-builder` and
+    /// `flatpak build-bundle`. Runtime and sdk shall be installed.
+    ///
+    /// Only the manifest is generated if disabled.
+    #[serde(default = "default_false")]
+    pub bundle: bool,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+impl FlatpakConfig {
+    /// Get command to run, default is package name.
+    #[must_use]
+    pub fn command<'a>(&'a self, conf: &'a Config) -> &'a str {
+        self.command.as_deref().unwrap_or(&conf.metadata.name)
+    }
+}
+
+impl Default for FlatpakConfig {
+    fn default() -> Self {
+        Self {
+            files: None,
+            runtime: default_runtime(),
+            runtime_version: default_runtime_version(),
+            sdk: default_sdk(),
+            command: None,
+            finish_args: Vec::new(),
+            bundle: false,
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_runtime() -> String {
+    "org.freedesktop.Platform".to_string()
+}
+
+fn default_runtime_version() -> String {
+    "24.08".to_string()
+}
+
+fn default_sdk() -> String {
+    "org.freedesktop.Sdk".to_string()
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
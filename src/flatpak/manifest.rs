@@ -0,0 +1,178 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use serde::Serialize;
+
+use super::config::FlatpakConfig;
+use crate::base::Arch;
+use crate::check::Issue;
+use crate::config::Config;
+use crate::error::Error;
+
+/// Directory containing pre-built files, relative to manifest.
+pub const FILES_DIR: &str = "files";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Manifest<'a> {
+    app_id: &'a str,
+    runtime: &'a str,
+    runtime_version: &'a str,
+    sdk: &'a str,
+    command: &'a str,
+    finish_args: &'a [String],
+    modules: Vec<Module<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Module<'a> {
+    name: &'a str,
+    buildsystem: &'static str,
+    build_commands: Vec<String>,
+    sources: Vec<Source>,
+}
+
+#[derive(Debug, Serialize)]
+struct Source {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    path: &'static str,
+}
+
+/// Get architecture name used by flatpak.
+#[must_use]
+pub const fn flatpak_arch(arch: Arch) -> &'static str {
+    match arch {
+        Arch::X86 => "i386",
+        Arch::X86_64 => "x86_64",
+        Arch::AArch64 => "aarch64",
+        Arch::Mips64 => "mips64",
+    }
+}
+
+/// Flatpak application id has at least 3 elements separated by dots, each
+/// element only contains `[A-Za-z0-9_-]` and does not start with a digit.
+pub fn validate_app_id(app_id: &str) -> Result<(), String> {
+    let elements = app_id.split('.').collect::<Vec<_>>();
+    if elements.len() < 3 {
+        return Err(format!(
+            "{:?} is invalid for flatpak, it shall have at least 3 parts, like `org.example.app`",
+            app_id
+        ));
+    }
+    let is_valid_element = |element: &&str| {
+        !element.is_empty()
+            && !element.starts_with(|c: char| c.is_ascii_digit())
+            && element
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    if let Some(element) = elements.iter().find(|element| !is_valid_element(element)) {
+        return Err(format!(
+            "{:?} is invalid for flatpak, part {:?} is not allowed",
+            app_id, element
+        ));
+    }
+    Ok(())
+}
+
+#[must_use]
+pub fn validate_flatpak(flatpak_conf: &FlatpakConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let values = [
+        ("runtime", &flatpak_conf.runtime),
+        ("runtime_version", &flatpak_conf.runtime_version),
+        ("sdk", &flatpak_conf.sdk),
+    ];
+    for (key, value) in values {
+        if value.trim().is_empty() {
+            issues.push(Issue::error(key, "is empty".to_string()));
+        }
+    }
+    if flatpak_conf
+        .command
+        .as_ref()
+        .is_some_and(|command| command.trim().is_empty())
+    {
+        issues.push(Issue::error("command", "is empty".to_string()));
+    }
+    for arg in &flatpak_conf.finish_args {
+        if !arg.starts_with("--") {
+            issues.push(Issue::error(
+                "finish_args",
+                format!("contains {:?}, which shall start with `--`", arg),
+            ));
+        }
+    }
+    issues
+}
+
+/// Generate flatpak-builder manifest in json format.
+///
+/// The only module runs `build_commands` in directory of pre-built files.
+pub fn generate_manifest(
+    conf: &Config,
+    flatpak_conf: &FlatpakConfig,
+    build_commands: Vec<String>,
+) -> Result<String, Error> {
+    let manifest = Manifest {
+        app_id: &conf.metadata.app_id,
+        runtime: &flatpak_conf.runtime,
+        runtime_version: &flatpak_conf.runtime_version,
+        sdk: &flatpak_conf.sdk,
+        command: flatpak_conf.command(conf),
+        finish_args: &flatpak_conf.finish_args,
+        modules: vec![Module {
+            name: &conf.metadata.name,
+            buildsystem: "simple",
+            build_commands,
+            sources: vec![Source {
+                kind: "dir",
+                path: FILES_DIR,
+            }],
+        }],
+    };
+    let mut json = serde_json::to_string_pretty(&manifest)?;
+    json.push('\n');
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_validate_app_id() {
+        assert!(validate_app_id("org.biofan.pifu").is_ok());
+        assert!(validate_app_id("io.github.user.my-app").is_ok());
+        assert!(validate_app_id("pifu").is_err());
+        assert!(validate_app_id("org.biofan.2048").is_err());
+        assert!(validate_app_id("org..pifu").is_err());
+    }
+
+    #[test]
+    fn test_generate_manifest() {
+        let mut metadata = Metadata::new("hello", "1.0.0");
+        metadata.app_id = "org.biofan.hello".to_string();
+        let conf = Config::new(metadata);
+        let flatpak_conf = FlatpakConfig {
+            finish_args: vec!["--socket=wayland".to_string()],
+            ..FlatpakConfig::default()
+        };
+        let json =
+            generate_manifest(&conf, &flatpak_conf, vec!["cp -a usr/. /app/".to_string()]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["app-id"], "org.biofan.hello");
+        assert_eq!(value["runtime"], "org.freedesktop.Platform");
+        assert_eq!(value["command"], "hello");
+        assert_eq!(value["finish-args"][0], "--socket=wayland");
+        let module = &value["modules"][0];
+        assert_eq!(module["buildsystem"], "simple");
+        assert_eq!(module["build-commands"][0], "cp -a usr/. /app/");
+        assert_eq!(module["sources"][0]["type"], "dir");
+        assert_eq!(module["sources"][0]["path"], "files");
+    }
+}
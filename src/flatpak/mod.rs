@@ -0,0 +1,11 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod build;
+mod config;
+mod manifest;
+
+pub use build::FlatpakPackager;
+pub use config::FlatpakConfig;
+pub use manifest::{validate_app_id, validate_flatpak};
@@ -0,0 +1,324 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::inspect::{inspect_project, ProjectInfo};
+use crate::error::{Error, ErrorKind};
+
+const APP_RUN_TEMPLATE: &str = include_str!("templates/AppRun");
+const DESKTOP_TEMPLATE: &str = include_str!("templates/app.desktop");
+const NSIS_INCLUDE_TEMPLATE: &str = include_str!("templates/include.nsi");
+const PLACEHOLDER_ICON: &[u8] = include_bytes!("templates/placeholder.ico");
+
+/// Generate `pkg/pifu.toml` and template files for project located at `dir`.
+///
+/// Returns list of generated files.
+pub fn init_project(dir: &Path, force: bool) -> Result<Vec<PathBuf>, Error> {
+    let info = inspect_project(dir)?;
+    log::info!("init_project() info: {:#?}", info);
+
+    let pkg_dir = dir.join("pkg");
+    let nsis_dir = pkg_dir.join("nsis");
+    let app_run_file = pkg_dir.join("AppRun");
+
+    let mut files = vec![
+        (
+            pkg_dir.join("pifu.toml"),
+            generate_config(&info)?.into_bytes(),
+        ),
+        (
+            app_run_file.clone(),
+            expand_template(APP_RUN_TEMPLATE, &info).into_bytes(),
+        ),
+        (
+            pkg_dir.join(format!("{}.desktop", info.name)),
+            expand_template(DESKTOP_TEMPLATE, &info).into_bytes(),
+        ),
+        (
+            nsis_dir.join("include.nsi"),
+            NSIS_INCLUDE_TEMPLATE.as_bytes().to_vec(),
+        ),
+    ];
+    if info.windows_icon.is_none() {
+        files.push((nsis_dir.join("install.ico"), PLACEHOLDER_ICON.to_vec()));
+        files.push((nsis_dir.join("uninstall.ico"), PLACEHOLDER_ICON.to_vec()));
+    }
+    if !force {
+        if let Some((path, _content)) = files.iter().find(|(path, _content)| path.exists()) {
+            return Err(Error::from_string(
+                ErrorKind::FileExistsError,
+                format!("{:?} already exists, use `--force` to overwrite it", path),
+            ));
+        }
+    }
+
+    let mut generated = Vec::new();
+    for (path, content) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+        generated.push(path);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&app_run_file, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(generated)
+}
+
+fn expand_template(template: &str, info: &ProjectInfo) -> String {
+    template
+        .replace("@NAME@", &info.name)
+        .replace("@EXEC@", &info.binaries[0])
+        .replace("@PRODUCT_NAME@", &product_name(&info.name))
+        .replace("@DESCRIPTION@", &info.description)
+}
+
+/// Convert package name to human readable name, like `hello-world` to `Hello World`.
+fn product_name(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().collect::<String>() + chars.as_str()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Generate application id from homepage, like `https://github.com/foo/bar`
+/// to `com.github.foo.bar`.
+fn app_id(info: &ProjectInfo) -> String {
+    let url = info
+        .homepage
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let mut parts = url.split('/').filter(|part| !part.is_empty());
+    let Some(host) = parts.next() else {
+        return format!("org.example.{}", info.name);
+    };
+    let mut id = host.split('.').rev().collect::<Vec<_>>();
+    id.extend(parts);
+    if id.last() != Some(&info.name.as_str()) {
+        id.push(&info.name);
+    }
+    id.join(".").to_lowercase().replace(
+        |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_',
+        "_",
+    )
+}
+
+/// Quote string as toml string literal.
+fn quote(s: &str) -> String {
+    toml::Value::String(s.to_string()).to_string()
+}
+
+fn generate_config(info: &ProjectInfo) -> Result<String, Error> {
+    let mut conf = String::new();
+    define_metadata(info, &mut conf)?;
+    define_linux(info, &mut conf)?;
+    define_windows(info, &mut conf)?;
+    Ok(conf)
+}
+
+fn define_metadata(info: &ProjectInfo, conf: &mut String) -> Result<(), Error> {
+    let name = &info.name;
+    writeln!(conf, "[metadata]")?;
+    writeln!(conf, "name = {}", quote(name))?;
+    writeln!(conf, "product_name = {}", quote(&product_name(name)))?;
+    writeln!(conf, "app_id = {}", quote(&app_id(info)))?;
+    writeln!(conf, "author = {}", quote(&info.author))?;
+    writeln!(conf, "description = {}", quote(&info.description))?;
+    writeln!(conf, "homepage = {}", quote(&info.homepage))?;
+    writeln!(conf, "version = {}", quote(&info.version))?;
+    writeln!(conf, r#"build_id = "${{date}}""#)?;
+    writeln!(conf, "license = {}", quote(&info.license))?;
+    if let Some(license_file) = info.license_file.as_ref() {
+        writeln!(conf, "license_file = {}", quote(license_file))?;
+    }
+    writeln!(conf)?;
+    writeln!(conf, r#"workdir = "target/pifu""#)?;
+    writeln!(conf, r#"src_dir = ".""#)?;
+    writeln!(conf)?;
+    writeln!(conf, "[build]")?;
+    writeln!(conf)?;
+    Ok(())
+}
+
+fn define_linux(info: &ProjectInfo, conf: &mut String) -> Result<(), Error> {
+    writeln!(conf, "[linux]")?;
+    writeln!(conf, r#"targets = ["deb", "rpm", "app_image"]"#)?;
+    writeln!(conf, r#"arch = ["x86_64"]"#)?;
+    writeln!(conf)?;
+    writeln!(conf, "files = [")?;
+    for binary in &info.binaries {
+        writeln!(
+            conf,
+            "\t{{from = {}, to = {}}},",
+            quote(&format!("${{target_dir}}/{}", binary)),
+            quote(&format!("usr/bin/{}", binary))
+        )?;
+    }
+    writeln!(conf, "]")?;
+    writeln!(conf)?;
+
+    // Desktop entry and hicolor icons are generated from this section.
+    writeln!(
+        conf,
+        "# Or remove this section and copy pkg/{}.desktop in `files` instead.",
+        info.name
+    )?;
+    writeln!(conf, "[linux.desktop]")?;
+    if info.binaries[0] != info.name {
+        writeln!(conf, "exec = {}", quote(&info.binaries[0]))?;
+    }
+    if let Some(icon) = info.icon.as_ref() {
+        writeln!(conf, "icon = {}", quote(icon))?;
+    } else {
+        writeln!(conf, "# TODO: No icon file found, add a png or svg icon.")?;
+        writeln!(conf, "# icon = \"pkg/{}.svg\"", info.name)?;
+    }
+    writeln!(conf, r#"categories = ["Utility"]"#)?;
+    writeln!(conf, "terminal = false")?;
+    writeln!(conf)?;
+
+    writeln!(conf, "[linux.app_image]")?;
+    writeln!(
+        conf,
+        "exe_files = [{}]",
+        quote(&format!("${{target_dir}}/{}", info.binaries[0]))
+    )?;
+    writeln!(conf)?;
+    writeln!(conf, "files = [")?;
+    for binary in &info.binaries {
+        writeln!(
+            conf,
+            "\t{{from = {}, to = {}}},",
+            quote(&format!("${{target_dir}}/{}", binary)),
+            quote(binary)
+        )?;
+    }
+    writeln!(conf, "\t{{from = \"pkg/AppRun\", to = \"AppRun\"}},")?;
+    writeln!(conf, "]")?;
+    writeln!(conf)?;
+    Ok(())
+}
+
+fn define_windows(info: &ProjectInfo, conf: &mut String) -> Result<(), Error> {
+    writeln!(conf, "[windows]")?;
+    writeln!(conf, r#"targets = ["nsis"]"#)?;
+    writeln!(conf, r#"arch = ["x86_64"]"#)?;
+    writeln!(conf)?;
+    writeln!(
+        conf,
+        "exe_file = {}",
+        quote(&format!("{}.exe", info.binaries[0]))
+    )?;
+    writeln!(conf)?;
+    writeln!(conf, "files = [")?;
+    for binary in &info.binaries {
+        writeln!(
+            conf,
+            "\t{{from = {}, to = {}}},",
+            quote(&format!("${{target_dir}}/{}.exe", binary)),
+            quote(&format!("{}.exe", binary))
+        )?;
+    }
+    if let Some(license_file) = info.license_file.as_ref() {
+        writeln!(
+            conf,
+            "\t{{from = {}, to = \"LICENSE.txt\"}},",
+            quote(license_file)
+        )?;
+    }
+    writeln!(conf, "]")?;
+    writeln!(conf)?;
+
+    writeln!(conf, "[windows.nsis]")?;
+    writeln!(conf, r#"include = "pkg/nsis/include.nsi""#)?;
+    writeln!(conf, "one_click = false")?;
+    writeln!(conf, "per_machine = false")?;
+    if let Some(windows_icon) = info.windows_icon.as_ref() {
+        writeln!(conf, "installer_icon = {}", quote(windows_icon))?;
+        writeln!(conf, "uninstaller_icon = {}", quote(windows_icon))?;
+    } else {
+        writeln!(
+            conf,
+            "# TODO: No .ico file found, replace these placeholder icons."
+        )?;
+        writeln!(conf, r#"installer_icon = "pkg/nsis/install.ico""#)?;
+        writeln!(conf, r#"uninstaller_icon = "pkg/nsis/uninstall.ico""#)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_name() {
+        assert_eq!(product_name("hello-world_app"), "Hello World App");
+    }
+
+    #[test]
+    fn test_app_id() {
+        let info = ProjectInfo {
+            name: "pifu".to_string(),
+            homepage: "https://github.com/PifuProject/pifu".to_string(),
+            ..ProjectInfo::default()
+        };
+        assert_eq!(app_id(&info), "com.github.pifuproject.pifu");
+    }
+
+    #[test]
+    fn test_generate_config() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/init-project");
+        let info = inspect_project(&dir).unwrap();
+        let content = generate_config(&info).unwrap();
+        let conf: crate::config::Config = toml::from_str(&content).unwrap();
+        assert_eq!(conf.metadata.name, "hello-world");
+        assert_eq!(conf.metadata.app_id, "com.github.foo.hello_world");
+
+        let linux = conf.linux.unwrap();
+        let desktop = linux.desktop.unwrap();
+        assert_eq!(desktop.exec, None);
+        assert_eq!(desktop.icon.as_deref(), Some("assets/hello-world.svg"));
+        assert_eq!(desktop.categories, ["Utility"]);
+    }
+
+    #[test]
+    fn test_init_and_check() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/init-project");
+        let dir = tempfile::tempdir().unwrap();
+        fs_extra::dir::copy(&fixture, dir.path(), &fs_extra::dir::CopyOptions::new()).unwrap();
+        let project_dir = dir.path().join("init-project");
+
+        let generated = init_project(&project_dir, false).unwrap();
+        assert_eq!(generated.len(), 6);
+        assert!(project_dir.join("pkg/AppRun").is_file());
+        assert!(project_dir.join("pkg/hello-world.desktop").is_file());
+        assert!(project_dir.join("pkg/nsis/install.ico").is_file());
+        assert!(init_project(&project_dir, false).is_err());
+
+        let config_file = project_dir.join("pkg/pifu.toml");
+        let (conf, diagnostics) = crate::check::parse_file(&config_file.to_string_lossy()).unwrap();
+        assert!(diagnostics.is_empty());
+        // `src_dir` is relative to current directory, where pifu runs.
+        let mut conf = conf.unwrap();
+        conf.metadata.src_dir = project_dir.to_string_lossy().to_string();
+        let content = fs::read_to_string(&config_file).unwrap();
+        let diagnostics = crate::check::check_config(&conf, &content, None);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Error;
+
+/// Information of current project, used to generate config file.
+#[derive(Debug, Default)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub license: String,
+    pub author: String,
+    pub homepage: String,
+
+    /// Names of executable files.
+    pub binaries: Vec<String>,
+
+    pub license_file: Option<String>,
+
+    /// Path to png or svg icon.
+    pub icon: Option<String>,
+
+    /// Path to ico file used by windows installer.
+    pub windows_icon: Option<String>,
+}
+
+const LICENSE_FILES: &[&str] = &["LICENSE", "LICENSE.md", "LICENSE.txt", "COPYING"];
+const ICON_DIRS: &[&str] = &[".", "pkg", "assets", "res", "resources", "icons"];
+
+/// Inspect Cargo.toml, binaries, license and icon files in `dir`.
+pub fn inspect_project(dir: &Path) -> Result<ProjectInfo, Error> {
+    let mut info = ProjectInfo {
+        version: "0.1.0".to_string(),
+        ..ProjectInfo::default()
+    };
+
+    let cargo_file = dir.join("Cargo.toml");
+    if cargo_file.exists() {
+        let content = fs::read_to_string(&cargo_file)?;
+        let manifest: toml::Value = toml::from_str(&content)?;
+        read_cargo_manifest(&manifest, &mut info);
+    }
+    if info.name.is_empty() {
+        info.name = dir.canonicalize()?.file_name().map_or_else(
+            || "app".to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+    }
+
+    info.binaries = find_binaries(&dir.join("target").join("release"))?;
+    if info.binaries.is_empty() {
+        info.binaries.push(info.name.clone());
+    } else if let Some(index) = info.binaries.iter().position(|bin| bin == &info.name) {
+        // Main binary comes first.
+        let main_bin = info.binaries.remove(index);
+        info.binaries.insert(0, main_bin);
+    }
+
+    info.license_file = LICENSE_FILES
+        .iter()
+        .find(|file| dir.join(file).is_file())
+        .map(ToString::to_string);
+
+    for icon_dir in ICON_DIRS {
+        let icon_dir = dir.join(icon_dir);
+        if !icon_dir.is_dir() {
+            continue;
+        }
+        if info.icon.is_none() {
+            info.icon = find_file_with_ext(dir, &icon_dir, &["svg", "png"])?;
+        }
+        if info.windows_icon.is_none() {
+            info.windows_icon = find_file_with_ext(dir, &icon_dir, &["ico"])?;
+        }
+    }
+
+    Ok(info)
+}
+
+fn read_cargo_manifest(manifest: &toml::Value, info: &mut ProjectInfo) {
+    let Some(package) = manifest.get("package") else {
+        return;
+    };
+    let get_str = |key: &str| {
+        package
+            .get(key)
+            .and_then(toml::Value::as_str)
+            .map(ToString::to_string)
+    };
+
+    if let Some(name) = get_str("name") {
+        info.name = name;
+    }
+    if let Some(version) = get_str("version") {
+        info.version = version;
+    }
+    info.description = get_str("description").unwrap_or_default();
+    info.license = get_str("license").unwrap_or_default();
+    info.homepage = get_str("homepage")
+        .or_else(|| get_str("repository"))
+        .unwrap_or_default();
+    info.author = package
+        .get("authors")
+        .and_then(toml::Value::as_array)
+        .and_then(|authors| authors.first())
+        .and_then(toml::Value::as_str)
+        .map(ToString::to_string)
+        .unwrap_or_default();
+}
+
+/// Find executable files in cargo output directory.
+fn find_binaries(dir: &Path) -> Result<Vec<String>, Error> {
+    let mut binaries = Vec::new();
+    if !dir.is_dir() {
+        return Ok(binaries);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_executable(&path)? {
+            if let Some(name) = path.file_name() {
+                binaries.push(name.to_string_lossy().to_string());
+            }
+        }
+    }
+    binaries.sort();
+    Ok(binaries)
+}
+
+/// Check ELF or PE magic header of file.
+fn is_executable(path: &Path) -> Result<bool, Error> {
+    let mut magic = [0_u8; 4];
+    let mut fd = File::open(path)?;
+    if fd.read_exact(&mut magic).is_err() {
+        return Ok(false);
+    }
+    let is_exe = path.extension().is_some_and(|ext| ext == "exe");
+    Ok(magic == *b"\x7fELF" || (is_exe && magic[..2] == *b"MZ"))
+}
+
+fn find_file_with_ext(
+    root_dir: &Path,
+    dir: &Path,
+    extensions: &[&str],
+) -> Result<Option<String>, Error> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let matched = path
+            .extension()
+            .is_some_and(|ext| extensions.iter().any(|e| ext == *e));
+        if path.is_file() && matched {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files.first().map(|path| {
+        path.strip_prefix(root_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_project() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/init-project");
+        let info = inspect_project(&dir).unwrap();
+        assert_eq!(info.name, "hello-world");
+        assert_eq!(info.version, "1.2.0");
+        assert_eq!(info.license, "MIT");
+        assert_eq!(info.author, "Foo Bar <foo@example.com>");
+        assert_eq!(info.binaries, ["hello-world"]);
+        assert_eq!(info.license_file.as_deref(), Some("LICENSE"));
+        assert_eq!(info.icon.as_deref(), Some("assets/hello-world.svg"));
+        assert_eq!(info.windows_icon, None);
+    }
+}
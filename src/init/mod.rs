@@ -0,0 +1,8 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod generate;
+mod inspect;
+
+pub use generate::init_project;
@@ -0,0 +1,143 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Ipk package is a gzipped tar archive of `./debian-binary`, `./data.tar.gz`
+//! and `./control.tar.gz`, entries are named with `./` prefix like `ipkg-build`.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::Write;
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::error::Error;
+
+/// Size of `name` field in tar header.
+const NAME_LEN: usize = 100;
+
+/// Append entry named `./<name>`, owned by root, empty name is the root directory.
+fn append_entry<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    path: &Path,
+) -> Result<(), Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(&metadata, tar::HeaderMode::Complete);
+    #[cfg(not(target_os = "windows"))]
+    header.set_mode(metadata.permissions().mode() & 0o7777);
+    header.set_mtime(metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs());
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_username("root")?;
+    header.set_groupname("root")?;
+
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        header.set_link_name(fs::read_link(path)?)?;
+    }
+
+    let name = if name.is_empty() {
+        "./".to_string()
+    } else if file_type.is_dir() {
+        format!("./{}/", name)
+    } else {
+        format!("./{}", name)
+    };
+    if file_type.is_file() {
+        let file = File::open(path)?;
+        append_named(builder, &mut header, &name, file)
+    } else {
+        header.set_size(0);
+        append_named(builder, &mut header, &name, std::io::empty())
+    }
+}
+
+/// `tar::Header::set_path()` strips `./` prefix, so name is written directly
+/// if it fits in header. Longer names are stored in GNU long name entries.
+fn append_named<W: Write, R: std::io::Read>(
+    builder: &mut tar::Builder<W>,
+    header: &mut tar::Header,
+    name: &str,
+    data: R,
+) -> Result<(), Error> {
+    if name.len() <= NAME_LEN {
+        let field = &mut header.as_old_mut().name;
+        field.fill(0);
+        field[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_cksum();
+        builder.append(header, data)?;
+    } else {
+        builder.append_data(header, name, data)?;
+    }
+    Ok(())
+}
+
+fn create_tar_gz<F>(to: &Path, append: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut tar::Builder<GzEncoder<File>>) -> Result<(), Error>,
+{
+    let encoder = GzEncoder::new(File::create(to)?, Compression::best());
+    let mut builder = tar::Builder::new(encoder);
+    append(&mut builder)?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Create gzipped tar archive of files in `dir`, including `./` entry.
+pub fn create_tar_gz_dir(dir: &Path, to: &Path) -> Result<(), Error> {
+    log::info!("create_tar_gz_dir() dir: {:?}, to: {:?}", dir, to);
+    create_tar_gz(to, |builder| {
+        for entry in WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry?;
+            let name = entry.path().strip_prefix(dir)?.to_string_lossy();
+            append_entry(builder, &name, entry.path())?;
+        }
+        Ok(())
+    })
+}
+
+/// Create gzipped tar archive of `files` in order, with their file names.
+pub fn create_tar_gz_files(files: &[&Path], to: &Path) -> Result<(), Error> {
+    log::info!("create_tar_gz_files() files: {:?}, to: {:?}", files, to);
+    create_tar_gz(to, |builder| {
+        for file in files {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            append_entry(builder, &name, file)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+
+    #[test]
+    fn test_create_tar_gz_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        fs::create_dir_all(data_dir.join("usr/bin")).unwrap();
+        fs::write(data_dir.join("usr/bin/hello"), "hello").unwrap();
+        let to = dir.path().join("data.tar.gz");
+        create_tar_gz_dir(&data_dir, &to).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&to).unwrap()));
+        let names = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                assert_eq!(entry.header().uid().unwrap(), 0);
+                String::from_utf8(entry.path_bytes().to_vec()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["./", "./usr/", "./usr/bin/", "./usr/bin/hello"]);
+    }
+}
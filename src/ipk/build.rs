@@ -0,0 +1,153 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use super::archive::{create_tar_gz_dir, create_tar_gz_files};
+use super::config::IpkConfig;
+use crate::appstream::install_metainfo;
+use crate::base::fileset::copy_filesets;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, LinuxConfig};
+use crate::deb::generate_control;
+use crate::desktop::install_desktop;
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of `OpenWrt` `.ipk` format.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IpkPackager;
+
+impl Packager for IpkPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Ipk
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        _options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        build_ipk(conf, linux_conf, arch)
+    }
+}
+
+pub fn build_ipk(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+) -> Result<Vec<PathBuf>, Error> {
+    let ipk_conf = &linux_conf.ipk;
+
+    let files = if let Some(files) = ipk_conf.files.as_ref() {
+        files
+    } else if let Some(files) = linux_conf.files.as_ref() {
+        files
+    } else {
+        return Err(Error::new(
+            ErrorKind::FilesNotSet,
+            "`files` property not set for ipk format",
+        ));
+    };
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    let ipk_dir = workdir.join("ipk");
+    let data_dir = ipk_dir.join("data");
+    let control_dir = ipk_dir.join("control");
+    let pkg_dir = ipk_dir.join("pkg");
+    utils::rmdir(&ipk_dir)?;
+    fs::create_dir_all(&data_dir)?;
+    fs::create_dir_all(&pkg_dir)?;
+
+    copy_filesets(files, conf, arch, PlatformTarget::Ipk, &data_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::Ipk,
+            &data_dir,
+            false,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &data_dir)?;
+    }
+
+    let data_file = pkg_dir.join("data.tar.gz");
+    create_tar_gz_dir(&data_dir, &data_file)?;
+
+    let file_size = utils::get_folder_size(&data_dir)?;
+    generate_control(
+        conf,
+        &ipk_conf.control(),
+        ipk_conf.arch_name(arch),
+        file_size,
+        &control_dir.join("control"),
+    )?;
+    write_conffiles(ipk_conf, &data_dir, &control_dir)?;
+    copy_scripts(ipk_conf, &control_dir)?;
+    let control_file = pkg_dir.join("control.tar.gz");
+    create_tar_gz_dir(&control_dir, &control_file)?;
+
+    let binary_file = pkg_dir.join("debian-binary");
+    fs::write(&binary_file, "2.0\n")?;
+
+    let artifact_name =
+        expand_file_macro(&ipk_conf.artifact_name, conf, arch, PlatformTarget::Ipk)?;
+    let artifact = workdir.join(artifact_name);
+    create_tar_gz_files(&[&binary_file, &data_file, &control_file], &artifact)?;
+    Ok(vec![artifact])
+}
+
+/// Write `conffiles`, each of them shall be installed by file sets.
+fn write_conffiles(ipk_conf: &IpkConfig, data_dir: &Path, control_dir: &Path) -> Result<(), Error> {
+    if ipk_conf.conffiles.is_empty() {
+        return Ok(());
+    }
+    let mut content = String::new();
+    for conffile in &ipk_conf.conffiles {
+        if !conffile.starts_with('/') {
+            return Err(Error::from_string(
+                ErrorKind::InvalidConfError,
+                format!(
+                    "Invalid config: `linux.ipk.conffiles` {:?} is not an absolute path",
+                    conffile
+                ),
+            ));
+        }
+        let path = data_dir.join(conffile.trim_start_matches('/'));
+        if !path.is_file() {
+            return Err(Error::from_string(
+                ErrorKind::InvalidConfError,
+                format!(
+                    "Invalid config: `linux.ipk.conffiles` {:?} is not installed by file sets",
+                    conffile
+                ),
+            ));
+        }
+        content.push_str(conffile);
+        content.push('\n');
+    }
+    fs::write(control_dir.join("conffiles"), content)?;
+    Ok(())
+}
+
+/// Copy maintainer scripts to control directory, and make them executable.
+fn copy_scripts(ipk_conf: &IpkConfig, control_dir: &Path) -> Result<(), Error> {
+    for (name, path) in ipk_conf.scripts() {
+        let dest = control_dir.join(name);
+        fs::copy(path, &dest).map_err(|err| Error::from(err).with_path(path))?;
+        #[cfg(not(target_os = "windows"))]
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::base::fileset::FileSet;
+use crate::base::Arch;
+use crate::deb::DebConfig;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IpkConfig {
+    /// File list.
+    pub files: Option<Vec<FileSet>>,
+
+    #[serde(default = "default_priority")]
+    pub priority: String,
+
+    pub section: Option<String>,
+    pub depends: Option<String>,
+    pub conflicts: Option<String>,
+    pub replaces: Option<String>,
+    pub provides: Option<String>,
+
+    /// Array of String - Config files kept by `opkg upgrade`, absolute paths
+    /// like `["/etc/config/hello"]`.
+    #[serde(default)]
+    pub conffiles: Vec<String>,
+
+    /// String - Path to script run before installing package.
+    pub preinst: Option<String>,
+
+    /// String - Path to script run after installing package.
+    pub postinst: Option<String>,
+
+    /// String - Path to script run before removing package.
+    pub prerm: Option<String>,
+
+    /// String - Path to script run after removing package.
+    pub postrm: Option<String>,
+
+    /// `OpenWrt` architecture names, which depend on target board,
+    /// like `{ aarch64 = "aarch64_cortex-a53" }`.
+    ///
+    /// Default is `aarch64_generic`, `mips64_octeonplus`, `x86_64` and `i386_pentium4`.
+    #[serde(default)]
+    pub arch_names: BTreeMap<String, String>,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+impl IpkConfig {
+    /// Get `OpenWrt` architecture name.
+    #[must_use]
+    pub fn arch_name(&self, arch: Arch) -> &str {
+        self.arch_names
+            .get(&arch.to_string())
+            .map_or_else(|| default_arch_name(arch), String::as_str)
+    }
+
+    /// Fields of control file, which has the same format as deb.
+    #[must_use]
+    pub fn control(&self) -> DebConfig {
+        DebConfig {
+            priority: self.priority.clone(),
+            section: self.section.clone(),
+            depends: self.depends.clone(),
+            conflicts: self.conflicts.clone(),
+            replaces: self.replaces.clone(),
+            provides: self.provides.clone(),
+            ..DebConfig::default()
+        }
+    }
+
+    /// Maintainer scripts with their names in control archive.
+    #[must_use]
+    pub fn scripts(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("preinst", &self.preinst),
+            ("postinst", &self.postinst),
+            ("prerm", &self.prerm),
+            ("postrm", &self.postrm),
+        ]
+        .into_iter()
+        .filter_map(|(name, path)| path.as_deref().map(|path| (name, path)))
+        .collect()
+    }
+}
+
+const fn default_arch_name(arch: Arch) -> &'static str {
+    match arch {
+        Arch::X86 => "i386_pentium4",
+        Arch::X86_64 => "x86_64",
+        Arch::AArch64 => "aarch64_generic",
+        Arch::Mips64 => "mips64_octeonplus",
+    }
+}
+
+impl Default for IpkConfig {
+    fn default() -> Self {
+        Self {
+            files: None,
+            priority: default_priority(),
+            section: None,
+            depends: None,
+            conflicts: None,
+            replaces: None,
+            provides: None,
+            conffiles: Vec::new(),
+            preinst: None,
+            postinst: None,
+            prerm: None,
+            postrm: None,
+            arch_names: BTreeMap::new(),
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_priority() -> String {
+    "optional".to_string()
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arch_name() {
+        let mut ipk_conf = IpkConfig::default();
+        assert_eq!(ipk_conf.arch_name(Arch::AArch64), "aarch64_generic");
+        ipk_conf
+            .arch_names
+            .insert("aarch64".to_string(), "aarch64_cortex-a53".to_string());
+        assert_eq!(ipk_conf.arch_name(Arch::AArch64), "aarch64_cortex-a53");
+        assert_eq!(ipk_conf.arch_name(Arch::X86_64), "x86_64");
+
+        ipk_conf.postinst = Some("scripts/postinst".to_string());
+        assert_eq!(ipk_conf.scripts(), [("postinst", "scripts/postinst")]);
+        assert_eq!(ipk_conf.control().priority, "optional");
+    }
+}
@@ -0,0 +1,10 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod archive;
+mod build;
+mod config;
+
+pub use build::IpkPackager;
+pub use config::IpkConfig;
@@ -9,18 +9,80 @@
     clippy::nursery,
     clippy::pedantic
 )]
-#![allow(clippy::module_name_repetitions)]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::module_name_repetitions,
+    clippy::multiple_crate_versions,
+    clippy::literal_string_with_formatting_args,
+    clippy::uninlined_format_args,
+    clippy::unnecessary_debug_formatting
+)]
 
+mod apk;
 mod app_image;
+mod appstream;
 pub mod base;
 mod build;
+mod cargo;
+mod check;
 mod cmdline;
 mod config;
 mod deb;
+mod desktop;
+mod doctor;
 mod download;
 mod error;
+mod flatpak;
+mod init;
+mod ipk;
+mod macos;
+mod macos_pkg;
 mod nsis;
+mod packager;
+mod plugin;
 mod rpm;
+mod schema;
+mod sign;
+mod snap;
+mod tarball;
 
+pub use base::fileset::FileSet;
+pub use base::{expand_file_macro_simple, Arch, GlobPatterns, Metadata, PlatformTarget};
+pub use build::{build, build_with_progress, BuildOptions, BuildOptionsBuilder, Progress};
 pub use cmdline::read_cmdline;
-pub use error::Error;
+pub use config::{Config, LinuxConfig, MacOsConfig, WindowsConfig};
+pub use error::{Error, ErrorKind};
+pub use packager::{Artifact, Packager};
+
+// Config sections of package formats.
+pub use apk::ApkConfig;
+pub use app_image::{AppImageConfig, Framework};
+pub use appstream::{AppStreamConfig, Release, Screenshot};
+pub use cargo::{BuildConfig, BuildTool, TargetTriples};
+pub use deb::DebConfig;
+pub use desktop::DesktopConfig;
+pub use flatpak::FlatpakConfig;
+pub use ipk::IpkConfig;
+pub use macos::{AppConfig, DmgConfig};
+pub use macos_pkg::MacosPkgConfig;
+pub use nsis::{CompressMethod, NsisConfig};
+pub use plugin::PluginConfig;
+pub use rpm::RpmConfig;
+pub use sign::SignConfig;
+pub use snap::{Confinement, Grade, SnapApp, SnapConfig};
+pub use tarball::TarballConfig;
+
+// Packagers of builtin formats.
+pub use apk::ApkPackager;
+pub use app_image::AppImagePackager;
+pub use deb::DebPackager;
+pub use flatpak::FlatpakPackager;
+pub use ipk::IpkPackager;
+pub use macos::{AppPackager, DmgPackager};
+pub use macos_pkg::MacosPkgPackager;
+pub use nsis::NsisPackager;
+pub use plugin::PluginPackager;
+pub use rpm::RpmPackager;
+pub use snap::SnapPackager;
+pub use tarball::TarballPackager;
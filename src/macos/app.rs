@@ -0,0 +1,118 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::icns::png_to_icns;
+use super::plist::{info_plist, validate_bundle_id};
+use crate::base::fileset::copy_filesets;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, MacOsConfig};
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of `macOS` application bundle, which is a directory named `<product_name>.app`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AppPackager;
+
+impl Packager for AppPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::App
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        _options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let macos_conf = conf.macos_conf()?;
+        build_app(conf, macos_conf, arch)
+    }
+}
+
+pub fn build_app(
+    conf: &Config,
+    macos_conf: &MacOsConfig,
+    arch: Arch,
+) -> Result<Vec<PathBuf>, Error> {
+    let workdir = Path::new(&conf.metadata.workdir);
+    let app_dir = workdir.join("app");
+    utils::rmdir(&app_dir)?;
+    let bundle = create_bundle(conf, macos_conf, arch, PlatformTarget::App, &app_dir)?;
+
+    // Move bundle out of staging directory, as it is the artifact.
+    let artifact = workdir.join(bundle.file_name().unwrap_or_default());
+    utils::rmdir(&artifact)?;
+    fs::rename(&bundle, &artifact)?;
+    Ok(vec![artifact])
+}
+
+/// Get file name of application bundle, like `Hello.app`.
+#[must_use]
+pub fn bundle_name(conf: &Config) -> String {
+    format!("{}.app", conf.metadata.product_name)
+}
+
+/// Assemble application bundle in `dest_dir`, and returns path to it.
+///
+/// File sets are copied to `Contents`, `Info.plist` and icon are generated.
+pub fn create_bundle(
+    conf: &Config,
+    macos_conf: &MacOsConfig,
+    arch: Arch,
+    target: PlatformTarget,
+    dest_dir: &Path,
+) -> Result<PathBuf, Error> {
+    validate_bundle_id(&conf.metadata.app_id).map_err(|message| {
+        Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Invalid config: `metadata.app_id` {}", message),
+        )
+    })?;
+    let files = macos_conf.files.as_ref().ok_or_else(|| {
+        Error::from_string(
+            ErrorKind::FilesNotSet,
+            format!("`files` property not set for {} format", target),
+        )
+    })?;
+
+    let bundle = dest_dir.join(bundle_name(conf));
+    let contents_dir = bundle.join("Contents");
+    let macos_dir = contents_dir.join("MacOS");
+    let resources_dir = contents_dir.join("Resources");
+    fs::create_dir_all(&macos_dir)?;
+    fs::create_dir_all(&resources_dir)?;
+
+    copy_filesets(files, conf, arch, target, &contents_dir)?;
+    let exe_file = macos_dir.join(macos_conf.exe_file(conf));
+    if !exe_file.is_file() {
+        return Err(Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!(
+                "Invalid config: `macos.exe_file` {:?} is not installed to `Contents/MacOS` by file sets",
+                macos_conf.exe_file(conf)
+            ),
+        ));
+    }
+
+    let icon_file = if let Some(icon) = macos_conf.icon.as_ref() {
+        // Icon is relative to `src_dir`, same as `linux.desktop.icon`.
+        let icon = expand_file_macro(icon, conf, arch, target)?;
+        let src_file = Path::new(&conf.metadata.src_dir).join(icon);
+        let icon_file = format!("{}.icns", conf.metadata.name);
+        png_to_icns(&src_file, &resources_dir.join(&icon_file))?;
+        Some(icon_file)
+    } else {
+        None
+    };
+
+    let plist = info_plist(conf, macos_conf, icon_file.as_deref())?;
+    fs::write(contents_dir.join("Info.plist"), plist)?;
+    fs::write(contents_dir.join("PkgInfo"), "APPL????")?;
+    Ok(bundle)
+}
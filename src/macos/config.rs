@@ -0,0 +1,64 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::base::utils::default_true;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfig {
+    /// String - `LSMinimumSystemVersion` in `Info.plist`.
+    #[serde(default = "default_minimum_system_version")]
+    pub minimum_system_version: String,
+
+    /// String - `LSApplicationCategoryType` in `Info.plist`,
+    /// like `public.app-category.developer-tools`.
+    pub category: Option<String>,
+
+    /// Boolean - whether to support retina display.
+    #[serde(default = "default_true")]
+    pub high_resolution: bool,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            minimum_system_version: default_minimum_system_version(),
+            category: None,
+            high_resolution: true,
+        }
+    }
+}
+
+fn default_minimum_system_version() -> String {
+    "10.13".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DmgConfig {
+    /// String - Volume name shown in Finder, default is `metadata.product_name`.
+    pub volume_name: Option<String>,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+impl Default for DmgConfig {
+    fn default() -> Self {
+        Self {
+            volume_name: None,
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
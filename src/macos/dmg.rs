@@ -0,0 +1,83 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::app::create_bundle;
+use crate::base::runner::Runner;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, MacOsConfig};
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of `macOS` disk image, `genisoimage` command is required.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DmgPackager;
+
+impl Packager for DmgPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Dmg
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let macos_conf = conf.macos_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_dmg(conf, macos_conf, arch, &runner)
+    }
+}
+
+pub fn build_dmg(
+    conf: &Config,
+    macos_conf: &MacOsConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let dmg_conf = &macos_conf.dmg;
+    let workdir = Path::new(&conf.metadata.workdir);
+    let dmg_dir = workdir.join("dmg");
+    utils::rmdir(&dmg_dir)?;
+    fs::create_dir_all(&dmg_dir)?;
+
+    create_bundle(conf, macos_conf, arch, PlatformTarget::Dmg, &dmg_dir)?;
+    // Users drag application bundle to this link to install it.
+    #[cfg(not(target_os = "windows"))]
+    std::os::unix::fs::symlink("/Applications", dmg_dir.join("Applications"))?;
+
+    let volume_name = dmg_conf
+        .volume_name
+        .as_deref()
+        .unwrap_or(&conf.metadata.product_name);
+    let artifact_name =
+        expand_file_macro(&dmg_conf.artifact_name, conf, arch, PlatformTarget::Dmg)?;
+    let artifact = workdir.join(artifact_name);
+    compile_dmg(&dmg_dir, volume_name, &artifact, runner)?;
+    Ok(vec![artifact])
+}
+
+/// Create hybrid ISO9660/HFS image, which is mounted by `macOS` as dmg.
+fn compile_dmg(
+    dmg_dir: &Path,
+    volume_name: &str,
+    artifact: &Path,
+    runner: &Runner,
+) -> Result<(), Error> {
+    let mut cmd = Command::new("genisoimage");
+    cmd.arg("-V")
+        .arg(volume_name)
+        .args(["-D", "-R", "-apple", "-no-pad", "-quiet"])
+        .arg("-o")
+        .arg(artifact)
+        .arg(dmg_dir);
+    runner.run(&mut cmd, ErrorKind::GenisoimageError)?;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Apple icon image format, which contains PNG images of multiple sizes.
+//!
+//! Each element is 4 bytes type, 4 bytes big endian length including the
+//! 8 bytes header, and the data.
+
+use image::imageops::FilterType;
+use image::{GenericImageView, ImageOutputFormat};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::error::{Error, ErrorKind};
+
+/// Element types of PNG images, supported since macOS 10.7.
+const ICON_TYPES: &[(&[u8; 4], u32)] = &[
+    (b"icp4", 16),
+    (b"icp5", 32),
+    (b"icp6", 64),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic09", 512),
+    (b"ic10", 1024),
+];
+
+const HEADER_LEN: usize = 8;
+
+/// Convert PNG icon to `.icns` file, with sizes which are not larger than itself.
+pub fn png_to_icns(src_file: &Path, dest_file: &Path) -> Result<(), Error> {
+    log::info!("png_to_icns() src: {:?}, dest: {:?}", src_file, dest_file);
+    let image = image::open(src_file).map_err(|err| Error::from(err).with_path(src_file))?;
+    let (width, height) = image.dimensions();
+    if width != height {
+        log::warn!(
+            "Icon {:?} is not square: {}x{}, it will be stretched",
+            src_file,
+            width,
+            height
+        );
+    }
+
+    let mut types: Vec<_> = ICON_TYPES
+        .iter()
+        .filter(|(_, size)| *size <= width)
+        .collect();
+    if types.is_empty() {
+        types.push(&ICON_TYPES[0]);
+    }
+
+    let mut elements = Vec::new();
+    for (kind, size) in types {
+        let mut png = Vec::new();
+        image
+            .resize_exact(*size, *size, FilterType::Lanczos3)
+            .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .map_err(|err| Error::from(err).with_path(src_file))?;
+        elements.extend_from_slice(*kind);
+        elements.extend_from_slice(&element_len(png.len())?.to_be_bytes());
+        elements.extend(png);
+    }
+
+    let mut icns = Vec::with_capacity(HEADER_LEN + elements.len());
+    icns.extend_from_slice(b"icns");
+    icns.extend_from_slice(&element_len(elements.len())?.to_be_bytes());
+    icns.extend(elements);
+    fs::write(dest_file, icns)?;
+    Ok(())
+}
+
+/// Length of element with header.
+fn element_len(data_len: usize) -> Result<u32, Error> {
+    u32::try_from(data_len + HEADER_LEN)
+        .map_err(|_| Error::new(ErrorKind::ImageError, "Icon is too large for icns format"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_to_icns() {
+        let dir = tempfile::tempdir().unwrap();
+        let png_file = dir.path().join("hello.png");
+        image::RgbaImage::new(48, 48).save(&png_file).unwrap();
+        let icns_file = dir.path().join("hello.icns");
+        png_to_icns(&png_file, &icns_file).unwrap();
+
+        let icns = fs::read(&icns_file).unwrap();
+        assert_eq!(&icns[..4], b"icns");
+        assert_eq!(
+            u32::from_be_bytes(icns[4..8].try_into().unwrap()) as usize,
+            icns.len()
+        );
+        // Only 16x16 and 32x32 images are generated.
+        assert_eq!(&icns[8..12], b"icp4");
+        let first_len = u32::from_be_bytes(icns[12..16].try_into().unwrap()) as usize;
+        assert_eq!(&icns[8 + first_len..12 + first_len], b"icp5");
+    }
+}
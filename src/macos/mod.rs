@@ -0,0 +1,14 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod app;
+mod config;
+mod dmg;
+mod icns;
+mod plist;
+
+pub use app::{bundle_name, create_bundle, AppPackager};
+pub use config::{AppConfig, DmgConfig};
+pub use dmg::DmgPackager;
+pub use plist::{validate_bundle_id, validate_bundle_version};
@@ -0,0 +1,131 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fmt::Write;
+
+use crate::base::utils::escape_xml;
+use crate::config::{Config, MacOsConfig};
+use crate::error::Error;
+
+/// Bundle identifier contains only letters, digits, hyphens and periods.
+pub fn validate_bundle_id(app_id: &str) -> Result<(), String> {
+    let is_valid = !app_id.is_empty()
+        && app_id.split('.').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if is_valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} is invalid bundle identifier, it shall contain only letters, digits, hyphens and periods",
+            app_id
+        ))
+    }
+}
+
+/// `CFBundleVersion` is at most three non-negative integers separated by periods.
+pub fn validate_bundle_version(build_id: &str) -> Result<(), String> {
+    let parts = build_id.split('.').collect::<Vec<_>>();
+    let is_valid = parts.len() <= 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if is_valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} is invalid for `CFBundleVersion`, it shall be like `123` or `1.2.3`",
+            build_id
+        ))
+    }
+}
+
+fn write_string(plist: &mut String, key: &str, value: &str) -> Result<(), Error> {
+    writeln!(plist, "\t<key>{}</key>", key)?;
+    writeln!(plist, "\t<string>{}</string>", escape_xml(value))?;
+    Ok(())
+}
+
+/// Generate `Contents/Info.plist` of application bundle.
+///
+/// `icon_file` is file name of `.icns` in `Contents/Resources`.
+pub fn info_plist(
+    conf: &Config,
+    macos_conf: &MacOsConfig,
+    icon_file: Option<&str>,
+) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let app_conf = &macos_conf.app;
+    let mut plist = String::new();
+    writeln!(plist, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        plist,
+        r#"<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">"#
+    )?;
+    writeln!(plist, r#"<plist version="1.0">"#)?;
+    writeln!(plist, "<dict>")?;
+    write_string(&mut plist, "CFBundleDevelopmentRegion", "en")?;
+    write_string(&mut plist, "CFBundleDisplayName", &metadata.product_name)?;
+    write_string(&mut plist, "CFBundleExecutable", macos_conf.exe_file(conf))?;
+    if let Some(icon_file) = icon_file {
+        write_string(&mut plist, "CFBundleIconFile", icon_file)?;
+    }
+    write_string(&mut plist, "CFBundleIdentifier", &metadata.app_id)?;
+    write_string(&mut plist, "CFBundleInfoDictionaryVersion", "6.0")?;
+    write_string(&mut plist, "CFBundleName", &metadata.product_name)?;
+    write_string(&mut plist, "CFBundlePackageType", "APPL")?;
+    write_string(&mut plist, "CFBundleShortVersionString", &metadata.version)?;
+    write_string(&mut plist, "CFBundleVersion", &metadata.build_id)?;
+    if let Some(category) = app_conf.category.as_ref() {
+        write_string(&mut plist, "LSApplicationCategoryType", category)?;
+    }
+    write_string(
+        &mut plist,
+        "LSMinimumSystemVersion",
+        &app_conf.minimum_system_version,
+    )?;
+    if let Some(copyright) = metadata.copyright.as_ref() {
+        write_string(&mut plist, "NSHumanReadableCopyright", copyright)?;
+    }
+    writeln!(plist, "\t<key>NSHighResolutionCapable</key>")?;
+    writeln!(
+        plist,
+        "\t<{}/>",
+        if app_conf.high_resolution {
+            "true"
+        } else {
+            "false"
+        }
+    )?;
+    writeln!(plist, "</dict>")?;
+    writeln!(plist, "</plist>")?;
+    Ok(plist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_info_plist() {
+        let mut metadata = Metadata::new("hello", "1.2.0");
+        metadata.product_name = "Hello & World".to_string();
+        metadata.build_id = "42".to_string();
+        let conf = Config::new(metadata);
+        let macos_conf = MacOsConfig::default();
+        let plist = info_plist(&conf, &macos_conf, Some("hello.icns")).unwrap();
+        assert!(plist
+            .contains("\t<key>CFBundleIdentifier</key>\n\t<string>org.example.hello</string>\n"));
+        assert!(plist.contains("\t<string>Hello &amp; World</string>\n"));
+        assert!(plist.contains("\t<key>CFBundleExecutable</key>\n\t<string>hello</string>\n"));
+        assert!(plist.contains("\t<key>CFBundleVersion</key>\n\t<string>42</string>\n"));
+        assert!(plist.ends_with("</dict>\n</plist>\n"));
+
+        assert!(validate_bundle_id("org.biofan.pifu-app").is_ok());
+        assert!(validate_bundle_id("org..pifu").is_err());
+        assert!(validate_bundle_version("20211018").is_ok());
+        assert!(validate_bundle_version("1.2.3.4").is_err());
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Bill of materials, which lists files in `Payload` and is read by `lsbom`.
+//!
+//! It is a block store with big endian integers, same as the one written by
+//! `mkbom` of bomutils. Variables `BomInfo`, `Paths`, `HLIndex`, `VIndex` and
+//! `Size64` refer to blocks, `Paths` is a B+ tree whose leaves contain pairs of
+//! path info and file name.
+
+use std::collections::HashMap;
+
+use super::payload::{EntryKind, PayloadEntry};
+use crate::error::{Error, ErrorKind};
+
+const HEADER_LEN: usize = 512;
+const MAGIC: &[u8; 8] = b"BOMStore";
+
+/// Max number of paths in a leaf of `Paths` tree.
+const LEAF_CAPACITY: usize = 256;
+
+const PATHS_BLOCK_SIZE: u32 = 4096;
+const INDEX_BLOCK_SIZE: u32 = 128;
+
+/// Same as `mkbom`, meaning of this field is unknown.
+const ARCHITECTURE: u16 = 3;
+
+#[derive(Debug, Default)]
+struct BomStore {
+    data: Vec<u8>,
+
+    /// Address and length of blocks, the first one is null block.
+    blocks: Vec<(u32, u32)>,
+
+    vars: Vec<(&'static str, u32)>,
+}
+
+fn to_u32(value: usize) -> Result<u32, Error> {
+    u32::try_from(value).map_err(|_| {
+        Error::new(
+            ErrorKind::IoError,
+            "Bom is too large, it shall be less than 4GiB",
+        )
+    })
+}
+
+impl BomStore {
+    fn new() -> Self {
+        Self {
+            blocks: vec![(0, 0)],
+            ..Self::default()
+        }
+    }
+
+    /// Append block and returns its index.
+    fn add_block(&mut self, block: &[u8]) -> Result<u32, Error> {
+        let address = to_u32(HEADER_LEN + self.data.len())?;
+        self.data.extend_from_slice(block);
+        self.blocks.push((address, to_u32(block.len())?));
+        to_u32(self.blocks.len() - 1)
+    }
+
+    /// Index of next block to be added.
+    fn next_index(&self) -> Result<u32, Error> {
+        to_u32(self.blocks.len())
+    }
+
+    fn add_var(&mut self, name: &'static str, index: u32) {
+        self.vars.push((name, index));
+    }
+
+    fn finish(self) -> Result<Vec<u8>, Error> {
+        let mut index = Vec::new();
+        put_u32(&mut index, to_u32(self.blocks.len())?);
+        for (address, len) in &self.blocks {
+            put_u32(&mut index, *address);
+            put_u32(&mut index, *len);
+        }
+        // Free list with two empty entries.
+        put_u32(&mut index, 2);
+        index.extend_from_slice(&[0; 16]);
+
+        let mut vars = Vec::new();
+        put_u32(&mut vars, to_u32(self.vars.len())?);
+        for (name, block) in &self.vars {
+            put_u32(&mut vars, *block);
+            vars.push(u8::try_from(name.len()).unwrap_or(u8::MAX));
+            vars.extend_from_slice(name.as_bytes());
+        }
+
+        let index_offset = HEADER_LEN + self.data.len();
+        let vars_offset = index_offset + index.len();
+        let mut bom = Vec::with_capacity(vars_offset + vars.len());
+        bom.extend_from_slice(MAGIC);
+        put_u32(&mut bom, 1);
+        put_u32(&mut bom, to_u32(self.blocks.len() - 1)?);
+        put_u32(&mut bom, to_u32(index_offset)?);
+        put_u32(&mut bom, to_u32(index.len())?);
+        put_u32(&mut bom, to_u32(vars_offset)?);
+        put_u32(&mut bom, to_u32(vars.len())?);
+        bom.resize(HEADER_LEN, 0);
+        bom.extend(self.data);
+        bom.extend(index);
+        bom.extend(vars);
+        Ok(bom)
+    }
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Checksum of POSIX `cksum` command.
+#[must_use]
+pub fn posix_cksum(data: &[u8]) -> u32 {
+    const POLY: u32 = 0x04C1_1DB7;
+    fn update(crc: u32, byte: u8) -> u32 {
+        let mut crc = crc ^ (u32::from(byte) << 24);
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ POLY
+            };
+        }
+        crc
+    }
+
+    let mut crc = data.iter().fold(0, |crc, byte| update(crc, *byte));
+    let mut len = data.len();
+    while len > 0 {
+        #[allow(clippy::cast_possible_truncation)]
+        let byte = (len & 0xff) as u8;
+        crc = update(crc, byte);
+        len >>= 8;
+    }
+    !crc
+}
+
+/// Block of path info, with type, mode, owner, size and checksum.
+fn path_info(entry: &PayloadEntry) -> Result<Vec<u8>, Error> {
+    let kind: u8 = match entry.kind {
+        EntryKind::File => 1,
+        EntryKind::Dir => 2,
+        EntryKind::Link => 3,
+    };
+    let mut info = vec![kind, 1];
+    put_u16(&mut info, ARCHITECTURE);
+    put_u16(
+        &mut info,
+        u16::try_from(entry.full_mode()).unwrap_or(u16::MAX),
+    );
+    // uid and gid of root.
+    put_u32(&mut info, 0);
+    put_u32(&mut info, 0);
+    put_u32(&mut info, entry.mtime);
+    put_u32(&mut info, to_u32(entry.data.len())?);
+    info.push(1);
+    if entry.kind == EntryKind::Dir {
+        put_u32(&mut info, 0);
+    } else {
+        put_u32(&mut info, posix_cksum(&entry.data));
+    }
+    if entry.kind == EntryKind::Link {
+        put_u32(&mut info, to_u32(entry.data.len() + 1)?);
+        info.extend_from_slice(&entry.data);
+        info.push(0);
+    } else {
+        put_u32(&mut info, 0);
+    }
+    Ok(info)
+}
+
+/// Header of tree, `child` is index of root node.
+fn tree(child: u32, block_size: u32, path_count: u32) -> Vec<u8> {
+    let mut tree = b"tree".to_vec();
+    put_u32(&mut tree, 1);
+    put_u32(&mut tree, child);
+    put_u32(&mut tree, block_size);
+    put_u32(&mut tree, path_count);
+    tree.push(0);
+    tree
+}
+
+/// Node of tree, `indices` are pairs of path info and file name for leaf,
+/// or pairs of child node and its last file name for branch.
+fn node(is_leaf: bool, indices: &[(u32, u32)], forward: u32, backward: u32) -> Vec<u8> {
+    let mut node = Vec::new();
+    put_u16(&mut node, u16::from(is_leaf));
+    put_u16(&mut node, u16::try_from(indices.len()).unwrap_or(u16::MAX));
+    put_u32(&mut node, forward);
+    put_u32(&mut node, backward);
+    for (index0, index1) in indices {
+        put_u32(&mut node, *index0);
+        put_u32(&mut node, *index1);
+    }
+    node
+}
+
+/// Add an empty tree and returns its index.
+fn empty_tree(bom: &mut BomStore, block_size: u32) -> Result<u32, Error> {
+    let leaf = bom.add_block(&node(true, &[], 0, 0))?;
+    bom.add_block(&tree(leaf, block_size, 0))
+}
+
+/// Add leaves of paths, and returns root node.
+fn add_paths(bom: &mut BomStore, entries: &[PayloadEntry]) -> Result<u32, Error> {
+    // Ids of paths start from 1, and parent of `.` is 0.
+    let mut ids = HashMap::new();
+    let mut indices = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let id = to_u32(i + 1)?;
+        ids.insert(entry.path.as_str(), id);
+        let (parent, name) = entry
+            .path
+            .rsplit_once('/')
+            .map_or((0, entry.path.as_str()), |(parent, name)| {
+                (ids.get(parent).copied().unwrap_or(0), name)
+            });
+
+        let info2 = bom.add_block(&path_info(entry)?)?;
+        let mut info1 = Vec::new();
+        put_u32(&mut info1, id);
+        put_u32(&mut info1, info2);
+        let info1 = bom.add_block(&info1)?;
+
+        let mut file = Vec::new();
+        put_u32(&mut file, parent);
+        file.extend_from_slice(name.as_bytes());
+        file.push(0);
+        let file = bom.add_block(&file)?;
+        indices.push((info1, file));
+    }
+
+    let chunks = indices.chunks(LEAF_CAPACITY).collect::<Vec<_>>();
+    if chunks.len() <= 1 {
+        return bom.add_block(&node(true, &indices, 0, 0));
+    }
+
+    // Leaves are linked to each other, and added in sequence.
+    let first_leaf = bom.next_index()?;
+    let mut branch = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let leaf_index = first_leaf + to_u32(i)?;
+        let forward = if i + 1 < chunks.len() {
+            leaf_index + 1
+        } else {
+            0
+        };
+        let backward = if i > 0 { leaf_index - 1 } else { 0 };
+        bom.add_block(&node(true, chunk, forward, backward))?;
+        let last_file = chunk.last().map_or(0, |(_, file)| *file);
+        branch.push((leaf_index, last_file));
+    }
+    bom.add_block(&node(false, &branch, 0, 0))
+}
+
+/// Create bill of materials of `entries` in payload.
+pub fn create_bom(entries: &[PayloadEntry]) -> Result<Vec<u8>, Error> {
+    let mut bom = BomStore::new();
+
+    let mut info = Vec::new();
+    put_u32(&mut info, 1);
+    put_u32(&mut info, to_u32(entries.len() + 1)?);
+    put_u32(&mut info, 1);
+    info.extend_from_slice(&[0; 16]);
+    let info = bom.add_block(&info)?;
+    bom.add_var("BomInfo", info);
+
+    let root = add_paths(&mut bom, entries)?;
+    let paths = bom.add_block(&tree(root, PATHS_BLOCK_SIZE, to_u32(entries.len())?))?;
+    bom.add_var("Paths", paths);
+
+    let hl_index = empty_tree(&mut bom, PATHS_BLOCK_SIZE)?;
+    bom.add_var("HLIndex", hl_index);
+
+    let v_tree = empty_tree(&mut bom, INDEX_BLOCK_SIZE)?;
+    let mut v_index = Vec::new();
+    put_u32(&mut v_index, 1);
+    put_u32(&mut v_index, v_tree);
+    put_u32(&mut v_index, 0);
+    v_index.push(0);
+    let v_index = bom.add_block(&v_index)?;
+    bom.add_var("VIndex", v_index);
+
+    let size64 = empty_tree(&mut bom, INDEX_BLOCK_SIZE)?;
+    bom.add_var("Size64", size64);
+
+    bom.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posix_cksum() {
+        // Same as output of `printf hello | cksum`.
+        assert_eq!(posix_cksum(b"hello"), 3_287_646_509);
+        assert_eq!(posix_cksum(b""), 4_294_967_295);
+    }
+
+    #[test]
+    fn test_create_bom() {
+        let entries = [
+            PayloadEntry {
+                path: ".".to_string(),
+                kind: EntryKind::Dir,
+                mode: 0o755,
+                mtime: 0,
+                data: Vec::new(),
+            },
+            PayloadEntry {
+                path: "./hello".to_string(),
+                kind: EntryKind::File,
+                mode: 0o644,
+                mtime: 0,
+                data: b"hello".to_vec(),
+            },
+        ];
+        let bom = create_bom(&entries).unwrap();
+        assert_eq!(&bom[..8], b"BOMStore");
+        let read_u32 =
+            |offset: usize| u32::from_be_bytes(bom[offset..offset + 4].try_into().unwrap());
+        let vars_offset = read_u32(24) as usize;
+        assert_eq!(read_u32(vars_offset), 5);
+        assert_eq!(bom[vars_offset + 8], 7);
+        assert_eq!(&bom[vars_offset + 9..vars_offset + 16], b"BomInfo");
+        assert_eq!(vars_offset + read_u32(28) as usize, bom.len());
+    }
+}
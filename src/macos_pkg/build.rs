@@ -0,0 +1,101 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::bom::create_bom;
+use super::info::{distribution, host_arch, package_info, PayloadInfo};
+use super::payload::{installed_size, payload_archive, scan_payload};
+use super::xar::{xar_archive, XarNode};
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, MacOsConfig};
+use crate::error::{Error, ErrorKind};
+use crate::macos::create_bundle;
+use crate::packager::Packager;
+
+/// Packager of `macOS` flat installer package, which is a product archive
+/// containing one component package.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MacosPkgPackager;
+
+impl Packager for MacosPkgPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::MacosPkg
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        _options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let macos_conf = conf.macos_conf()?;
+        build_macos_pkg(conf, macos_conf, arch)
+    }
+}
+
+pub fn build_macos_pkg(
+    conf: &Config,
+    macos_conf: &MacOsConfig,
+    arch: Arch,
+) -> Result<Vec<PathBuf>, Error> {
+    let pkg_conf = &macos_conf.macos_pkg;
+    let host_arch = host_arch(arch).ok_or_else(|| {
+        Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Arch {} is not supported by macos", arch),
+        )
+    })?;
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    let pkg_dir = workdir.join("macos_pkg");
+    utils::rmdir(&pkg_dir)?;
+    let root_dir = pkg_dir.join("root");
+    fs::create_dir_all(&root_dir)?;
+    create_bundle(conf, macos_conf, arch, PlatformTarget::MacosPkg, &root_dir)?;
+
+    let entries = scan_payload(&root_dir)?;
+    let payload_info = PayloadInfo {
+        number_of_files: entries.len(),
+        install_kbytes: installed_size(&entries).div_ceil(1024),
+    };
+    let component = format!("{}.pkg", conf.metadata.name);
+    let component_files = vec![
+        XarNode::File {
+            name: "Bom".to_string(),
+            data: create_bom(&entries)?,
+        },
+        XarNode::File {
+            name: "Payload".to_string(),
+            data: payload_archive(&entries)?,
+        },
+        XarNode::File {
+            name: "PackageInfo".to_string(),
+            data: package_info(conf, pkg_conf, payload_info)?.into_bytes(),
+        },
+    ];
+    let nodes = [
+        XarNode::File {
+            name: "Distribution".to_string(),
+            data: distribution(conf, host_arch, &component, payload_info)?.into_bytes(),
+        },
+        XarNode::Dir {
+            name: component,
+            children: component_files,
+        },
+    ];
+
+    let artifact_name = expand_file_macro(
+        &pkg_conf.artifact_name,
+        conf,
+        arch,
+        PlatformTarget::MacosPkg,
+    )?;
+    let artifact = workdir.join(artifact_name);
+    fs::write(&artifact, xar_archive(&nodes)?)?;
+    Ok(vec![artifact])
+}
@@ -0,0 +1,39 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MacosPkgConfig {
+    /// String - Directory to install application bundle.
+    ///
+    /// Default is `/Applications`.
+    #[serde(default = "default_install_location")]
+    pub install_location: String,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+impl Default for MacosPkgConfig {
+    fn default() -> Self {
+        Self {
+            install_location: default_install_location(),
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_install_location() -> String {
+    "/Applications".to_string()
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
@@ -0,0 +1,136 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fmt::Write;
+
+use super::config::MacosPkgConfig;
+use crate::base::utils::escape_xml;
+use crate::base::Arch;
+use crate::config::Config;
+use crate::error::Error;
+use crate::macos::bundle_name;
+
+/// Size and number of files in `Payload`.
+#[derive(Debug, Clone, Copy)]
+pub struct PayloadInfo {
+    pub number_of_files: usize,
+    pub install_kbytes: u64,
+}
+
+/// Get architecture name used by `installer`, `None` if it is not supported.
+#[must_use]
+pub const fn host_arch(arch: Arch) -> Option<&'static str> {
+    match arch {
+        Arch::X86_64 => Some("x86_64"),
+        Arch::AArch64 => Some("arm64"),
+        Arch::X86 | Arch::Mips64 => None,
+    }
+}
+
+/// Generate `PackageInfo` of component package.
+pub fn package_info(
+    conf: &Config,
+    pkg_conf: &MacosPkgConfig,
+    payload: PayloadInfo,
+) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let app_id = escape_xml(&metadata.app_id);
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(
+        xml,
+        r#"<pkg-info format-version="2" identifier="{}" version="{}" install-location="{}" auth="root" overwrite-permissions="true" relocatable="false" postinstall-action="none">"#,
+        app_id,
+        escape_xml(&metadata.version),
+        escape_xml(&pkg_conf.install_location)
+    )?;
+    writeln!(
+        xml,
+        r#"    <payload numberOfFiles="{}" installKBytes="{}"/>"#,
+        payload.number_of_files, payload.install_kbytes
+    )?;
+    writeln!(
+        xml,
+        r#"    <bundle path="./{}" id="{}" CFBundleShortVersionString="{}" CFBundleVersion="{}"/>"#,
+        escape_xml(&bundle_name(conf)),
+        app_id,
+        escape_xml(&metadata.version),
+        escape_xml(&metadata.build_id)
+    )?;
+    writeln!(xml, "    <bundle-version>")?;
+    writeln!(xml, r#"        <bundle id="{}"/>"#, app_id)?;
+    writeln!(xml, "    </bundle-version>")?;
+    writeln!(xml, "</pkg-info>")?;
+    Ok(xml)
+}
+
+/// Generate `Distribution` of product archive, which installs `component`.
+pub fn distribution(
+    conf: &Config,
+    arch: &str,
+    component: &str,
+    payload: PayloadInfo,
+) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let app_id = escape_xml(&metadata.app_id);
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(xml, r#"<installer-gui-script minSpecVersion="2">"#)?;
+    writeln!(
+        xml,
+        "    <title>{}</title>",
+        escape_xml(&metadata.product_name)
+    )?;
+    writeln!(
+        xml,
+        r#"    <options customize="never" require-scripts="false" hostArchitectures="{}"/>"#,
+        arch
+    )?;
+    writeln!(xml, r#"    <domains enable_localSystem="true"/>"#)?;
+    writeln!(xml, "    <choices-outline>")?;
+    writeln!(xml, r#"        <line choice="default">"#)?;
+    writeln!(xml, r#"            <line choice="{}"/>"#, app_id)?;
+    writeln!(xml, "        </line>")?;
+    writeln!(xml, "    </choices-outline>")?;
+    writeln!(xml, r#"    <choice id="default"/>"#)?;
+    writeln!(xml, r#"    <choice id="{}" visible="false">"#, app_id)?;
+    writeln!(xml, r#"        <pkg-ref id="{}"/>"#, app_id)?;
+    writeln!(xml, "    </choice>")?;
+    writeln!(
+        xml,
+        r#"    <pkg-ref id="{}" version="{}" onConclusion="none" installKBytes="{}">#{}</pkg-ref>"#,
+        app_id,
+        escape_xml(&metadata.version),
+        payload.install_kbytes,
+        escape_xml(component)
+    )?;
+    writeln!(xml, "</installer-gui-script>")?;
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_distribution() {
+        let mut metadata = Metadata::new("hello", "1.0.0");
+        metadata.product_name = "Hello".to_string();
+        let conf = Config::new(metadata);
+        let payload = PayloadInfo {
+            number_of_files: 5,
+            install_kbytes: 12,
+        };
+        let xml = distribution(&conf, "arm64", "hello.pkg", payload).unwrap();
+        assert!(xml.contains(r#"hostArchitectures="arm64""#));
+        assert!(xml.contains(
+            r#"<pkg-ref id="org.example.hello" version="1.0.0" onConclusion="none" installKBytes="12">#hello.pkg</pkg-ref>"#
+        ));
+
+        let xml = package_info(&conf, &MacosPkgConfig::default(), payload).unwrap();
+        assert!(xml.contains(r#"install-location="/Applications""#));
+        assert!(xml.contains(r#"<bundle path="./Hello.app" id="org.example.hello""#));
+    }
+}
@@ -0,0 +1,13 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod bom;
+mod build;
+mod config;
+mod info;
+mod payload;
+mod xar;
+
+pub use build::MacosPkgPackager;
+pub use config::MacosPkgConfig;
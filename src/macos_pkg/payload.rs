@@ -0,0 +1,193 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! `Payload` of component package is a gzipped cpio archive in odc format,
+//! with paths relative to install location, like `./Hello.app`.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::Write;
+#[cfg(not(target_os = "windows"))]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::error::Error;
+
+const CPIO_MAGIC: &str = "070707";
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+const S_IFDIR: u32 = 0o040_000;
+const S_IFREG: u32 = 0o100_000;
+const S_IFLNK: u32 = 0o120_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Link,
+}
+
+/// A file in payload, shared by `Payload` and `Bom`.
+#[derive(Debug, Clone)]
+pub struct PayloadEntry {
+    /// Path like `.` or `./Hello.app/Contents`.
+    pub path: String,
+
+    pub kind: EntryKind,
+
+    /// Permission bits, without file type.
+    pub mode: u32,
+
+    pub mtime: u32,
+
+    /// Content of file, or target of symbolic link.
+    pub data: Vec<u8>,
+}
+
+impl PayloadEntry {
+    /// Mode with file type bits, as in `st_mode`.
+    #[must_use]
+    pub const fn full_mode(&self) -> u32 {
+        let kind = match self.kind {
+            EntryKind::File => S_IFREG,
+            EntryKind::Dir => S_IFDIR,
+            EntryKind::Link => S_IFLNK,
+        };
+        kind | self.mode
+    }
+}
+
+/// Collect files in `dir` in sorted order, including `dir` itself as `.`.
+pub fn scan_payload(dir: &Path) -> Result<Vec<PayloadEntry>, Error> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        let src = entry.path();
+        let rel_path = src.strip_prefix(dir)?.to_string_lossy().to_string();
+        let path = if rel_path.is_empty() {
+            ".".to_string()
+        } else {
+            format!("./{}", rel_path)
+        };
+        let metadata = fs::symlink_metadata(src)?;
+        let mtime = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+        #[cfg(not(target_os = "windows"))]
+        let mode = metadata.permissions().mode() & 0o7777;
+        #[cfg(target_os = "windows")]
+        let mode = if metadata.is_dir() { 0o755 } else { 0o644 };
+
+        let file_type = entry.file_type();
+        let (kind, mode, data) = if file_type.is_dir() {
+            (EntryKind::Dir, mode, Vec::new())
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(src)?;
+            let data = target.to_string_lossy().as_bytes().to_vec();
+            (EntryKind::Link, 0o755, data)
+        } else {
+            (EntryKind::File, mode, fs::read(src)?)
+        };
+        entries.push(PayloadEntry {
+            path,
+            kind,
+            mode,
+            mtime: u32::try_from(mtime).unwrap_or(u32::MAX),
+            data,
+        });
+    }
+    Ok(entries)
+}
+
+/// Installed size in bytes.
+#[must_use]
+pub fn installed_size(entries: &[PayloadEntry]) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::File)
+        .map(|entry| entry.data.len() as u64)
+        .sum()
+}
+
+/// Write cpio header and name of odc format.
+fn write_header(
+    cpio: &mut Vec<u8>,
+    ino: usize,
+    mode: u32,
+    mtime: u32,
+    name: &str,
+    size: usize,
+) -> Result<(), Error> {
+    write!(
+        cpio,
+        "{}{:06o}{:06o}{:06o}{:06o}{:06o}{:06o}{:06o}{:011o}{:06o}{:011o}",
+        CPIO_MAGIC,
+        0,
+        ino,
+        mode,
+        0,
+        0,
+        1,
+        0,
+        mtime,
+        name.len() + 1,
+        size
+    )?;
+    cpio.extend_from_slice(name.as_bytes());
+    cpio.push(0);
+    Ok(())
+}
+
+/// Create gzipped cpio archive of `entries`, all files are owned by root.
+pub fn payload_archive(entries: &[PayloadEntry]) -> Result<Vec<u8>, Error> {
+    let mut cpio = Vec::new();
+    for (ino, entry) in entries.iter().enumerate() {
+        write_header(
+            &mut cpio,
+            ino + 1,
+            entry.full_mode(),
+            entry.mtime,
+            &entry.path,
+            entry.data.len(),
+        )?;
+        cpio.extend_from_slice(&entry.data);
+    }
+    write_header(&mut cpio, 0, 0, 0, CPIO_TRAILER, 0)?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&cpio)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_payload_archive() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        fs::create_dir_all(dir.join("Hello.app")).unwrap();
+        fs::write(dir.join("Hello.app/hello"), "hello").unwrap();
+        let entries = scan_payload(dir).unwrap();
+        let paths = entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [".", "./Hello.app", "./Hello.app/hello"]);
+        assert_eq!(installed_size(&entries), 5);
+
+        let mut cpio = Vec::new();
+        GzDecoder::new(payload_archive(&entries).unwrap().as_slice())
+            .read_to_end(&mut cpio)
+            .unwrap();
+        assert!(cpio.starts_with(b"070707"));
+        // Header of `.` is 76 bytes, followed by name with NUL.
+        assert_eq!(&cpio[76..78], b".\0");
+        assert!(cpio.ends_with(b"TRAILER!!!\0"));
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Xar archive is a 28 bytes header, zlib compressed table of contents in XML,
+//! and the heap. The heap starts with sha1 checksum of compressed TOC,
+//! followed by contents of files, which are stored without compression.
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha1::{Digest, Sha1};
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::base::hash::sha1_hex;
+use crate::base::utils::escape_xml;
+use crate::error::Error;
+
+const MAGIC: u32 = 0x7861_7221;
+const HEADER_LEN: u16 = 28;
+const VERSION: u16 = 1;
+const CHECKSUM_SHA1: u32 = 1;
+const CHECKSUM_LEN: usize = 20;
+
+/// Node in xar archive.
+#[derive(Debug, Clone)]
+pub enum XarNode {
+    File { name: String, data: Vec<u8> },
+    Dir { name: String, children: Vec<Self> },
+}
+
+#[derive(Debug, Default)]
+struct Writer {
+    toc: String,
+    heap: Vec<u8>,
+    next_id: usize,
+}
+
+impl Writer {
+    fn write_node(&mut self, node: &XarNode, indent: &str) -> Result<(), Error> {
+        self.next_id += 1;
+        writeln!(self.toc, r#"{}<file id="{}">"#, indent, self.next_id)?;
+        match node {
+            XarNode::File { name, data } => {
+                let checksum = sha1_hex(data);
+                writeln!(self.toc, "{}  <data>", indent)?;
+                writeln!(self.toc, "{}    <length>{}</length>", indent, data.len())?;
+                writeln!(
+                    self.toc,
+                    "{}    <offset>{}</offset>",
+                    indent,
+                    CHECKSUM_LEN + self.heap.len()
+                )?;
+                writeln!(self.toc, "{}    <size>{}</size>", indent, data.len())?;
+                writeln!(
+                    self.toc,
+                    r#"{}    <encoding style="application/octet-stream"/>"#,
+                    indent
+                )?;
+                writeln!(
+                    self.toc,
+                    r#"{}    <extracted-checksum style="sha1">{}</extracted-checksum>"#,
+                    indent, checksum
+                )?;
+                writeln!(
+                    self.toc,
+                    r#"{}    <archived-checksum style="sha1">{}</archived-checksum>"#,
+                    indent, checksum
+                )?;
+                writeln!(self.toc, "{}  </data>", indent)?;
+                self.write_attrs(name, "file", "0644", indent)?;
+                self.heap.extend_from_slice(data);
+            }
+            XarNode::Dir { name, children } => {
+                self.write_attrs(name, "directory", "0755", indent)?;
+                let child_indent = format!("{}  ", indent);
+                for child in children {
+                    self.write_node(child, &child_indent)?;
+                }
+            }
+        }
+        writeln!(self.toc, "{}</file>", indent)?;
+        Ok(())
+    }
+
+    fn write_attrs(
+        &mut self,
+        name: &str,
+        kind: &str,
+        mode: &str,
+        indent: &str,
+    ) -> Result<(), Error> {
+        writeln!(self.toc, "{}  <name>{}</name>", indent, escape_xml(name))?;
+        writeln!(self.toc, "{}  <type>{}</type>", indent, kind)?;
+        writeln!(self.toc, "{}  <mode>{}</mode>", indent, mode)?;
+        writeln!(self.toc, "{}  <uid>0</uid>", indent)?;
+        writeln!(self.toc, "{}  <user>root</user>", indent)?;
+        writeln!(self.toc, "{}  <gid>0</gid>", indent)?;
+        writeln!(self.toc, "{}  <group>wheel</group>", indent)?;
+        Ok(())
+    }
+}
+
+/// Create xar archive of `nodes`.
+pub fn xar_archive(nodes: &[XarNode]) -> Result<Vec<u8>, Error> {
+    let mut writer = Writer::default();
+    writeln!(writer.toc, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer.toc, "<xar>")?;
+    writeln!(writer.toc, " <toc>")?;
+    writeln!(writer.toc, r#"  <checksum style="sha1">"#)?;
+    writeln!(writer.toc, "   <offset>0</offset>")?;
+    writeln!(writer.toc, "   <size>{}</size>", CHECKSUM_LEN)?;
+    writeln!(writer.toc, "  </checksum>")?;
+    writeln!(
+        writer.toc,
+        "  <creation-time>{}</creation-time>",
+        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+    )?;
+    for node in nodes {
+        writer.write_node(node, "  ")?;
+    }
+    writeln!(writer.toc, " </toc>")?;
+    writeln!(writer.toc, "</xar>")?;
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(writer.toc.as_bytes())?;
+    let toc = encoder.finish()?;
+
+    let mut xar =
+        Vec::with_capacity(usize::from(HEADER_LEN) + toc.len() + CHECKSUM_LEN + writer.heap.len());
+    xar.extend_from_slice(&MAGIC.to_be_bytes());
+    xar.extend_from_slice(&HEADER_LEN.to_be_bytes());
+    xar.extend_from_slice(&VERSION.to_be_bytes());
+    xar.extend_from_slice(&(toc.len() as u64).to_be_bytes());
+    xar.extend_from_slice(&(writer.toc.len() as u64).to_be_bytes());
+    xar.extend_from_slice(&CHECKSUM_SHA1.to_be_bytes());
+    let checksum = Sha1::digest(&toc);
+    xar.extend(toc);
+    xar.extend(checksum);
+    xar.extend(writer.heap);
+    Ok(xar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_xar_archive() {
+        let nodes = [
+            XarNode::File {
+                name: "Distribution".to_string(),
+                data: b"dist".to_vec(),
+            },
+            XarNode::Dir {
+                name: "hello.pkg".to_string(),
+                children: vec![XarNode::File {
+                    name: "Payload".to_string(),
+                    data: b"payload".to_vec(),
+                }],
+            },
+        ];
+        let xar = xar_archive(&nodes).unwrap();
+        assert_eq!(&xar[..4], b"xar!");
+        let toc_len = usize::try_from(u64::from_be_bytes(xar[8..16].try_into().unwrap())).unwrap();
+        let toc = &xar[28..28 + toc_len];
+        let heap = &xar[28 + toc_len..];
+        assert_eq!(hex::encode(&heap[..20]), sha1_hex(toc));
+        assert_eq!(&heap[20..], b"distpayload");
+
+        let mut xml = String::new();
+        ZlibDecoder::new(toc).read_to_string(&mut xml).unwrap();
+        assert!(xml.contains("<offset>24</offset>"));
+        assert!(xml.contains("    <file id=\"3\">\n      <data>\n"));
+    }
+}
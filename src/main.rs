@@ -2,13 +2,22 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use pifu::{read_cmdline, Error};
+use colored::Colorize;
+use std::process::ExitCode;
 
-fn main() -> Result<(), Error> {
+use pifu::read_cmdline;
+
+fn main() -> ExitCode {
     if cfg!(debug_assertions) {
         std::env::set_var("RUST_LOG", "info");
     }
     env_logger::init();
 
-    read_cmdline()
+    match read_cmdline() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{} {}", "Error:".red(), err.report());
+            ExitCode::from(err.kind().exit_code())
+        }
+    }
 }
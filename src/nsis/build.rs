@@ -9,14 +9,45 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use super::config::NsisConfig;
+use crate::base::runner::Runner;
 use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
 use crate::config::{Config, WindowsConfig};
 use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
 
-pub fn build_nsis(conf: &Config, windows_conf: &WindowsConfig, arch: Arch) -> Result<(), Error> {
-    let nsis_conf = if let Some(nsis_conf) = windows_conf.nsis.as_ref() {
-        nsis_conf
-    } else {
+/// Packager of windows installer, `makensis` command is required.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NsisPackager;
+
+impl Packager for NsisPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Nsis
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let windows_conf = conf.windows_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_nsis(conf, windows_conf, arch, &runner)
+    }
+}
+
+/// Build windows installer.
+///
+/// Returns path to generated installer, or an empty list if custom `script` is used,
+/// as its output file is unknown.
+pub fn build_nsis(
+    conf: &Config,
+    windows_conf: &WindowsConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let Some(nsis_conf) = windows_conf.nsis.as_ref() else {
         return Err(Error::new(
             ErrorKind::InvalidConfError,
             "`nsis` config not set!",
@@ -24,13 +55,24 @@ pub fn build_nsis(conf: &Config, windows_conf: &WindowsConfig, arch: Arch) -> Re
     };
 
     if let Some(script) = nsis_conf.script.as_ref() {
-        compile_nsis(&script)
+        compile_nsis(&src_file(conf, script)?, runner)?;
+        Ok(Vec::new())
     } else {
         let nsis_file = generate_nsis_file(conf, windows_conf, arch, nsis_conf)?;
-        compile_nsis(&nsis_file)
+        compile_nsis(&nsis_file, runner)?;
+        let artifact_name =
+            expand_file_macro(&nsis_conf.artifact_name, conf, arch, PlatformTarget::Nsis)?;
+        let nsis_dir = nsis_file.parent().unwrap_or_else(|| Path::new("."));
+        Ok(vec![nsis_dir.join(artifact_name)])
     }
 }
 
+/// Get absolute path of `path`, which is relative to `src_dir`.
+fn src_file(conf: &Config, path: &str) -> Result<PathBuf, Error> {
+    let file = Path::new(&conf.metadata.src_dir).join(path);
+    fs::canonicalize(&file).map_err(|err| Error::from(err).with_path(&file))
+}
+
 fn define_header(
     conf: &Config,
     arch: Arch,
@@ -42,7 +84,7 @@ fn define_header(
     writeln!(nsis_fd, "!include \"MUI2.nsh\"\n")?;
 
     if let Some(include_file) = nsis_conf.include.as_ref() {
-        writeln!(nsis_fd, "!include {:?}\n", fs::canonicalize(include_file)?)?;
+        writeln!(nsis_fd, "!include {:?}\n", src_file(conf, include_file)?)?;
     }
 
     writeln!(nsis_fd, "Name {}", &conf.metadata.name)?;
@@ -68,17 +110,17 @@ fn define_header(
     Ok(())
 }
 
-fn define_icons(nsis_conf: &NsisConfig, nsis_fd: &mut File) -> Result<(), Error> {
+fn define_icons(conf: &Config, nsis_conf: &NsisConfig, nsis_fd: &mut File) -> Result<(), Error> {
     // Icons
     writeln!(
         nsis_fd,
         "!define MUI_ICON {:?}",
-        fs::canonicalize(&nsis_conf.installer_icon)?
+        src_file(conf, &nsis_conf.installer_icon)?
     )?;
     writeln!(
         nsis_fd,
         "!define MUI_UNICON {:?}",
-        fs::canonicalize(&nsis_conf.uninstaller_icon)?
+        src_file(conf, &nsis_conf.uninstaller_icon)?
     )?;
 
     if let Some(header_icon) = nsis_conf.installer_header_icon.as_ref() {
@@ -86,21 +128,21 @@ fn define_icons(nsis_conf: &NsisConfig, nsis_fd: &mut File) -> Result<(), Error>
         writeln!(
             nsis_fd,
             "!define MUI_HEADERIMAGE_BITMAP {:?}",
-            fs::canonicalize(header_icon)?
+            src_file(conf, header_icon)?
         )?;
     }
     if let Some(installer_sidebar) = nsis_conf.installer_sidebar.as_ref() {
         writeln!(
             nsis_fd,
             "!define MUI_WELCOMEFINISHPAGE_BITMAP {:?}",
-            fs::canonicalize(installer_sidebar)?
+            src_file(conf, installer_sidebar)?
         )?;
     }
     if let Some(uninstaller_sidebar) = nsis_conf.uninstaller_sidebar.as_ref() {
         writeln!(
             nsis_fd,
             "!define MUI_UNWELCOMEFINISHPAGE_BITMAP {:?}",
-            fs::canonicalize(uninstaller_sidebar)?
+            src_file(conf, uninstaller_sidebar)?
         )?;
     }
 
@@ -133,11 +175,7 @@ fn define_pages(
                     &conf.metadata.name
                 )?;
             } else {
-                writeln!(
-                    nsis_fd,
-                    r#"InstallDir $PROGRAMFILES\{}"#,
-                    &conf.metadata.name
-                )?;
+                writeln!(nsis_fd, r"InstallDir $PROGRAMFILES\{}", &conf.metadata.name)?;
             }
             writeln!(nsis_fd, "RequestExecutionlevel Admin")?;
         } else {
@@ -159,7 +197,7 @@ fn define_pages(
             writeln!(
                 nsis_fd,
                 "!insertmacro MUI_PAGE_LICENSE {:?}",
-                fs::canonicalize(license_file)?
+                src_file(conf, license_file)?
             )?;
         }
 
@@ -281,6 +319,7 @@ fn define_uninstall_section(
 fn define_install_section(
     conf: &Config,
     windows_conf: &WindowsConfig,
+    arch: Arch,
     nsis_conf: &NsisConfig,
     nsis_fd: &mut File,
     nsis_dir: &Path,
@@ -299,7 +338,11 @@ fn define_install_section(
     // Install section
     writeln!(nsis_fd, "\nSection \"Install\"")?;
     writeln!(nsis_fd, r#"  SetOutPath "$INSTDIR""#)?;
-    for file in files {
+    for file in files
+        .iter()
+        .filter(|file| file.matches(arch, PlatformTarget::Nsis))
+    {
+        let file = file.expand(conf, arch, PlatformTarget::Nsis)?;
         file.copy_to(&conf.metadata.src_dir, nsis_dir)?;
         writeln!(nsis_fd, "  File {}", &file.to)?;
     }
@@ -312,7 +355,7 @@ fn define_install_section(
     };
 
     let reg_uninst_key = format!(
-        r#"Software\Microsoft\Windows\CurrentVersion\Uninstall\{}"#,
+        r"Software\Microsoft\Windows\CurrentVersion\Uninstall\{}",
         &conf.metadata.product_name
     );
 
@@ -404,26 +447,21 @@ fn generate_nsis_file(
     let mut nsis_fd = File::create(&nsis_file)?;
 
     define_header(conf, arch, nsis_conf, &mut nsis_fd)?;
-    define_icons(nsis_conf, &mut nsis_fd)?;
+    define_icons(conf, nsis_conf, &mut nsis_fd)?;
     define_pages(conf, windows_conf, arch, nsis_conf, &mut nsis_fd)?;
     define_languages(conf, &mut nsis_fd)?;
-    define_install_section(conf, windows_conf, nsis_conf, &mut nsis_fd, &nsis_dir)?;
+    define_install_section(conf, windows_conf, arch, nsis_conf, &mut nsis_fd, &nsis_dir)?;
 
     Ok(nsis_file)
 }
 
 /// Compile nsis script
-fn compile_nsis<P>(nsis_file: &P) -> Result<(), Error>
+fn compile_nsis<P>(nsis_file: &P, runner: &Runner) -> Result<(), Error>
 where
     P: AsRef<Path> + Debug,
 {
-    let status = Command::new("makensis").arg(nsis_file.as_ref()).status()?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(Error::from_string(
-            ErrorKind::NsisCompilerError,
-            format!("`makensis` returns error while compiling {:?}", nsis_file),
-        ))
-    }
+    let mut cmd = Command::new("makensis");
+    cmd.arg(nsis_file.as_ref());
+    runner.run(&mut cmd, ErrorKind::NsisCompilerError)?;
+    Ok(())
 }
@@ -2,14 +2,20 @@
 // Use of this source is governed by General Public License that can be found
 // in the LICENSE file.
 
-use serde::{Deserialize, Serialize};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
 
 use crate::base::fileset::FileSet;
 use crate::base::utils::{default_false, default_true};
+use crate::base::{deserialize_named, NamedEnum};
 
 /// `NsisConfig` is defined based on <https://www.electron.build/configuration/nsis>
-#[derive(Debug, Deserialize, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct NsisConfig {
     pub files: Option<Vec<FileSet>>,
 
@@ -37,13 +43,13 @@ pub struct NsisConfig {
     #[serde(default = "default_true")]
     pub allow_to_change_installation_directory: bool,
 
-    /// String - The path to installer icon.
+    /// String - The path to installer icon, relative to `src_dir`.
     pub installer_icon: String,
 
-    /// String - The path to uninstaller icon.
+    /// String - The path to uninstaller icon, relative to `src_dir`.
     pub uninstaller_icon: String,
 
-    /// String - assisted installer only. MUI_HEADERIMAGE
+    /// String - assisted installer only. `MUI_HEADERIMAGE`
     pub installer_header: Option<String>,
 
     /// String - one-click installer only.
@@ -52,12 +58,12 @@ pub struct NsisConfig {
     /// Image format is bmp, and image size is 150x57 pixels.
     pub installer_header_icon: Option<String>,
 
-    /// String - assisted installer only. MUI_WELCOMEFINISHPAGE_BITMAP.
+    /// String - assisted installer only. `MUI_WELCOMEFINISHPAGE_BITMAP`.
     ///
     /// Image format is bmp, and image size 164 × 314 pixels.
     pub installer_sidebar: Option<String>,
 
-    /// String - assisted installer only. MUI_UNWELCOMEFINISHPAGE_BITMAP.
+    /// String - assisted installer only. `MUI_UNWELCOMEFINISHPAGE_BITMAP`.
     ///
     /// Image format is bmp, and image size 164 × 314 pixels.
     pub uninstaller_sidebar: Option<String>,
@@ -67,10 +73,10 @@ pub struct NsisConfig {
     /// Default is `${product_name} ${version}`.
     pub uninstall_display_name: Option<String>,
 
-    /// String - The path to NSIS include script to customize installer.
+    /// String - The path to NSIS include script to customize installer, relative to `src_dir`.
     pub include: Option<String>,
 
-    /// String - The path to NSIS script to customize installer.
+    /// String - The path to NSIS script to customize installer, relative to `src_dir`.
     ///
     /// Not recommanded.
     pub script: Option<String>,
@@ -125,25 +131,40 @@ pub struct NsisConfig {
     pub compress_method: CompressMethod,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
 pub enum CompressMethod {
-    #[serde(alias = "bzip2")]
     BZip2,
-
-    #[serde(alias = "lzma")]
     Lzma,
-
-    #[serde(alias = "zlib")]
     Zlib,
 }
 
+impl NamedEnum for CompressMethod {
+    const NAMES: &'static [(Self, &'static str)] = &[
+        (Self::BZip2, "bzip2"),
+        (Self::Lzma, "lzma"),
+        (Self::Zlib, "zlib"),
+    ];
+}
+
+impl<'de> Deserialize<'de> for CompressMethod {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_named(deserializer)
+    }
+}
+
+impl JsonSchema for CompressMethod {
+    fn schema_name() -> String {
+        "CompressMethod".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        Self::schema("Compressor of nsis installer")
+    }
+}
+
 impl fmt::Display for CompressMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::BZip2 => write!(f, "bzip2"),
-            Self::Lzma => write!(f, "lzma"),
-            Self::Zlib => write!(f, "zlib"),
-        }
+        f.write_str(self.name())
     }
 }
 
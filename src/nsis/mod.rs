@@ -5,5 +5,5 @@
 mod build;
 mod config;
 
-pub use build::build_nsis;
-pub use config::NsisConfig;
+pub use build::NsisPackager;
+pub use config::{CompressMethod, NsisConfig};
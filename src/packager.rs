@@ -0,0 +1,42 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::base::{Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::Config;
+use crate::error::Error;
+
+/// A file generated by packager.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Artifact {
+    pub target: PlatformTarget,
+
+    /// Packager name, plugin name for `PlatformTarget::Plugin`.
+    pub name: String,
+
+    pub arch: Arch,
+    pub path: PathBuf,
+}
+
+/// Package format backend.
+pub trait Packager {
+    /// Package format generated by this packager.
+    fn target(&self) -> PlatformTarget;
+
+    /// Name of packager used in progress messages.
+    fn name(&self) -> String {
+        self.target().to_string()
+    }
+
+    /// Build package for `arch` and returns paths to generated files.
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error>;
+}
@@ -0,0 +1,271 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! External packagers are executables which speak a simple JSON protocol:
+//!
+//! - pifu copies file sets of the plugin into its staging directory,
+//! - runs the plugin executable and writes a `PluginRequest` object to its stdin,
+//! - plugin builds packages, and writes a `PluginResponse` object to stdout,
+//!   messages shall be printed to stderr, which are saved to log file,
+//! - plugin exits with status code 0 on success.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::config::PluginConfig;
+use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
+use crate::base::{utils, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::Config;
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Version of plugin protocol, increased on incompatible changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Message sent to plugin via stdin.
+#[derive(Debug, Serialize)]
+pub struct PluginRequest<'a> {
+    pub protocol_version: u32,
+
+    /// Plugin name, as in `[plugins.<name>]`.
+    pub name: &'a str,
+
+    /// Architecture name, like `x86_64`.
+    pub arch: String,
+
+    /// Rust target triple of `arch`, like `x86_64-unknown-linux-gnu`.
+    pub triple: Option<String>,
+
+    /// Absolute path to directory containing files to be packaged.
+    pub staging_dir: PathBuf,
+
+    /// Absolute path to directory where packages shall be placed.
+    pub workdir: PathBuf,
+
+    /// Resolved config.
+    pub config: &'a Config,
+
+    /// Plugin specific options.
+    pub options: Option<&'a serde_json::Value>,
+}
+
+/// Message read from stdout of plugin.
+#[derive(Debug, Deserialize)]
+pub struct PluginResponse {
+    /// Generated files, relative to `workdir` or absolute.
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// Packager which delegates to an external executable.
+#[derive(Debug, Clone)]
+pub struct PluginPackager {
+    name: String,
+}
+
+impl PluginPackager {
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Packager for PluginPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Plugin
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let plugin_conf = conf.plugins.get(&self.name).ok_or_else(|| {
+            Error::from_string(
+                ErrorKind::InvalidConfError,
+                format!("`plugins.{}` config not set!", self.name),
+            )
+        })?;
+        let runner = options.runner(conf, &self.name, arch)?;
+        build_plugin(conf, &self.name, plugin_conf, arch, &runner)
+    }
+}
+
+pub fn build_plugin(
+    conf: &Config,
+    name: &str,
+    plugin_conf: &PluginConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let files = if let Some(files) = plugin_conf.files.as_ref() {
+        files
+    } else if let Some(files) = conf.linux.as_ref().and_then(|linux| linux.files.as_ref()) {
+        files
+    } else {
+        return Err(Error::from_string(
+            ErrorKind::FilesNotSet,
+            format!("`files` property not set for plugin {}", name),
+        ));
+    };
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    // Separated from staging directories of builtin targets.
+    let staging_dir = workdir.join("plugins").join(name);
+    utils::rmdir(&staging_dir)?;
+    fs::create_dir_all(&staging_dir)?;
+    copy_filesets(files, conf, arch, PlatformTarget::Plugin, &staging_dir)?;
+
+    let workdir = fs::canonicalize(workdir)?;
+    let triple = conf
+        .build
+        .as_ref()
+        .and_then(|build_conf| build_conf.triple(arch, PlatformTarget::Plugin));
+    let request = PluginRequest {
+        protocol_version: PROTOCOL_VERSION,
+        name,
+        arch: arch.to_string(),
+        triple,
+        staging_dir: fs::canonicalize(&staging_dir)?,
+        workdir: workdir.clone(),
+        config: conf,
+        options: plugin_conf.options.as_ref(),
+    };
+    let response = run_plugin(&plugin_conf.command(name), &request, runner)?;
+
+    Ok(response
+        .artifacts
+        .into_iter()
+        .map(|path| workdir.join(path))
+        .collect())
+}
+
+fn run_plugin(
+    command: &str,
+    request: &PluginRequest,
+    runner: &Runner,
+) -> Result<PluginResponse, Error> {
+    let input = serde_json::to_vec(request)?;
+    let mut cmd = Command::new(command);
+    let (stdout, stderr) =
+        runner.run_with_stderr(&mut cmd, Some(&input), ErrorKind::PluginError)?;
+
+    serde_json::from_str(&stdout).map_err(|err| {
+        Error::from_string(
+            ErrorKind::PluginError,
+            format!("Invalid response from plugin `{}`", command),
+        )
+        .with_source(err)
+        .with_command(command)
+        .with_stderr(&stderr)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+    use crate::base::fileset::FileSet;
+    use crate::base::Metadata;
+
+    /// Write a plugin script to `dir`, it saves request to `request.json`.
+    fn write_plugin(dir: &Path, body: &str) -> String {
+        let path = dir.join("pifu-stub");
+        let script = format!(
+            "#!/bin/sh\ncat > \"{}\"\n{}\n",
+            dir.join("request.json").display(),
+            body
+        );
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_build_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = fs::canonicalize(dir.path()).unwrap();
+        let src_dir = dir.join("src");
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::write(src_dir.join("hello.sh"), "echo hello").unwrap();
+
+        let mut metadata = Metadata::new("hello", "1.0.0");
+        metadata.workdir = dir.join("pifu").to_string_lossy().to_string();
+        metadata.src_dir = src_dir.to_string_lossy().to_string();
+        let conf = Config::new(metadata);
+        let plugin_conf = PluginConfig {
+            command: Some(write_plugin(
+                &dir,
+                r#"echo building >&2; echo '{"artifacts": ["hello.pkg", "/tmp/hello.sig"]}'"#,
+            )),
+            files: Some(vec![FileSet {
+                from: "hello.sh".to_string(),
+                to: "bin/hello.sh".to_string(),
+                filter: None,
+                mode: None,
+                arch: None,
+                targets: None,
+            }]),
+            options: Some(serde_json::json!({"level": 3})),
+            ..PluginConfig::default()
+        };
+        let runner = Runner::new(&dir, "stub", false).unwrap();
+        let artifacts = build_plugin(&conf, "stub", &plugin_conf, Arch::X86_64, &runner).unwrap();
+        assert_eq!(
+            artifacts,
+            [dir.join("pifu/hello.pkg"), PathBuf::from("/tmp/hello.sig")]
+        );
+
+        let request: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("request.json")).unwrap()).unwrap();
+        assert_eq!(request["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(request["name"], "stub");
+        assert_eq!(request["arch"], "x86_64");
+        assert_eq!(request["config"]["metadata"]["name"], "hello");
+        assert_eq!(request["options"]["level"], 3);
+        let staging_dir = PathBuf::from(request["staging_dir"].as_str().unwrap());
+        assert!(staging_dir.join("bin/hello.sh").is_file());
+    }
+
+    #[test]
+    fn test_run_plugin_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let runner = Runner::new(dir, "stub", false).unwrap();
+        let conf = Config::new(Metadata::new("hello", "1.0.0"));
+        let request = PluginRequest {
+            protocol_version: PROTOCOL_VERSION,
+            name: "stub",
+            arch: Arch::X86_64.to_string(),
+            triple: None,
+            staging_dir: dir.join("staging"),
+            workdir: dir.to_path_buf(),
+            config: &conf,
+            options: None,
+        };
+
+        // Non-zero exit status.
+        let command = write_plugin(dir, "echo 'missing tool' >&2; exit 2");
+        let err = run_plugin(&command, &request, &runner).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PluginError);
+        assert_eq!(err.context().stderr.as_deref(), Some("missing tool"));
+
+        // Malformed response.
+        let command = write_plugin(dir, "echo 'bad response' >&2; echo '{\"artifacts\": '");
+        let err = run_plugin(&command, &request, &runner).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PluginError);
+        assert_eq!(err.context().stderr.as_deref(), Some("bad response"));
+    }
+}
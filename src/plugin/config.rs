@@ -0,0 +1,57 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::base::fileset::FileSet;
+use crate::base::Arch;
+
+/// Config of `[plugins.<name>]` section, an external packager which builds
+/// custom package format.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// String - Path to plugin executable.
+    ///
+    /// Default is `pifu-<name>`, which is searched in `$PATH`.
+    pub command: Option<String>,
+
+    /// Architectures to build.
+    #[serde(default = "default_arch")]
+    pub arch: Vec<Arch>,
+
+    /// Files copied to staging directory before running plugin.
+    ///
+    /// Default is `files` of `linux` section.
+    pub files: Option<Vec<FileSet>>,
+
+    /// Plugin specific options, passed to plugin as is.
+    pub options: Option<serde_json::Value>,
+}
+
+impl PluginConfig {
+    /// Get path to plugin executable.
+    #[must_use]
+    pub fn command(&self, name: &str) -> String {
+        self.command
+            .clone()
+            .unwrap_or_else(|| format!("pifu-{}", name))
+    }
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            arch: default_arch(),
+            files: None,
+            options: None,
+        }
+    }
+}
+
+fn default_arch() -> Vec<Arch> {
+    vec![Arch::X86_64]
+}
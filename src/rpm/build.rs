@@ -4,19 +4,50 @@
 
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use super::config::RpmConfig;
+use crate::appstream::install_metainfo;
 use crate::base::archive;
 use crate::base::compress;
 use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
 use crate::base::utils;
-use crate::base::Arch;
+use crate::base::{Arch, PlatformTarget};
+use crate::build::BuildOptions;
 use crate::config::{Config, LinuxConfig};
+use crate::desktop::install_desktop;
 use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
 
-pub fn build_rpm(conf: &Config, linux_conf: &LinuxConfig, _arch: Arch) -> Result<(), Error> {
+/// Packager of `.rpm` format, `rpmbuild` command is required.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RpmPackager;
+
+impl Packager for RpmPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Rpm
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_rpm(conf, linux_conf, arch, &runner)
+    }
+}
+
+pub fn build_rpm(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
     let rpm_conf = &linux_conf.rpm;
 
     let workdir = Path::new(&conf.metadata.workdir);
@@ -26,15 +57,11 @@ pub fn build_rpm(conf: &Config, linux_conf: &LinuxConfig, _arch: Arch) -> Result
     fs::create_dir_all(&rpm_dir)?;
     let spec_file = rpm_dir.join(format!("{}.spec", &conf.metadata.name));
     let mut spec_fd = File::create(&spec_file).map_err(|err| {
-        Error::from_string(
-            ErrorKind::IoError,
-            format!(
-                "Failed to create rpm spec file {:?}, error: {:?}",
-                &spec_file, err
-            ),
-        )
+        Error::new(ErrorKind::IoError, "Failed to create rpm spec file")
+            .with_source(err)
+            .with_path(&spec_file)
     })?;
-    let source_dir = rpm_dir.join(&format!(
+    let source_dir = rpm_dir.join(format!(
         "{}-{}",
         &conf.metadata.name, &conf.metadata.version
     ));
@@ -53,7 +80,20 @@ pub fn build_rpm(conf: &Config, linux_conf: &LinuxConfig, _arch: Arch) -> Result
             "`files` property not set for rpm format",
         ));
     };
-    copy_filesets(files, &conf.metadata.src_dir, &source_dir)?;
+    copy_filesets(files, conf, arch, PlatformTarget::Rpm, &source_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::Rpm,
+            &source_dir,
+            false,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &source_dir)?;
+    }
 
     // Create binary tarbal.
     let source_tar_file = rpm_dir.join(format!("{}.tar", &conf.metadata.name));
@@ -67,10 +107,9 @@ pub fn build_rpm(conf: &Config, linux_conf: &LinuxConfig, _arch: Arch) -> Result
     let new_source_xz_file = rpm_source_dir.join(format!("{}.tar.xz", &conf.metadata.name));
     fs::rename(&source_xz_file, new_source_xz_file)?;
 
-    generate_rpm_file(&spec_file, &rpm_dir)?;
+    generate_rpm_file(&spec_file, &rpm_dir, runner)?;
 
-    let rpm_dir = rpm_dir.to_str().unwrap();
-    move_rpm_files(rpm_dir)
+    move_rpm_files(&rpm_dir, workdir)
 }
 
 fn generate_spec_file(
@@ -102,7 +141,7 @@ fn generate_spec_file(
     writeln!(spec_fd, "\n%description\n{}", &conf.metadata.description)?;
     writeln!(
         spec_fd,
-        r#"%prep  
+        r"%prep  
 %setup -q           
 
 %build 
@@ -122,13 +161,13 @@ cp -rfa * %{{buildroot}}
 
 %files
 /*
-"#
+"
     )?;
 
     Ok(())
 }
 
-fn generate_rpm_file(spec_file: &Path, rpm_dir: &Path) -> Result<(), Error> {
+fn generate_rpm_file(spec_file: &Path, rpm_dir: &Path, runner: &Runner) -> Result<(), Error> {
     log::info!(
         "generate_rpm_file() spec: {:?}, rpm_dir: {:?}",
         spec_file,
@@ -137,34 +176,14 @@ fn generate_rpm_file(spec_file: &Path, rpm_dir: &Path) -> Result<(), Error> {
     let def = format!("_topdir {}", fs::canonicalize(rpm_dir)?.display());
 
     let mut cmd = Command::new("rpmbuild");
-    //if cfg!(not(debug_assertions)) {
-    cmd.stdout(Stdio::null()).stderr(Stdio::null());
-    //}
     // Change rootdir of rpm build.
-    let status = cmd
-        .arg("-D")
-        .arg(&def)
-        .arg("-bb")
-        .arg(spec_file)
-        .status()
-        .map_err(|err| {
-            Error::from_string(
-                ErrorKind::RpmCompilerError,
-                format!("Failed to run `rpmbuild` command, error: {:?}, please check `rpm` package is installed", err),
-            )
-        })?;
-
-    if status.success() {
-        Ok(())
-    } else {
-        Err(Error::from_string(
-            ErrorKind::RpmCompilerError,
-            format!("rpm file: {:?}", spec_file),
-        ))
-    }
+    cmd.arg("-D").arg(&def).arg("-bb").arg(spec_file);
+    runner.run(&mut cmd, ErrorKind::RpmCompilerError)?;
+    Ok(())
 }
 
-fn move_rpm_files(rpm_dir: &str) -> Result<(), Error> {
-    let rpm_files = format!("{}/RPMS/*/*.rpm", rpm_dir);
-    utils::mv(&rpm_files, rpm_dir)
+/// Move generated rpm files to `workdir`.
+fn move_rpm_files(rpm_dir: &Path, workdir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let rpm_files = format!("{}/RPMS/*/*.rpm", rpm_dir.display());
+    utils::mv(&rpm_files, workdir)
 }
@@ -5,5 +5,5 @@
 mod build;
 mod config;
 
-pub use build::build_rpm;
+pub use build::RpmPackager;
 pub use config::RpmConfig;
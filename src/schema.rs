@@ -0,0 +1,54 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::schema::{InstanceType, Metadata, RootSchema, Schema, SchemaObject};
+use schemars::schema_for;
+
+use crate::config::Config;
+use crate::error::Error;
+
+/// Schema of enum types, which accept both variant names and their serde aliases.
+#[must_use]
+pub fn enum_schema(description: &str, values: &[&str]) -> Schema {
+    SchemaObject {
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_string()),
+            ..Metadata::default()
+        })),
+        instance_type: Some(InstanceType::String.into()),
+        enum_values: Some(values.iter().map(|value| (*value).into()).collect()),
+        ..SchemaObject::default()
+    }
+    .into()
+}
+
+/// Schema of `pifu.toml`, also used to find unknown keys in config file.
+#[must_use]
+pub fn config_schema() -> RootSchema {
+    schema_for!(Config)
+}
+
+/// Generate JSON schema of `pifu.toml`.
+pub fn generate_schema() -> Result<String, Error> {
+    serde_json::to_string_pretty(&config_schema()).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_schema() {
+        let schema = generate_schema().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        let definitions = &value["definitions"];
+        assert!(definitions["NsisConfig"]["properties"]["one_click"]["description"].is_string());
+        assert_eq!(
+            definitions["NsisConfig"]["properties"]["one_click"]["default"],
+            true
+        );
+        let arches = definitions["Arch"]["enum"].as_array().unwrap();
+        assert!(arches.contains(&serde_json::Value::from("x86_64")));
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Keys used to sign packages, shared by all targets which support signing.
+#[derive(Debug, Default, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignConfig {
+    /// String - Key id, fingerprint or email of signing key.
+    ///
+    /// Default key of gpg is used if not set.
+    pub gpg_key: Option<String>,
+
+    /// String - Home directory of gpg, default is `$GNUPGHOME` or `~/.gnupg`.
+    pub gpg_home: Option<String>,
+
+    /// String - Name of environment variable which holds passphrase of key,
+    /// like `PIFU_GPG_PASSPHRASE`, so that it can be used in CI.
+    pub passphrase_env: Option<String>,
+
+    /// String - gpg command, default is `gpg`.
+    #[serde(default = "default_gpg")]
+    pub gpg: String,
+
+    /// String - Path to RSA private key generated by `abuild-keygen`, like
+    /// `/home/user/.abuild/user-5f1a2b3c.rsa`, used to sign apk packages.
+    ///
+    /// Public key shall be installed to `/etc/apk/keys/<file name of key>.pub`.
+    pub abuild_key: Option<String>,
+
+    /// String - openssl command, default is `openssl`.
+    #[serde(default = "default_openssl")]
+    pub openssl: String,
+}
+
+fn default_gpg() -> String {
+    "gpg".to_string()
+}
+
+fn default_openssl() -> String {
+    "openssl".to_string()
+}
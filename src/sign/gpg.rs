@@ -0,0 +1,108 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::config::SignConfig;
+use crate::base::runner::Runner;
+use crate::error::{Error, ErrorKind};
+
+fn gpg_command(sign_conf: &SignConfig) -> Command {
+    let mut cmd = Command::new(&sign_conf.gpg);
+    cmd.arg("--batch");
+    if let Some(gpg_home) = sign_conf.gpg_home.as_ref() {
+        cmd.arg("--homedir").arg(gpg_home);
+    }
+    cmd
+}
+
+/// Read passphrase from environment variable set in `sign_conf`.
+pub(super) fn passphrase(sign_conf: &SignConfig) -> Result<Option<String>, Error> {
+    let Some(name) = sign_conf.passphrase_env.as_ref() else {
+        return Ok(None);
+    };
+    env::var(name).map(Some).map_err(|err| {
+        Error::from_string(
+            ErrorKind::EnvironmentNotSetError,
+            format!(
+                "Environment variable `{}` for passphrase of signing key not set",
+                name
+            ),
+        )
+        .with_source(err)
+    })
+}
+
+/// Get signing key in `sign_conf`, or fingerprint of first secret key.
+pub fn signing_key(sign_conf: &SignConfig, runner: &Runner) -> Result<String, Error> {
+    if let Some(key) = sign_conf.gpg_key.as_ref() {
+        return Ok(key.clone());
+    }
+    let mut cmd = gpg_command(sign_conf);
+    cmd.arg("--list-secret-keys").arg("--with-colons");
+    let stdout = runner.run(&mut cmd, ErrorKind::SignError)?;
+    // Fingerprint of primary key follows `sec` record.
+    stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("sec:"))
+        .find_map(|line| line.strip_prefix("fpr:"))
+        .and_then(|fpr| fpr.split(':').find(|field| !field.is_empty()))
+        .map(ToString::to_string)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::SignError,
+                "No secret key found in gpg keyring, set `sign.gpg_key`",
+            )
+        })
+}
+
+/// Create armored detached signature of `file` at `<file>.asc`.
+pub fn gpg_detach_sign(
+    sign_conf: &SignConfig,
+    file: &Path,
+    key: &str,
+    runner: &Runner,
+) -> Result<PathBuf, Error> {
+    let mut sig_file = file.as_os_str().to_owned();
+    sig_file.push(".asc");
+    let sig_file = PathBuf::from(sig_file);
+    if sig_file.exists() {
+        fs::remove_file(&sig_file)?;
+    }
+
+    let mut cmd = gpg_command(sign_conf);
+    cmd.arg("--yes")
+        .arg("--armor")
+        .arg("--detach-sign")
+        .arg("--local-user")
+        .arg(key);
+    let passphrase = passphrase(sign_conf)?;
+    if passphrase.is_some() {
+        cmd.args(["--pinentry-mode", "loopback", "--passphrase-fd", "0"]);
+    }
+    cmd.arg("--output").arg(&sig_file).arg(file);
+    runner.run_with_input(
+        &mut cmd,
+        passphrase.as_ref().map(String::as_bytes),
+        ErrorKind::SignError,
+    )?;
+    Ok(sig_file)
+}
+
+/// Export armored public key of signing `key`.
+pub fn gpg_export_key(sign_conf: &SignConfig, key: &str, runner: &Runner) -> Result<String, Error> {
+    let mut cmd = gpg_command(sign_conf);
+    cmd.arg("--armor").arg("--export").arg(key);
+    let key = runner.run(&mut cmd, ErrorKind::SignError)?;
+    if key.trim().is_empty() {
+        return Err(Error::new(
+            ErrorKind::SignError,
+            "No public key exported by gpg, check `sign.gpg_key`",
+        ));
+    }
+    Ok(key)
+}
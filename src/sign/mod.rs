@@ -0,0 +1,11 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod config;
+mod gpg;
+mod openssl;
+
+pub use config::SignConfig;
+pub use gpg::{gpg_detach_sign, gpg_export_key, signing_key};
+pub use openssl::openssl_sign;
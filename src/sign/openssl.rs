@@ -0,0 +1,39 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::config::SignConfig;
+use super::gpg::passphrase;
+use crate::base::runner::Runner;
+use crate::error::{Error, ErrorKind};
+
+/// Sign `file` with RSA private `key` and SHA-256 digest, returns raw signature.
+pub fn openssl_sign(
+    sign_conf: &SignConfig,
+    key: &Path,
+    file: &Path,
+    runner: &Runner,
+) -> Result<Vec<u8>, Error> {
+    let mut sig_file = file.as_os_str().to_owned();
+    sig_file.push(".sig");
+
+    let mut cmd = Command::new(&sign_conf.openssl);
+    cmd.arg("dgst").arg("-sha256").arg("-sign").arg(key);
+    let passphrase = passphrase(sign_conf)?.map(|passphrase| passphrase + "\n");
+    if passphrase.is_some() {
+        cmd.args(["-passin", "stdin"]);
+    }
+    cmd.arg("-out").arg(&sig_file).arg(file);
+    runner.run_with_input(
+        &mut cmd,
+        passphrase.as_ref().map(String::as_bytes),
+        ErrorKind::SignError,
+    )?;
+    let signature = fs::read(&sig_file).map_err(|err| Error::from(err).with_path(&sig_file))?;
+    fs::remove_file(&sig_file)?;
+    Ok(signature)
+}
@@ -0,0 +1,122 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::metadata::{
+    snap_arch, snap_yaml, validate_snap, validate_snap_name, validate_snap_version,
+};
+use crate::appstream::install_metainfo;
+use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::check::reject_errors;
+use crate::config::{Config, LinuxConfig};
+use crate::desktop::install_desktop;
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of `.snap` format, `mksquashfs` command is required.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SnapPackager;
+
+impl Packager for SnapPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Snap
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_snap(conf, linux_conf, arch, &runner)
+    }
+}
+
+pub fn build_snap(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let snap_conf = &linux_conf.snap;
+    reject_errors("linux.snap", &validate_snap(conf, snap_conf))?;
+    validate_snap_name(&conf.metadata.name)
+        .and_then(|()| validate_snap_version(&conf.metadata.version))
+        .map_err(|message| {
+            Error::from_string(
+                ErrorKind::InvalidConfError,
+                format!("Invalid config: {}", message),
+            )
+        })?;
+    let snap_arch = snap_arch(arch).ok_or_else(|| {
+        Error::from_string(
+            ErrorKind::InvalidConfError,
+            format!("Arch {} is not supported by snap", arch),
+        )
+    })?;
+
+    let files = if let Some(files) = snap_conf.files.as_ref() {
+        files
+    } else if let Some(files) = linux_conf.files.as_ref() {
+        files
+    } else {
+        return Err(Error::new(
+            ErrorKind::FilesNotSet,
+            "`files` property not set for snap format",
+        ));
+    };
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    let snap_dir = workdir.join("snap");
+    utils::rmdir(&snap_dir)?;
+    fs::create_dir_all(&snap_dir)?;
+
+    copy_filesets(files, conf, arch, PlatformTarget::Snap, &snap_dir)?;
+    if let Some(desktop_conf) = linux_conf.desktop.as_ref() {
+        install_desktop(
+            conf,
+            desktop_conf,
+            arch,
+            PlatformTarget::Snap,
+            &snap_dir,
+            false,
+        )?;
+    }
+    if let Some(appstream_conf) = linux_conf.appstream.as_ref() {
+        install_metainfo(conf, appstream_conf, &snap_dir)?;
+    }
+
+    let meta_dir = snap_dir.join("meta");
+    fs::create_dir_all(&meta_dir)?;
+    fs::write(
+        meta_dir.join("snap.yaml"),
+        snap_yaml(conf, snap_conf, snap_arch)?,
+    )?;
+
+    let artifact_name =
+        expand_file_macro(&snap_conf.artifact_name, conf, arch, PlatformTarget::Snap)?;
+    let artifact = workdir.join(artifact_name);
+    compile_snap(&snap_dir, &artifact, runner)?;
+    Ok(vec![artifact])
+}
+
+/// Pack `snap_dir` with options required by snapd, see `snap pack`.
+fn compile_snap(snap_dir: &Path, artifact: &Path, runner: &Runner) -> Result<(), Error> {
+    let mut cmd = Command::new("mksquashfs");
+    cmd.arg(snap_dir)
+        .arg(artifact)
+        .args(["-noappend", "-comp", "xz", "-all-root"])
+        .args(["-no-xattrs", "-no-fragments", "-no-progress"]);
+    runner.run(&mut cmd, ErrorKind::MksquashfsError)?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::base::fileset::FileSet;
+
+/// Generate `meta/snap.yaml`, snap name and version are read from metadata.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapConfig {
+    /// File list, copied to root of snap.
+    pub files: Option<Vec<FileSet>>,
+
+    /// String - Single line summary, default is `metadata.description`.
+    pub summary: Option<String>,
+
+    /// String - Long description, default is `metadata.description`.
+    pub description: Option<String>,
+
+    /// String - Base snap providing runtime libraries, like `core22`.
+    pub base: Option<String>,
+
+    /// String - Confinement level, `strict`, `classic` or `devmode`.
+    #[serde(default)]
+    pub confinement: Confinement,
+
+    /// String - Release quality, `stable` or `devel`.
+    #[serde(default)]
+    pub grade: Grade,
+
+    /// Applications exposed by this snap, like `{ hello = { command = "usr/bin/hello" } }`.
+    ///
+    /// Default is an app with package name, which runs `usr/bin/<name>`.
+    #[serde(default)]
+    pub apps: BTreeMap<String, SnapApp>,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapApp {
+    /// String - Command line to run, relative to root of snap.
+    pub command: String,
+
+    /// Array of String - Interfaces to connect, like `["home", "network"]`.
+    #[serde(default)]
+    pub plugs: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confinement {
+    #[default]
+    Strict,
+    Classic,
+    Devmode,
+}
+
+impl Confinement {
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Classic => "classic",
+            Self::Devmode => "devmode",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    #[default]
+    Stable,
+    Devel,
+}
+
+impl Grade {
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Devel => "devel",
+        }
+    }
+}
+
+impl Default for SnapConfig {
+    fn default() -> Self {
+        Self {
+            files: None,
+            summary: None,
+            description: None,
+            base: None,
+            confinement: Confinement::default(),
+            grade: Grade::default(),
+            apps: BTreeMap::new(),
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
@@ -0,0 +1,254 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::config::{SnapApp, SnapConfig};
+use crate::base::Arch;
+use crate::check::Issue;
+use crate::config::Config;
+use crate::error::Error;
+
+/// snapd rejects summary longer than this.
+const MAX_SUMMARY_LEN: usize = 128;
+
+const MAX_NAME_LEN: usize = 40;
+const MAX_VERSION_LEN: usize = 32;
+
+/// Get architecture name used by snapd, `None` if it is not supported.
+#[must_use]
+pub const fn snap_arch(arch: Arch) -> Option<&'static str> {
+    match arch {
+        Arch::X86 => Some("i386"),
+        Arch::X86_64 => Some("amd64"),
+        Arch::AArch64 => Some("arm64"),
+        Arch::Mips64 => None,
+    }
+}
+
+/// Words separated by single hyphens, like `hello-world`.
+fn is_hyphenated(name: &str, is_valid_char: impl Fn(char) -> bool) -> bool {
+    !name.is_empty()
+        && name
+            .split('-')
+            .all(|word| !word.is_empty() && word.chars().all(&is_valid_char))
+}
+
+/// Snap name contains lower case letters, digits and hyphens, with at least one letter.
+pub fn validate_snap_name(name: &str) -> Result<(), String> {
+    let is_valid = name.len() <= MAX_NAME_LEN
+        && is_hyphenated(name, |c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name.contains(|c: char| c.is_ascii_lowercase());
+    if is_valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} is invalid for snap, it shall contain only lower case letters, digits and single hyphens, \
+             with at most {} characters",
+            name, MAX_NAME_LEN
+        ))
+    }
+}
+
+/// Snap version has at most 32 characters, and starts with a letter or digit.
+pub fn validate_snap_version(version: &str) -> Result<(), String> {
+    if version.is_empty() || version.len() > MAX_VERSION_LEN {
+        return Err(format!(
+            "{:?} is invalid for snap, it shall have 1 to {} characters",
+            version, MAX_VERSION_LEN
+        ));
+    }
+    if let Some(c) = version
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !":.+~-".contains(*c))
+    {
+        return Err(format!(
+            "{:?} is invalid for snap, character {:?} is not allowed",
+            version, c
+        ));
+    }
+    if !version.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || version.ends_with(|c: char| ":.-".contains(c))
+    {
+        return Err(format!(
+            "{:?} is invalid for snap, it shall start with a letter or digit",
+            version
+        ));
+    }
+    Ok(())
+}
+
+fn summary<'a>(conf: &'a Config, snap_conf: &'a SnapConfig) -> &'a str {
+    snap_conf
+        .summary
+        .as_deref()
+        .unwrap_or(&conf.metadata.description)
+}
+
+fn description<'a>(conf: &'a Config, snap_conf: &'a SnapConfig) -> &'a str {
+    snap_conf
+        .description
+        .as_deref()
+        .unwrap_or(&conf.metadata.description)
+}
+
+/// Get apps of snap, default is an app with package name.
+fn apps(conf: &Config, snap_conf: &SnapConfig) -> BTreeMap<String, SnapApp> {
+    if snap_conf.apps.is_empty() {
+        let app = SnapApp {
+            command: format!("usr/bin/{}", conf.metadata.name),
+            plugs: Vec::new(),
+        };
+        BTreeMap::from([(conf.metadata.name.clone(), app)])
+    } else {
+        snap_conf.apps.clone()
+    }
+}
+
+/// Validate values written to `snap.yaml`.
+#[must_use]
+pub fn validate_snap(conf: &Config, snap_conf: &SnapConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let summary = summary(conf, snap_conf);
+    if summary.trim().is_empty() {
+        issues.push(Issue::error(
+            "summary",
+            "is empty, and `metadata.description` is also empty".to_string(),
+        ));
+    } else if summary.chars().count() > MAX_SUMMARY_LEN || summary.contains('\n') {
+        issues.push(Issue::error(
+            "summary",
+            format!(
+                "shall be a single line with at most {} characters",
+                MAX_SUMMARY_LEN
+            ),
+        ));
+    }
+
+    for (name, app) in &apps(conf, snap_conf) {
+        if !is_hyphenated(name, |c| c.is_ascii_alphanumeric()) {
+            issues.push(Issue::error(
+                "apps",
+                format!(
+                    "{:?} is invalid app name, it shall contain only letters, digits and single hyphens",
+                    name
+                ),
+            ));
+        }
+        if app.command.trim().is_empty() {
+            issues.push(Issue::error(
+                "apps",
+                format!("command of app {:?} is empty", name),
+            ));
+        }
+        for plug in &app.plugs {
+            if !is_hyphenated(plug, |c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+                issues.push(Issue::error(
+                    "apps",
+                    format!("plug {:?} of app {:?} is invalid", plug, name),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Quote `value` as double-quoted YAML scalar.
+fn quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Generate content of `meta/snap.yaml` for `arch`.
+pub fn snap_yaml(conf: &Config, snap_conf: &SnapConfig, arch: &str) -> Result<String, Error> {
+    let metadata = &conf.metadata;
+    let mut yaml = String::new();
+    writeln!(yaml, "name: {}", quote(&metadata.name))?;
+    writeln!(yaml, "version: {}", quote(&metadata.version))?;
+    writeln!(yaml, "summary: {}", quote(summary(conf, snap_conf).trim()))?;
+    writeln!(
+        yaml,
+        "description: {}",
+        quote(description(conf, snap_conf).trim())
+    )?;
+    if !metadata.license.is_empty() {
+        writeln!(yaml, "license: {}", quote(&metadata.license))?;
+    }
+    if let Some(base) = snap_conf.base.as_ref() {
+        writeln!(yaml, "base: {}", quote(base))?;
+    }
+    writeln!(yaml, "confinement: {}", snap_conf.confinement.name())?;
+    writeln!(yaml, "grade: {}", snap_conf.grade.name())?;
+    writeln!(yaml, "architectures: [{}]", arch)?;
+    writeln!(yaml, "apps:")?;
+    for (name, app) in &apps(conf, snap_conf) {
+        writeln!(yaml, "  {}:", name)?;
+        writeln!(
+            yaml,
+            "    command: {}",
+            quote(app.command.trim_start_matches('/'))
+        )?;
+        if !app.plugs.is_empty() {
+            writeln!(yaml, "    plugs: [{}]", app.plugs.join(", "))?;
+        }
+    }
+    Ok(yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::Metadata;
+
+    #[test]
+    fn test_validate_snap_name() {
+        assert!(validate_snap_name("pifu-hello").is_ok());
+        assert!(validate_snap_name("2048").is_err());
+        assert!(validate_snap_name("Hello").is_err());
+        assert!(validate_snap_name("hello--world").is_err());
+        assert!(validate_snap_version("1.0.0-rc1").is_ok());
+        assert!(validate_snap_version("1.0_beta").is_err());
+    }
+
+    #[test]
+    fn test_snap_yaml() {
+        let mut metadata = Metadata::new("hello", "1.0.0");
+        metadata.description = "Say \"hello\"".to_string();
+        let conf = Config::new(metadata);
+        let mut snap_conf = SnapConfig::default();
+        let yaml = snap_yaml(&conf, &snap_conf, "amd64").unwrap();
+        assert!(yaml.contains("name: \"hello\"\n"));
+        assert!(yaml.contains("summary: \"Say \\\"hello\\\"\"\n"));
+        assert!(yaml.contains("confinement: strict\n"));
+        assert!(yaml.contains("architectures: [amd64]\n"));
+        assert!(yaml.contains("  hello:\n    command: \"usr/bin/hello\"\n"));
+
+        snap_conf.apps.insert(
+            "hello-daemon".to_string(),
+            SnapApp {
+                command: "bin/hellod".to_string(),
+                plugs: vec!["network-bind".to_string()],
+            },
+        );
+        let yaml = snap_yaml(&conf, &snap_conf, "arm64").unwrap();
+        assert!(!yaml.contains("usr/bin/hello"));
+        assert!(yaml.contains("    plugs: [network-bind]\n"));
+        assert!(validate_snap(&conf, &snap_conf).is_empty());
+    }
+}
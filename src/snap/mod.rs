@@ -0,0 +1,12 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+mod build;
+mod config;
+mod metadata;
+
+pub use build::SnapPackager;
+pub use config::{Confinement, Grade, SnapApp, SnapConfig};
+pub use metadata::snap_arch;
+pub use metadata::{validate_snap, validate_snap_name, validate_snap_version};
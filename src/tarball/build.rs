@@ -0,0 +1,151 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::app_image::{copy_libraries, patch_rpath};
+use crate::base::fileset::copy_filesets;
+use crate::base::runner::Runner;
+use crate::base::utils;
+use crate::base::{expand_file_macro, Arch, PlatformTarget};
+use crate::build::BuildOptions;
+use crate::config::{Config, LinuxConfig};
+use crate::error::{Error, ErrorKind};
+use crate::packager::Packager;
+
+/// Packager of portable `.tar.gz` archive.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TarballPackager;
+
+impl Packager for TarballPackager {
+    fn target(&self) -> PlatformTarget {
+        PlatformTarget::Tarball
+    }
+
+    fn build(
+        &self,
+        conf: &Config,
+        arch: Arch,
+        options: &BuildOptions,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let linux_conf = conf.linux_conf()?;
+        let runner = options.runner(conf, &self.name(), arch)?;
+        build_tarball(conf, linux_conf, arch, &runner)
+    }
+}
+
+pub fn build_tarball(
+    conf: &Config,
+    linux_conf: &LinuxConfig,
+    arch: Arch,
+    runner: &Runner,
+) -> Result<Vec<PathBuf>, Error> {
+    let tarball_conf = &linux_conf.tarball;
+
+    let files = if let Some(files) = tarball_conf.files.as_ref() {
+        files
+    } else if let Some(files) = linux_conf.files.as_ref() {
+        files
+    } else {
+        return Err(Error::new(
+            ErrorKind::FilesNotSet,
+            "`files` property not set for tarball format",
+        ));
+    };
+
+    let artifact_name = expand_file_macro(
+        &tarball_conf.artifact_name,
+        conf,
+        arch,
+        PlatformTarget::Tarball,
+    )?;
+    let ext = format!(".{}", PlatformTarget::Tarball.extension());
+    let dir_name = artifact_name
+        .strip_suffix(&ext)
+        .unwrap_or(&artifact_name)
+        .to_string();
+
+    let workdir = Path::new(&conf.metadata.workdir);
+    let tarball_dir = workdir.join("tarball");
+    utils::rmdir(&tarball_dir)?;
+    let app_dir = tarball_dir.join(&dir_name);
+    fs::create_dir_all(&app_dir)?;
+
+    copy_filesets(files, conf, arch, PlatformTarget::Tarball, &app_dir)?;
+
+    if !tarball_conf.exe_files.is_empty() {
+        let libs_dir = app_dir.join("libs");
+        fs::create_dir_all(&libs_dir)?;
+        let elf_files = tarball_conf
+            .exe_files
+            .iter()
+            .map(|exe_file| {
+                expand_file_macro(exe_file, conf, arch, PlatformTarget::Tarball).map(PathBuf::from)
+            })
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+        copy_libraries(&elf_files, &tarball_conf.exclude_libs, &libs_dir, runner)?;
+        if tarball_conf.patch_rpath {
+            patch_rpath(&app_dir, &libs_dir)?;
+        }
+    }
+
+    let artifact = workdir.join(&artifact_name);
+    create_tarball(&app_dir, &dir_name, &artifact)?;
+    Ok(vec![artifact])
+}
+
+/// Create gzipped tar archive of `dir`, with top level directory `name`.
+///
+/// Owner and timestamp of entries are reset, permissions are either 0755 or 0644.
+fn create_tarball(dir: &Path, name: &str, to: &Path) -> Result<(), Error> {
+    log::info!("create_tarball() dir: {:?}, to: {:?}", dir, to);
+    let encoder = GzEncoder::new(File::create(to)?, Compression::best());
+    let mut builder = tar::Builder::new(encoder);
+    builder.mode(tar::HeaderMode::Deterministic);
+    builder.follow_symlinks(false);
+    builder.append_dir_all(name, dir)?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+
+    #[test]
+    fn test_create_tarball() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let app_dir = tmp_dir.path().join("hello-1.0.0");
+        fs::create_dir_all(app_dir.join("libs")).unwrap();
+        fs::write(app_dir.join("hello"), "#!/bin/sh\n").unwrap();
+        fs::write(app_dir.join("libs").join("libfoo.so.1"), "").unwrap();
+        let artifact = tmp_dir.path().join("hello.tar.gz");
+        create_tarball(&app_dir, "hello-1.0.0", &artifact).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&artifact).unwrap()));
+        let mut names = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                assert_eq!(entry.header().uid().unwrap(), 0);
+                entry.path().unwrap().to_string_lossy().to_string()
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "hello-1.0.0/",
+                "hello-1.0.0/hello",
+                "hello-1.0.0/libs",
+                "hello-1.0.0/libs/libfoo.so.1"
+            ]
+        );
+    }
+}
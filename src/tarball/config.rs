@@ -0,0 +1,55 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::app_image::default_exclude_libs;
+use crate::base::fileset::FileSet;
+use crate::base::utils::default_true;
+
+/// Portable gzipped tarball, which is extracted and run in place.
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TarballConfig {
+    /// File list, paths are relative to top level directory of tarball.
+    pub files: Option<Vec<FileSet>>,
+
+    /// A list of elf executable files. If they are dynamically linked, dependent
+    /// libraries will be copied to `libs` folder.
+    #[serde(default)]
+    pub exe_files: Vec<String>,
+
+    #[serde(default = "default_exclude_libs")]
+    pub exclude_libs: Vec<String>,
+
+    /// Boolean - whether to set `DT_RUNPATH` of ELF files in tarball to `libs`
+    /// relative to `$ORIGIN`, so that copied libraries are found without
+    /// `LD_LIBRARY_PATH`.
+    #[serde(default = "default_true")]
+    pub patch_rpath: bool,
+
+    /// String - The artifact file name template.
+    ///
+    /// Default is `${name}-${version}-${arch}.${ext}`, the top level directory
+    /// in tarball has the same name without extension.
+    #[serde(default = "default_artifact_name")]
+    pub artifact_name: String,
+}
+
+impl Default for TarballConfig {
+    fn default() -> Self {
+        Self {
+            files: None,
+            exe_files: Vec::new(),
+            exclude_libs: default_exclude_libs(),
+            patch_rpath: true,
+            artifact_name: default_artifact_name(),
+        }
+    }
+}
+
+fn default_artifact_name() -> String {
+    "${name}-${version}-${arch}.${ext}".to_string()
+}
@@ -0,0 +1,50 @@
+// Copyright (c) 2021 Xu Shaohua <shaohua@biofan.org>. All rights reserved.
+// Use of this source is governed by General Public License that can be found
+// in the LICENSE file.
+
+//! Build a package with library API, as an external crate does.
+
+use std::fs;
+
+use pifu::base::Metadata;
+use pifu::{
+    Arch, BuildOptions, Config, FileSet, LinuxConfig, Packager, PlatformTarget, TarballConfig,
+    TarballPackager,
+};
+
+#[test]
+fn test_build_tarball() {
+    let dir = tempfile::tempdir().unwrap();
+    let src_dir = dir.path().join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("hello.sh"), "#!/bin/sh\necho hello\n").unwrap();
+
+    let mut metadata = Metadata::new("hello", "1.0.0");
+    metadata.workdir = dir.path().join("pifu").to_string_lossy().to_string();
+    metadata.src_dir = src_dir.to_string_lossy().to_string();
+    let linux = LinuxConfig {
+        targets: vec![PlatformTarget::Tarball],
+        tarball: TarballConfig {
+            files: Some(vec![FileSet {
+                from: "hello.sh".to_string(),
+                to: "bin/hello.sh".to_string(),
+                filter: None,
+                mode: None,
+                arch: None,
+                targets: None,
+            }]),
+            ..TarballConfig::default()
+        },
+        ..LinuxConfig::default()
+    };
+    let conf = Config::new(metadata).with_linux(linux);
+
+    let packager = TarballPackager;
+    assert_eq!(packager.target(), PlatformTarget::Tarball);
+    let files = packager
+        .build(&conf, Arch::X86_64, &BuildOptions::default())
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(files[0].ends_with("hello-1.0.0-x86_64.tar.gz"));
+    assert!(files[0].is_file());
+}
@@ -0,0 +1 @@
+int hello(int value) { return value + 1; }